@@ -20,6 +20,7 @@ impl InputConst {
             (Self::MOVE_BACK, EngineInputs::MoveBack),
             (Self::MOVE_LEFT, EngineInputs::MoveLeft),
             (Self::MOVE_RIGHT, EngineInputs::MoveRight),
+            (Self::DODGE, EngineInputs::Dodge),
         ];
     }
 }
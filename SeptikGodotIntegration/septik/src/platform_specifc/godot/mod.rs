@@ -59,15 +59,13 @@ impl GameEngine {
 
         self.player_inputs.append(&mut input_handler::input_poll());
 
-        if self.player_inputs.is_empty() == false {
-            self.executed_frames += 1;
-            godot_print!("f: {}", self.executed_frames);
+        // Always dispatch, so the simulation keeps running when there's no input
+        self.executed_frames += 1;
 
-            self.world.register_player_inputs(&self.player_inputs);
-            self.world.dispatch();
+        self.world.register_player_inputs(&self.player_inputs);
+        self.world.dispatch();
 
-            self.player_inputs.clear();
-        }
+        self.player_inputs.clear();
 
         self.link_to_gd_nodes(owner);
     }
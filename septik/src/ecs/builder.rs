@@ -0,0 +1,244 @@
+use super::{systems, Clock, Scheduler, SystemClock, SystemDescriptor, World};
+use crate::lib_core::math::Rng;
+
+use time::Duration;
+
+const NANOSECONDS_IN_SECOND: i64 = 1_000_000_000;
+
+/// Sets of entities a world can start with
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scenario {
+    /// No entities, such as for tests and servers
+    Empty,
+    /// A player, an enemy and a voxel chunk
+    Demo,
+}
+
+impl Scenario {
+    /// Spawn the scenario's entities
+    fn setup(&self, world: &mut World) -> Result<(), String> {
+        match self {
+            Scenario::Empty => {}
+            Scenario::Demo => {
+                world.spawn_prefab("player")?;
+                world.spawn_prefab("basic_enemy")?;
+                world.spawn_prefab("basic_voxel_chunk")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Configures and creates a `World`. By default the world is empty, runs the engine systems at 60 ticks per second and follows real time.
+pub struct WorldBuilder {
+    tick_rate: u32,
+    capacity: usize,
+    clock: Option<Box<dyn Clock + Send>>,
+    seed: u64,
+    engine_systems: bool,
+    systems: Vec<SystemDescriptor>,
+    scenario: Scenario,
+}
+
+impl WorldBuilder {
+    pub const DEFAULT_TICK_RATE: u32 = 60;
+
+    pub fn new() -> Self {
+        return Self {
+            tick_rate: Self::DEFAULT_TICK_RATE,
+            capacity: 0,
+            clock: None,
+            seed: World::DEFAULT_SEED,
+            engine_systems: true,
+            systems: vec![],
+            scenario: Scenario::Empty,
+        };
+    }
+
+    /// The number of ticks to run per second
+    pub fn tick_rate(mut self, ticks_per_second: u32) -> Self {
+        self.tick_rate = ticks_per_second;
+        self
+    }
+
+    /// The number of entities to allocate room for up front
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// The clock that drives the simulation, such as a `ManualClock` for tests
+    pub fn clock(mut self, clock: Box<dyn Clock + Send>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// The seed for the world's random number generator
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Don't register the engine's systems, leaving only the ones added with `system`
+    pub fn without_engine_systems(mut self) -> Self {
+        self.engine_systems = false;
+        self
+    }
+
+    /// Register an additional system
+    pub fn system(mut self, descriptor: SystemDescriptor) -> Self {
+        self.systems.push(descriptor);
+        self
+    }
+
+    /// The entities the world starts with
+    pub fn scenario(mut self, scenario: Scenario) -> Self {
+        self.scenario = scenario;
+        self
+    }
+
+    /// Create the world. Fails if the tick rate is zero, the systems can't be ordered or the scenario can't be set up.
+    pub fn build(self) -> Result<World, String> {
+        if self.tick_rate == 0 {
+            return Err(String::from("Tick rate must be greater than zero"));
+        }
+
+        let clock = match self.clock {
+            Some(clock) => clock,
+            None => Box::new(SystemClock::new()),
+        };
+
+        let mut scheduler = Scheduler::new();
+        if self.engine_systems {
+            systems::register_systems(&mut scheduler)?;
+        }
+
+        for descriptor in self.systems {
+            scheduler.add_system(descriptor)?;
+        }
+
+        let mut world = World::with_storages(
+            Duration::nanoseconds(NANOSECONDS_IN_SECOND / self.tick_rate as i64),
+            clock,
+            scheduler,
+            Rng::new(self.seed),
+            self.capacity,
+        );
+
+        world.load_default_prefabs();
+        self.scenario.setup(&mut world)?;
+
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::{ManualClock, Stage};
+
+    #[test]
+    fn WorldBuilder_defaults_to_empty() {
+        let world = WorldBuilder::new().build().unwrap();
+
+        assert!(world.entities().is_empty());
+        assert_eq!(0, world.tick());
+        assert_eq!(Duration::nanoseconds(16_666_666), world.frame_duration());
+        assert_eq!(
+            vec![
+                "character_action",
+                "dodge",
+                "facing_update",
+                "ailment_update",
+                "invulnerability_update",
+                "attack_update",
+                "overwatch",
+                "position_update",
+                "world_transform_update",
+                "death",
+                "input_cleanup"
+            ],
+            world.system_order()
+        );
+    }
+
+    #[test]
+    fn WorldBuilder_demo_scenario() {
+        let world = WorldBuilder::new()
+            .scenario(Scenario::Demo)
+            .build()
+            .unwrap();
+
+        assert_eq!(3, world.entities().len());
+        assert_eq!(World::new().checksum(), world.checksum());
+    }
+
+    #[test]
+    fn WorldBuilder_tick_rate_and_clock() {
+        let clock = ManualClock::new();
+        let mut world = WorldBuilder::new()
+            .tick_rate(20)
+            .clock(Box::new(clock.clone()))
+            .build()
+            .unwrap();
+
+        assert_eq!(Duration::milliseconds(50), world.frame_duration());
+
+        clock.advance(Duration::milliseconds(120));
+        assert_eq!(2, world.dispatch());
+
+        assert!(WorldBuilder::new().tick_rate(0).build().is_err());
+    }
+
+    #[test]
+    fn WorldBuilder_seed() {
+        let mut a = WorldBuilder::new().seed(1).build().unwrap();
+        let mut b = WorldBuilder::new().seed(1).build().unwrap();
+        let mut c = WorldBuilder::new().seed(2).build().unwrap();
+
+        let roll = a.rng.next_u32();
+
+        assert_eq!(roll, b.rng.next_u32());
+        assert_ne!(roll, c.rng.next_u32());
+    }
+
+    #[test]
+    fn WorldBuilder_systems() {
+        fn spawn(world: &mut World) {
+            world.add_entity();
+        }
+
+        let mut world = WorldBuilder::new()
+            .without_engine_systems()
+            .system(SystemDescriptor::new("spawn", Stage::Logic, spawn))
+            .build()
+            .unwrap();
+
+        assert_eq!(vec!["spawn"], world.system_order());
+
+        world.step();
+        assert_eq!(1, world.entities().len());
+
+        let duplicate = WorldBuilder::new()
+            .system(SystemDescriptor::new(
+                "position_update",
+                Stage::Logic,
+                spawn,
+            ))
+            .build();
+        assert!(duplicate.is_err());
+    }
+
+    #[test]
+    fn WorldBuilder_capacity() {
+        let mut world = WorldBuilder::new().capacity(1000).build().unwrap();
+
+        for _ in 0..1000 {
+            let e = world.add_entity();
+            world.transforms[e] = Some(crate::ecs::components::TransformComponent::new());
+        }
+
+        assert_eq!(1000, world.entities().len());
+    }
+}
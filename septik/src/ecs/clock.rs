@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use time::{Duration, Instant};
+
+/// Source of time for the simulation. Injected so that tests can control time.
+pub trait Clock {
+    /// The time elapsed since the clock started
+    fn now(&self) -> Duration;
+}
+
+/// Clock that follows real time.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        return Self {
+            start: Instant::now(),
+        };
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        Instant::now() - self.start
+    }
+}
+
+/// Clock that only moves when advanced. Clones share the same time, so a copy can be kept to drive a world that owns the clock, even from another thread.
+#[derive(Clone)]
+pub struct ManualClock {
+    nanoseconds: Arc<AtomicI64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        return Self {
+            nanoseconds: Arc::new(AtomicI64::new(0)),
+        };
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        self.nanoseconds
+            .fetch_add(duration.whole_nanoseconds() as i64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::nanoseconds(self.nanoseconds.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ManualClock_advance() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+
+        assert_eq!(Duration::zero(), clock.now());
+
+        shared.advance(Duration::milliseconds(5));
+        shared.advance(Duration::milliseconds(10));
+
+        assert_eq!(Duration::milliseconds(15), clock.now());
+    }
+
+    #[test]
+    fn ManualClock_advance_from_another_thread() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+
+        std::thread::spawn(move || shared.advance(Duration::milliseconds(5)))
+            .join()
+            .unwrap();
+
+        assert_eq!(Duration::milliseconds(5), clock.now());
+    }
+
+    #[test]
+    fn SystemClock_moves_forward() {
+        let clock = SystemClock::new();
+        let start = clock.now();
+
+        assert!(clock.now() >= start);
+    }
+}
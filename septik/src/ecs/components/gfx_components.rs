@@ -1,9 +1,9 @@
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
 pub enum Mesh {
     Monkey,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Hash)]
 pub struct MeshComponent {
     pub mesh: Mesh,
 }
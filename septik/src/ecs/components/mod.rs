@@ -1,6 +1,6 @@
 use crate::ecs::Entity;
 use crate::lib_core::{
-    math::{FixedNumber, Range, Rotation3d, Vec3d},
+    math::{FixedNumber, Meter, Range, Rotation3d, Vec3d},
     voxels::voxel_chunk::VoxelChunk,
     Aabb, Direction, InputType,
 };
@@ -8,7 +8,7 @@ use crate::lib_core::{
 pub mod gfx_components;
 pub mod unit_components;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Hash)]
 pub struct ThirdPersonCameraComponent {
     pub relative_position: Vec3d,
     pub default_relative_position: Vec3d,
@@ -26,7 +26,7 @@ impl ThirdPersonCameraComponent {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Hash)]
 pub struct VoxelChunkComponent {
     pub chunk: VoxelChunk,
 }
@@ -39,12 +39,12 @@ impl VoxelChunkComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct TargetComponent {
     pub entity: Entity,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct AabbComponent {
     pub aabb: Aabb,
 }
@@ -53,9 +53,14 @@ impl AabbComponent {
     pub fn new(aabb: Aabb) -> Self {
         Self { aabb: aabb }
     }
+
+    /// Retrieve the aabb in world space, by moving it to the transform's position
+    pub fn world_aabb(&self, transform: &TransformComponent) -> Aabb {
+        self.aabb.translate(transform.position)
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct AiComponent {}
 
 impl AiComponent {
@@ -64,7 +69,7 @@ impl AiComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct EnemyComponent {}
 
 impl EnemyComponent {
@@ -73,10 +78,10 @@ impl EnemyComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct TargetableComponent {}
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct GdNodeComponent {
     pub id: i64,
 }
@@ -87,7 +92,7 @@ impl GdNodeComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct MoveSpeedComponent {
     pub value: FixedNumber,
 }
@@ -98,13 +103,314 @@ impl MoveSpeedComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct HitPointComponent {
-    pub value: u32,
-    pub max_value: u32,
+/// A character's health, dead when empty
+pub type HitPointComponent = Meter;
+
+/// The kinds of damage that can be dealt, so characters can resist some more than others
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub enum DamageType {
+    Physical,
+    Bleed,
+    Poison,
+}
+
+impl DamageType {
+    pub const size: usize = 3;
+
+    pub fn index(&self) -> usize {
+        let index = match self {
+            DamageType::Physical => 0,
+            DamageType::Bleed => 1,
+            DamageType::Poison => 2,
+        };
+
+        return index;
+    }
+}
+
+/// How much of each type of damage is ignored, as a percentage
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct ResistancesComponent {
+    pub percentages: [u8; DamageType::size],
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl ResistancesComponent {
+    pub fn new() -> Self {
+        return Self {
+            percentages: [0; DamageType::size],
+        };
+    }
+
+    /// The resistance to the damage type, from 0 to 100
+    pub fn get(&self, damage_type: DamageType) -> u8 {
+        self.percentages[damage_type.index()].min(100)
+    }
+
+    pub fn set(&mut self, damage_type: DamageType, percentage: u8) {
+        self.percentages[damage_type.index()] = percentage.min(100);
+    }
+}
+
+/// Ticks where the character can't be damaged, such as after being hit or while dodging
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct InvulnerabilityComponent {
+    /// The number of ticks left before the character can be damaged again
+    pub remaining_ticks: u32,
+    /// The number of invulnerable ticks given after taking physical damage
+    pub ticks_on_hit: u32,
+}
+
+impl InvulnerabilityComponent {
+    pub fn new(ticks_on_hit: u32) -> Self {
+        return Self {
+            remaining_ticks: 0,
+            ticks_on_hit: ticks_on_hit,
+        };
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.remaining_ticks > 0
+    }
+}
+
+/// A resource spent on actions such as dodging, which regenerates over time
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct StaminaComponent {
+    pub meter: Meter,
+    /// Stamina regained each tick
+    pub regen_per_tick: u32,
+}
+
+impl StaminaComponent {
+    pub fn new(max_value: u32, regen_per_tick: u32) -> Self {
+        return Self {
+            meter: Meter::new(max_value),
+            regen_per_tick: regen_per_tick,
+        };
+    }
+}
+
+/// A dodge in progress. Kept until the cooldown is over, so the character can't dodge again until it's removed.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct DodgeComponent {
+    pub direction: Direction,
+    /// Ticks left of the velocity burst
+    pub remaining_ticks: u32,
+    /// Ticks left before the character can dodge again
+    pub cooldown_ticks: u32,
+}
+
+/// The melee attacks a character can make
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub enum AttackType {
+    /// A wide sweep in front of the character
+    Horizontal,
+    /// A narrow, slower overhead strike with more reach
+    Vertical,
+}
+
+/// Where a character is in an attack
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AttackPhase {
+    Startup,
+    Active,
+    Recovery,
+}
+
+/// A melee attack in progress. Removed once the recovery frames are over.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct AttackComponent {
+    pub attack_type: AttackType,
+    /// Ticks since the attack started
+    pub elapsed: u32,
+    pub startup_frames: u32,
+    pub active_frames: u32,
+    pub recovery_frames: u32,
+    /// Entities already hit, so each is only hit once per attack
+    pub hit: Vec<Entity>,
+    /// The hitbox spawned during the active frames
+    pub hitbox: Option<Entity>,
+}
+
+impl AttackComponent {
+    pub fn new(
+        attack_type: AttackType,
+        startup_frames: u32,
+        active_frames: u32,
+        recovery_frames: u32,
+    ) -> Self {
+        return Self {
+            attack_type: attack_type,
+            elapsed: 0,
+            startup_frames: startup_frames,
+            active_frames: active_frames,
+            recovery_frames: recovery_frames,
+            hit: vec![],
+            hitbox: None,
+        };
+    }
+
+    /// The phase for the current tick, or None if the attack is over
+    pub fn phase(&self) -> Option<AttackPhase> {
+        if self.elapsed < self.startup_frames {
+            Some(AttackPhase::Startup)
+        } else if self.elapsed < self.startup_frames + self.active_frames {
+            Some(AttackPhase::Active)
+        } else if self.elapsed < self.total_frames() {
+            Some(AttackPhase::Recovery)
+        } else {
+            None
+        }
+    }
+
+    pub fn total_frames(&self) -> u32 {
+        self.startup_frames + self.active_frames + self.recovery_frames
+    }
+}
+
+/// A transient box that hits targetable entities for its owner's attack. Its aabb is relative to the owner's position.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct HitboxComponent {
+    pub owner: Entity,
+}
+
+/// The space a unit on overwatch is watching
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub enum OverwatchArea {
+    /// A 90 degree wedge in front of the watcher, out to the range
+    Cone {
+        direction: Direction,
+        range: FixedNumber,
+    },
+    /// A circle around a spot, wherever the watcher is
+    Area { center: Vec3d, radius: FixedNumber },
+}
+
+impl OverwatchArea {
+    /// Whether the point is inside the area, ignoring the y axis
+    pub fn contains(&self, watcher_position: Vec3d, point: Vec3d) -> bool {
+        match self {
+            OverwatchArea::Cone { direction, range } => {
+                let mut offset = point - watcher_position;
+                offset.y = 0.into();
+
+                if offset.length() > *range {
+                    return false;
+                }
+
+                if offset == Vec3d::default() {
+                    return true;
+                }
+
+                // cos(45 degrees)
+                let min_alignment = FixedNumber::from_bits(2896);
+
+                offset.normalize().dot(direction.to_vec3d()) >= min_alignment
+            }
+            OverwatchArea::Area { center, radius } => {
+                let mut offset = point - *center;
+                offset.y = 0.into();
+
+                offset.length() <= *radius
+            }
+        }
+    }
+
+    /// Whether a move from start to end passes through the area at any point, ignoring the y axis
+    pub fn crossed_by(&self, watcher_position: Vec3d, start: Vec3d, end: Vec3d) -> bool {
+        if self.contains(watcher_position, start) || self.contains(watcher_position, end) {
+            return true;
+        }
+
+        match self {
+            OverwatchArea::Cone { direction, range } => {
+                // The cone is convex, so a move through it either crosses one of the straight edges,
+                // or enters and leaves through the arc, passing closest to the watcher while inside
+                let closest = closest_point_on_segment(start, end, watcher_position);
+                if self.contains(watcher_position, closest) {
+                    return true;
+                }
+
+                [
+                    direction.rotate_clockwise(),
+                    direction.rotate_counter_clockwise(),
+                ]
+                .iter()
+                .any(|edge| {
+                    let edge_end = watcher_position + edge.to_vec3d().multiply(*range);
+
+                    segments_cross(start, end, watcher_position, edge_end)
+                })
+            }
+            OverwatchArea::Area { center, .. } => self.contains(
+                watcher_position,
+                closest_point_on_segment(start, end, *center),
+            ),
+        }
+    }
+}
+
+/// The point between start and end closest to the given point, ignoring the y axis
+fn closest_point_on_segment(start: Vec3d, end: Vec3d, point: Vec3d) -> Vec3d {
+    let mut segment = end - start;
+    segment.y = 0.into();
+    let mut offset = point - start;
+    offset.y = 0.into();
+
+    let length_squared = segment.length_squared();
+    if length_squared == 0.into() {
+        return start;
+    }
+
+    let t = FixedNumber::clamp(offset.dot(segment) / length_squared, 0.into(), 1.into());
+
+    start + (end - start).multiply(t)
+}
+
+/// Whether two segments touch or cross, ignoring the y axis
+fn segments_cross(a_start: Vec3d, a_end: Vec3d, b_start: Vec3d, b_end: Vec3d) -> bool {
+    // Which side of the line through start and end the point is on, in i128 so the products can't overflow
+    let side = |start: Vec3d, end: Vec3d, point: Vec3d| {
+        let bits = |n: FixedNumber| n.to_bits() as i128;
+
+        let cross = bits(end.x - start.x) * bits(point.z - start.z)
+            - bits(end.z - start.z) * bits(point.x - start.x);
+
+        cross.signum()
+    };
+
+    side(a_start, a_end, b_start) * side(a_start, a_end, b_end) <= 0
+        && side(b_start, b_end, a_start) * side(b_start, b_end, a_end) <= 0
+}
+
+/// A unit waiting to fire at the first hostile unit to move inside its area. Removed once it fires.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct OverwatchComponent {
+    pub area: OverwatchArea,
+    /// The number of shots fired when triggered
+    pub shots: u8,
+    /// The strength of each shot
+    pub strength: u8,
+}
+
+impl OverwatchComponent {
+    pub fn new(area: OverwatchArea, shots: u8, strength: u8) -> Self {
+        return Self {
+            area: area,
+            shots: shots,
+            strength: strength,
+        };
+    }
+}
+
+/// Marks a character whose hit points have run out
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct DeadComponent {
+    /// The entity that dealt the killing blow, if any
+    pub killer: Option<Entity>,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct VelocityComponent {
     pub value: Vec3d,
     pub rotational_velocity: Rotation3d,
@@ -119,7 +425,7 @@ impl VelocityComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct TransformComponent {
     pub position: Vec3d,
     pub rotation: Rotation3d,
@@ -132,9 +438,27 @@ impl TransformComponent {
             rotation: Rotation3d::default(),
         };
     }
+
+    /// Treat this as a local transform and convert it to the parent's space
+    pub fn relative_to(&self, parent: &TransformComponent) -> Self {
+        let position = parent.position + parent.rotation.apply_to_vec3d(self.position);
+
+        let rotation = if parent.rotation == Rotation3d::default() {
+            self.rotation
+        } else {
+            Rotation3d::from_quaternion(
+                parent.rotation.to_quaternion() * self.rotation.to_quaternion(),
+            )
+        };
+
+        return Self {
+            position: position,
+            rotation: rotation,
+        };
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct PlayerComponent {}
 
 impl PlayerComponent {
@@ -143,7 +467,7 @@ impl PlayerComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct FacingComponent {
     pub direction: Direction,
 }
@@ -156,7 +480,7 @@ impl FacingComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct EngineInputsComponent {
     pub inputs: Vec<InputType>,
 }
@@ -167,12 +491,46 @@ impl EngineInputsComponent {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct AilmentsComponent {
     pub ailments: Vec<Ailment>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl AilmentsComponent {
+    pub fn new() -> Self {
+        return Self { ailments: vec![] };
+    }
+
+    /// Fill the ailment's meter, adding the ailment if the character doesn't have it yet.
+    /// Build-up is ignored while the ailment is active, so lasting effects can't be stacked.
+    pub fn build_up(&mut self, ailment: AilmentTypes, amount: u8) {
+        let index = match self
+            .ailments
+            .iter()
+            .position(|a| a.modifier_type == ailment)
+        {
+            Some(index) => index,
+            None => {
+                self.ailments.push(Ailment::new(ailment));
+                self.ailments.len() - 1
+            }
+        };
+
+        let ailment = &mut self.ailments[index];
+        if !ailment.active {
+            ailment.status_meter.saturating_add(amount);
+        }
+    }
+
+    /// Whether the ailment has been triggered and is still taking effect
+    pub fn is_active(&self, ailment: AilmentTypes) -> bool {
+        self.ailments
+            .iter()
+            .any(|a| a.modifier_type == ailment && a.active)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
 /// Information related to an ailment a character is inflicted with.
 pub struct Ailment {
     /// The type of the modifier
@@ -181,9 +539,23 @@ pub struct Ailment {
     pub modifier_multiplier: u8,
     /// The current meter of the multiplier. Causes the effect to be triggered when full.
     pub status_meter: Range,
+    /// Whether the ailment has been triggered and is draining. Only lasting ailments, such as Poison and Sloth, stay active.
+    pub active: bool,
+}
+
+impl Ailment {
+    /// An ailment with an empty meter and a multiplier of 1
+    pub fn new(modifier_type: AilmentTypes) -> Self {
+        return Self {
+            modifier_type: modifier_type,
+            modifier_multiplier: 1,
+            status_meter: Range::empty(),
+            active: false,
+        };
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 /// Various types of ailments that can be inflicted upon characters. For now, keep small until more things need to be added.
 pub enum AilmentTypes {
     /// A modifier which when triggered, causes a single hp loss event and resets the meter to 0.
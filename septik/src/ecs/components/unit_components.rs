@@ -1,6 +1,11 @@
+use super::HitPointComponent;
 use crate::lib_core::math::{FixedNumber, Range, Rotation3d, Vec3d};
 
+/// Hit points each wound is worth. Wounds that get through are dealt as this much physical damage.
+pub const HIT_POINTS_PER_WOUND: u32 = 10;
+
 /// Base struct for unit data
+#[derive(Clone, Debug, PartialEq, Hash)]
 pub struct UnitComponent {
     /// The modifier for melee attack hits
     pub weapon_skill: u8,
@@ -10,11 +15,11 @@ pub struct UnitComponent {
     pub melee_str: u8,
     /// The modifier for calculating the number to trigger a wound
     pub toughness: u8,
-    /// The remaining wounds a character has left
+    /// The wounds a character starts with. Wounds are taken from its hit points, `HIT_POINTS_PER_WOUND` each.
     pub wounds: u8,
     /// The order that the unit executes their actions
     pub initiative: u8,
-    /// When a wound is triggered, use this stat to determine whether the wound actually hits
+    /// The d6 roll needed to ignore a wound, such as 4 for a 4+ save. 0 means the unit has no save, and lower is better otherwise
     pub armor_save: u8,
     /// The base number of melee attacks a character has
     pub melee_atks: u8,
@@ -32,9 +37,19 @@ impl UnitComponent {
             toughness: 1,
             wounds: 1,
             initiative: 1,
-            armor_save: 1,
+            armor_save: 0,
             melee_atks: 1,
             base_size: 1,
         };
     }
+
+    /// Full hit points for the unit's wounds
+    pub fn hitpoints(&self) -> HitPointComponent {
+        let max_value = self.wounds as u32 * HIT_POINTS_PER_WOUND;
+
+        return HitPointComponent {
+            value: max_value,
+            max_value: max_value,
+        };
+    }
 }
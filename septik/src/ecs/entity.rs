@@ -0,0 +1,215 @@
+use crate::lib_core::serialization::{SaveReader, SaveWriter, Serializable};
+
+/// A handle to an entity. The generation changes each time the index is recycled, so handles to deleted entities can be detected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: usize,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn new(index: usize, generation: u32) -> Self {
+        return Self {
+            index: index,
+            generation: generation,
+        };
+    }
+
+    /// The slot in each storage for this entity
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Allocates entities, recycling the indexes of deleted entities.
+#[derive(Clone, Hash)]
+pub struct EntityAllocator {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free_indexes: Vec<usize>,
+}
+
+impl EntityAllocator {
+    pub fn new() -> Self {
+        return Self {
+            generations: vec![],
+            alive: vec![],
+            free_indexes: vec![],
+        };
+    }
+
+    /// Create an allocator with room for `capacity` entities before it has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        return Self {
+            generations: Vec::with_capacity(capacity),
+            alive: Vec::with_capacity(capacity),
+            free_indexes: vec![],
+        };
+    }
+
+    /// Create a new entity, reusing a free index if there is one
+    pub fn allocate(&mut self) -> Entity {
+        match self.free_indexes.pop() {
+            Some(index) => {
+                self.alive[index] = true;
+
+                Entity::new(index, self.generations[index])
+            }
+            None => {
+                let index = self.generations.len();
+
+                self.generations.push(0);
+                self.alive.push(true);
+
+                Entity::new(index, 0)
+            }
+        }
+    }
+
+    /// Free the entity so its index can be reused. Returns false if the entity was already dead.
+    pub fn free(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let index = entity.index;
+
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free_indexes.push(index);
+
+        true
+    }
+
+    /// Returns true if the entity has not been freed
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        entity.index < self.alive.len()
+            && self.alive[entity.index]
+            && self.generations[entity.index] == entity.generation
+    }
+
+    /// Retrieve all living entities
+    pub fn alive(&self) -> Vec<Entity> {
+        let mut entities = Vec::with_capacity(self.generations.len());
+
+        for index in 0..self.generations.len() {
+            if self.alive[index] {
+                entities.push(Entity::new(index, self.generations[index]));
+            }
+        }
+
+        entities
+    }
+
+    /// The number of living entities
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free_indexes.len()
+    }
+}
+
+impl Serializable for Entity {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.index);
+        writer.write(&self.generation);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        Ok(Self::new(reader.read()?, reader.read()?))
+    }
+}
+
+impl Serializable for EntityAllocator {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.generations);
+        writer.write(&self.alive);
+        writer.write(&self.free_indexes);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let allocator = Self {
+            generations: reader.read()?,
+            alive: reader.read()?,
+            free_indexes: reader.read()?,
+        };
+
+        if allocator.alive.len() != allocator.generations.len() {
+            return Err(String::from("Entity allocator is corrupt"));
+        }
+
+        for index in allocator.free_indexes.iter() {
+            if *index >= allocator.alive.len() || allocator.alive[*index] {
+                return Err(String::from("Entity allocator is corrupt"));
+            }
+        }
+
+        Ok(allocator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn EntityAllocator_allocate() {
+        let mut allocator = EntityAllocator::new();
+
+        let a = allocator.allocate();
+        let b = allocator.allocate();
+
+        assert_eq!(Entity::new(0, 0), a);
+        assert_eq!(Entity::new(1, 0), b);
+        assert_eq!(vec![a, b], allocator.alive());
+        assert_eq!(2, allocator.len());
+    }
+
+    #[test]
+    fn EntityAllocator_free_recycles_index() {
+        let mut allocator = EntityAllocator::new();
+
+        let a = allocator.allocate();
+        let b = allocator.allocate();
+
+        assert_eq!(true, allocator.free(a));
+        assert_eq!(vec![b], allocator.alive());
+
+        let c = allocator.allocate();
+
+        assert_eq!(a.index(), c.index());
+        assert_eq!(1, c.generation());
+        assert_eq!(vec![c, b], allocator.alive());
+    }
+
+    #[test]
+    fn EntityAllocator_stale_entity_is_dead() {
+        let mut allocator = EntityAllocator::new();
+
+        let a = allocator.allocate();
+        allocator.free(a);
+        let b = allocator.allocate();
+
+        assert_eq!(false, allocator.is_alive(a));
+        assert_eq!(true, allocator.is_alive(b));
+        assert_eq!(false, allocator.is_alive(Entity::new(10, 0)));
+    }
+
+    #[test]
+    fn EntityAllocator_double_free() {
+        let mut allocator = EntityAllocator::new();
+
+        let a = allocator.allocate();
+
+        assert_eq!(true, allocator.free(a));
+        assert_eq!(false, allocator.free(a));
+        assert_eq!(0, allocator.len());
+
+        // Only one slot should have been freed
+        let b = allocator.allocate();
+        let c = allocator.allocate();
+        assert_ne!(b.index(), c.index());
+    }
+}
@@ -0,0 +1,155 @@
+use super::{
+    components::{AilmentTypes, DamageType},
+    Entity,
+};
+use crate::lib_core::{
+    serialization::{SaveReader, SaveWriter, Serializable},
+    EngineInputs,
+};
+
+/// Double buffered event queue. Events published during a tick are readable for the whole of the next tick, then dropped.
+/// This makes delivery independent of the order systems run in, at the cost of one tick of latency.
+#[derive(Clone, Hash)]
+pub struct EventChannel<T> {
+    readable: Vec<T>,
+    published: Vec<T>,
+}
+
+impl<T> EventChannel<T> {
+    pub fn new() -> Self {
+        return Self {
+            readable: vec![],
+            published: vec![],
+        };
+    }
+
+    /// Publish an event, which becomes readable next tick
+    pub fn publish(&mut self, event: T) {
+        self.published.push(event);
+    }
+
+    /// The events published last tick
+    pub fn read(&self) -> &[T] {
+        &self.readable
+    }
+
+    /// Swap the buffers, dropping the events that were readable this tick
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.readable, &mut self.published);
+        self.published.clear();
+    }
+}
+
+impl<T: Serializable> Serializable for EventChannel<T> {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.readable);
+        writer.write(&self.published);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            readable: reader.read()?,
+            published: reader.read()?,
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct DamageDealt {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub amount: u32,
+    pub damage_type: DamageType,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct EntityDied {
+    pub entity: Entity,
+    pub killer: Option<Entity>,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct AilmentTriggered {
+    pub entity: Entity,
+    pub ailment: AilmentTypes,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct InputAction {
+    pub entity: Entity,
+    pub action: EngineInputs,
+}
+
+/// A unit on overwatch fired at a unit moving into its area
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct OverwatchTriggered {
+    pub watcher: Entity,
+    pub target: Entity,
+    pub wounds_removed: u8,
+    pub slain: bool,
+}
+
+/// All event channels systems can communicate through
+#[derive(Clone, Hash)]
+pub struct Events {
+    pub damage_dealt: EventChannel<DamageDealt>,
+    pub entity_died: EventChannel<EntityDied>,
+    pub ailment_triggered: EventChannel<AilmentTriggered>,
+    pub input_action: EventChannel<InputAction>,
+    pub overwatch_triggered: EventChannel<OverwatchTriggered>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        return Self {
+            damage_dealt: EventChannel::new(),
+            entity_died: EventChannel::new(),
+            ailment_triggered: EventChannel::new(),
+            input_action: EventChannel::new(),
+            overwatch_triggered: EventChannel::new(),
+        };
+    }
+
+    /// Advance every channel to the next tick
+    pub fn update(&mut self) {
+        self.damage_dealt.update();
+        self.entity_died.update();
+        self.ailment_triggered.update();
+        self.input_action.update();
+        self.overwatch_triggered.update();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn EventChannel_readable_next_tick() {
+        let mut channel = EventChannel::new();
+
+        channel.publish(1);
+        channel.publish(2);
+        assert!(channel.read().is_empty());
+
+        channel.update();
+        assert_eq!(&[1, 2], channel.read());
+    }
+
+    #[test]
+    fn EventChannel_lives_one_tick() {
+        let mut channel = EventChannel::new();
+
+        channel.publish(1);
+        channel.update();
+        channel.publish(2);
+
+        assert_eq!(&[1], channel.read());
+
+        channel.update();
+        assert_eq!(&[2], channel.read());
+
+        channel.update();
+        assert!(channel.read().is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use super::{components::TransformComponent, ComponentStorage, Entity, World};
+
+/// Parent/child relationships between entities. Children follow their parent's transform and are deleted with it.
+impl World {
+    /// Attach the child to the parent, detaching it from any previous parent. Fails if this would create a cycle.
+    pub fn attach(&mut self, child: Entity, parent: Entity) -> Result<(), String> {
+        if !self.is_alive(child) || !self.is_alive(parent) {
+            return Err(format!(
+                "Unable to attach {:?} to {:?} as one is not alive",
+                child, parent
+            ));
+        }
+
+        if child == parent || self.is_ancestor(child, parent) {
+            return Err(format!(
+                "Attaching {:?} to {:?} would create a cycle",
+                child, parent
+            ));
+        }
+
+        self.detach(child);
+
+        self.parents[child] = Some(parent);
+        match self.children.get_mut(parent) {
+            Some(children) => children.push(child),
+            None => {
+                self.children[parent] = Some(vec![child]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Detach the child from its parent, if it has one. Its transform becomes relative to the world.
+    pub fn detach(&mut self, child: Entity) {
+        let parent = match self.parents.remove(child) {
+            Some(parent) => parent,
+            None => return,
+        };
+
+        if let Some(children) = self.children.get_mut(parent) {
+            children.retain(|e| *e != child);
+        }
+    }
+
+    /// The parent of the entity, if it is attached to a living one
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        match self.parents[entity] {
+            Some(parent) if self.is_alive(parent) => Some(parent),
+            _ => None,
+        }
+    }
+
+    /// The direct children of the entity
+    pub fn children(&self, entity: Entity) -> Vec<Entity> {
+        match self.children.get(entity) {
+            Some(children) => children.clone(),
+            None => vec![],
+        }
+    }
+
+    /// Returns true if the ancestor is somewhere above the entity in the hierarchy
+    pub fn is_ancestor(&self, ancestor: Entity, entity: Entity) -> bool {
+        let mut current = self.parent(entity);
+
+        while let Some(parent) = current {
+            if parent == ancestor {
+                return true;
+            }
+
+            current = self.parent(parent);
+        }
+
+        false
+    }
+
+    /// Compute the world space transforms from the chain of local transforms, starting at the roots of each hierarchy
+    pub(super) fn update_world_transforms(&mut self) {
+        for e in self.entities() {
+            if self.parent(e).is_none() {
+                self.update_world_transform(e, None);
+            }
+        }
+    }
+
+    fn update_world_transform(&mut self, entity: Entity, parent_world: Option<TransformComponent>) {
+        // Entities without a transform act as if they have an identity transform
+        let world_transform = match (self.transforms.get(entity), parent_world) {
+            (Some(local), Some(parent_world)) => Some(local.relative_to(&parent_world)),
+            (Some(local), None) => Some(local.clone()),
+            (None, parent_world) => parent_world,
+        };
+
+        self.world_transforms[entity] = world_transform.clone();
+
+        for child in self.children(entity) {
+            self.update_world_transform(child, world_transform.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib_core::math::{FixedNumber, Rotation3d, Vec3d};
+
+    fn vec(position: (i32, i32, i32)) -> Vec3d {
+        position.into()
+    }
+
+    fn transform(position: (i32, i32, i32)) -> TransformComponent {
+        let mut transform = TransformComponent::new();
+        transform.position = vec(position);
+
+        transform
+    }
+
+    #[test]
+    fn World_attach_and_detach() {
+        let mut world = World::new();
+        let parent = world.add_entity();
+        let child = world.add_entity();
+
+        world.attach(child, parent).unwrap();
+
+        assert_eq!(Some(parent), world.parent(child));
+        assert_eq!(vec![child], world.children(parent));
+
+        world.detach(child);
+
+        assert_eq!(None, world.parent(child));
+        assert!(world.children(parent).is_empty());
+    }
+
+    #[test]
+    fn World_attach_moves_between_parents() {
+        let mut world = World::new();
+        let a = world.add_entity();
+        let b = world.add_entity();
+        let child = world.add_entity();
+
+        world.attach(child, a).unwrap();
+        world.attach(child, b).unwrap();
+
+        assert_eq!(Some(b), world.parent(child));
+        assert!(world.children(a).is_empty());
+        assert_eq!(vec![child], world.children(b));
+    }
+
+    #[test]
+    fn World_attach_rejects_cycles() {
+        let mut world = World::new();
+        let a = world.add_entity();
+        let b = world.add_entity();
+        let c = world.add_entity();
+
+        world.attach(b, a).unwrap();
+        world.attach(c, b).unwrap();
+
+        assert!(world.attach(a, a).is_err());
+        assert!(world.attach(a, c).is_err());
+        assert_eq!(None, world.parent(a));
+        assert!(world.is_ancestor(a, c));
+        assert_eq!(false, world.is_ancestor(c, a));
+    }
+
+    #[test]
+    fn World_world_transforms_follow_parent() {
+        let mut world = World::new();
+        let owner = world.add_entity();
+        let weapon = world.add_entity();
+        let gem = world.add_entity();
+
+        world.transforms[owner] = Some(transform((10, 0, 0)));
+        world.transforms[weapon] = Some(transform((1, 2, 0)));
+        world.transforms[gem] = Some(transform((0, 0, 3)));
+        world.attach(weapon, owner).unwrap();
+        world.attach(gem, weapon).unwrap();
+
+        world.update_world_transforms();
+
+        let position = |e| world.world_transforms[e].as_ref().unwrap().position;
+        assert_eq!(vec((10, 0, 0)), position(owner));
+        assert_eq!(vec((11, 2, 0)), position(weapon));
+        assert_eq!(vec((11, 2, 3)), position(gem));
+    }
+
+    #[test]
+    fn World_world_transforms_apply_parent_rotation() {
+        let mut world = World::new();
+        let owner = world.add_entity();
+        let weapon = world.add_entity();
+
+        let mut owner_transform = transform((0, 0, 0));
+        owner_transform.rotation = Rotation3d {
+            pitch_radians: 0.into(),
+            yaw_radians: FixedNumber::HALF_PI(),
+            roll_radians: 0.into(),
+        };
+        world.transforms[owner] = Some(owner_transform);
+        world.transforms[weapon] = Some(transform((0, 0, -2)));
+        world.attach(weapon, owner).unwrap();
+
+        world.update_world_transforms();
+
+        // Turning left moves something in front of the owner to its left
+        let weapon_world = world.world_transforms[weapon].as_ref().unwrap();
+        let error = FixedNumber::from_bits(8);
+        assert!((weapon_world.position.x - (-2).into()).abs() <= error);
+        assert!(weapon_world.position.z.abs() <= error);
+        assert!((weapon_world.rotation.yaw_radians - FixedNumber::HALF_PI()).abs() <= error);
+    }
+
+    #[test]
+    fn World_delete_cascades_to_children() {
+        let mut world = World::new();
+        let owner = world.add_entity();
+        let weapon = world.add_entity();
+        let gem = world.add_entity();
+        let other = world.add_entity();
+
+        world.attach(weapon, owner).unwrap();
+        world.attach(gem, weapon).unwrap();
+        let other_owner = world.add_entity();
+        world.attach(other, other_owner).unwrap();
+
+        world.delete_entity(owner);
+        world.maintain();
+
+        assert_eq!(false, world.is_alive(owner));
+        assert_eq!(false, world.is_alive(weapon));
+        assert_eq!(false, world.is_alive(gem));
+        assert_eq!(true, world.is_alive(other));
+    }
+
+    #[test]
+    fn World_delete_child_detaches() {
+        let mut world = World::new();
+        let owner = world.add_entity();
+        let weapon = world.add_entity();
+
+        world.attach(weapon, owner).unwrap();
+
+        world.delete_entity(weapon);
+        world.maintain();
+
+        assert_eq!(true, world.is_alive(owner));
+        assert!(world.children(owner).is_empty());
+    }
+}
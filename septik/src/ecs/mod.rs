@@ -1,162 +1,734 @@
-mod assemblages;
 pub mod systems;
 
 use crate::lib_core::{EngineInputs, InputType};
 
-use time::{Duration, Instant};
-const MILLISECONDS_IN_SECOND: u64 = 1000;
+use crate::lib_core::math::{FixedNumber, Rng};
+use crate::lib_core::serialization::{SaveReader, SaveWriter};
+
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+
+use time::Duration;
 
 pub mod components;
 use components::{
     gfx_components::MeshComponent, unit_components::UnitComponent, AabbComponent, AiComponent,
-    AilmentsComponent, EnemyComponent, EngineInputsComponent, FacingComponent, GdNodeComponent,
-    HitPointComponent, MoveSpeedComponent, PlayerComponent, TargetComponent, TargetableComponent,
+    AilmentsComponent, AttackComponent, DeadComponent, DodgeComponent, EnemyComponent,
+    EngineInputsComponent, FacingComponent, GdNodeComponent, HitPointComponent, HitboxComponent,
+    InvulnerabilityComponent, MoveSpeedComponent, OverwatchComponent, PlayerComponent,
+    ResistancesComponent, StaminaComponent, TargetComponent, TargetableComponent,
     ThirdPersonCameraComponent, TransformComponent, VelocityComponent, VoxelChunkComponent,
 };
 
-pub type Entity = usize;
-
-pub type Storage<T> = Vec<Option<T>>;
-
-// TODO: parent/child implementation based off of this:
-// http://bitsquid.blogspot.com/2014/10/building-data-oriented-entity-system.html
-pub struct World {
-    frame_duration: time::Duration,
-    last_frame_execution: time::Instant,
-
-    next_entity: Entity,
-    pub parents: Storage<Entity>,
-    pub ailments: Storage<AilmentsComponent>,
-    pub engine_inputs: Storage<EngineInputsComponent>,
-    pub facing_direction: Storage<FacingComponent>,
-    pub hitpoints: Storage<HitPointComponent>,
-    pub players: Storage<PlayerComponent>,
-    pub transforms: Storage<TransformComponent>,
-    pub velocities: Storage<VelocityComponent>,
-    pub move_speeds: Storage<MoveSpeedComponent>,
-    pub gd_nodes: Storage<GdNodeComponent>,
-    pub targets: Storage<TargetComponent>,
-    pub targetables: Storage<TargetableComponent>,
-    pub ais: Storage<AiComponent>,
-    pub enemies: Storage<EnemyComponent>,
-    pub aabbs: Storage<AabbComponent>,
-    pub voxel_chunks: Storage<VoxelChunkComponent>,
-    pub meshes: Storage<MeshComponent>,
-    pub third_person_cameras: Storage<ThirdPersonCameraComponent>,
-    pub units: Storage<UnitComponent>,
+mod entity;
+pub use entity::{Entity, EntityAllocator};
+
+mod storage;
+pub use storage::{ComponentStorage, SparseStorage, Storage};
+
+mod query;
+pub use query::{Join, JoinIter, Maybe, Not};
+
+mod scheduler;
+pub use scheduler::{Scheduler, Stage, System, SystemDescriptor};
+
+mod clock;
+pub use clock::{Clock, ManualClock, SystemClock};
+
+mod hierarchy;
+
+mod snapshot;
+pub use snapshot::{ChecksumHasher, SnapshotHistory, WorldSnapshot};
+
+mod save;
+pub use save::{Migration, SAVE_VERSION};
+
+pub mod events;
+use events::Events;
+
+mod prefabs;
+pub use prefabs::{Prefab, PrefabComponent, PrefabLibrary};
+
+mod builder;
+pub use builder::{Scenario, WorldBuilder};
+
+mod turns;
+pub use turns::{Phase, TurnState};
+
+#[cfg(test)]
+mod test_fixtures;
+
+/// Declares the world with its component storages, generating everything that has to touch each storage from a single list.
+/// Each storage is listed with the name its components are shown under in dumps and the save version that added it.
+macro_rules! world_storages {
+    ($($(#[$meta:meta])* $name:ident: $storage:ty => $label:literal since $since:literal,)*) => {
+        // Parent/child implementation based off of this:
+        // http://bitsquid.blogspot.com/2014/10/building-data-oriented-entity-system.html
+        pub struct World {
+            frame_duration: time::Duration,
+            clock: Box<dyn Clock + Send>,
+            last_clock_time: time::Duration,
+            accumulator: time::Duration,
+            tick: u64,
+            max_ticks_per_dispatch: u32,
+
+            /// Taken out while the systems run
+            scheduler: Option<Scheduler>,
+            entity_allocator: EntityAllocator,
+            deleted_entities: Vec<Entity>,
+            pub events: Events,
+            pub rng: Rng,
+            history: Option<SnapshotHistory>,
+            prefabs: PrefabLibrary,
+            turns: Option<TurnState>,
+            $(
+                $(#[$meta])*
+                pub $name: $storage,
+            )*
+        }
+
+        /// Copies of every component storage, held by snapshots
+        #[derive(Clone)]
+        struct ComponentStorages {
+            $($name: $storage,)*
+        }
+
+        impl World {
+            /// Create a world without any entities, with the storages sized for `capacity` entities
+            fn with_storages(
+                frame_duration: Duration,
+                clock: Box<dyn Clock + Send>,
+                scheduler: Scheduler,
+                rng: Rng,
+                capacity: usize,
+            ) -> Self {
+                return Self {
+                    frame_duration: frame_duration,
+                    last_clock_time: clock.now(),
+                    clock: clock,
+                    accumulator: Duration::zero(),
+                    tick: 0,
+                    max_ticks_per_dispatch: World::MAX_TICKS_PER_DISPATCH,
+                    scheduler: Some(scheduler),
+                    entity_allocator: EntityAllocator::with_capacity(capacity),
+                    deleted_entities: vec![],
+                    events: Events::new(),
+                    rng: rng,
+                    history: None,
+                    prefabs: PrefabLibrary::new(),
+                    turns: None,
+                    $($name: <$storage>::with_capacity(capacity),)*
+                };
+            }
+
+            /// Remove the entity's components from every storage
+            fn clear_components(&mut self, entity: Entity) {
+                $(self.$name.clear(entity);)*
+            }
+
+            fn copy_components(&self) -> ComponentStorages {
+                return ComponentStorages {
+                    $($name: self.$name.clone(),)*
+                };
+            }
+
+            fn restore_components(&mut self, components: ComponentStorages) {
+                $(self.$name = components.$name;)*
+            }
+
+            fn hash_components<H: Hasher>(&self, state: &mut H) {
+                $(self.$name.hash(state);)*
+            }
+
+            /// Write the storages added in the given save versions, in order
+            fn write_components(&self, writer: &mut SaveWriter, added_in: RangeInclusive<u16>) {
+                $(
+                    if added_in.contains(&$since) {
+                        writer.write(&self.$name);
+                    }
+                )*
+            }
+
+            /// Read the storages added in the given save versions. Storages newer than the save are left empty.
+            fn read_components(
+                &mut self,
+                reader: &mut SaveReader,
+                added_in: RangeInclusive<u16>,
+            ) -> Result<(), String> {
+                $(
+                    if added_in.contains(&$since) && reader.version() >= $since {
+                        self.$name = reader.read()?;
+                    }
+                )*
+
+                Ok(())
+            }
+
+            /// Write a line for each of the entity's components
+            fn dump_components(&self, dump: &mut String, entity: Entity) {
+                $(save::dump_component(dump, $label, &self.$name[entity]);)*
+            }
+        }
+    };
+}
+
+world_storages! {
+    parents: Storage<Entity> => "parent" since 1,
+    children: Storage<Vec<Entity>> => "children" since 1,
+    /// World space transforms, calculated from the local transforms of each entity and its parents
+    world_transforms: Storage<TransformComponent> => "world_transform" since 1,
+    ailments: Storage<AilmentsComponent> => "ailments" since 1,
+    engine_inputs: Storage<EngineInputsComponent> => "engine_inputs" since 1,
+    facing_direction: Storage<FacingComponent> => "facing_direction" since 1,
+    hitpoints: Storage<HitPointComponent> => "hitpoints" since 1,
+    players: Storage<PlayerComponent> => "player" since 1,
+    transforms: Storage<TransformComponent> => "transform" since 1,
+    velocities: Storage<VelocityComponent> => "velocity" since 1,
+    move_speeds: Storage<MoveSpeedComponent> => "move_speed" since 1,
+    gd_nodes: Storage<GdNodeComponent> => "gd_node" since 1,
+    targets: Storage<TargetComponent> => "target" since 1,
+    targetables: Storage<TargetableComponent> => "targetable" since 1,
+    ais: Storage<AiComponent> => "ai" since 1,
+    enemies: Storage<EnemyComponent> => "enemy" since 1,
+    aabbs: Storage<AabbComponent> => "aabb" since 1,
+    voxel_chunks: SparseStorage<VoxelChunkComponent> => "voxel_chunk" since 1,
+    meshes: Storage<MeshComponent> => "mesh" since 1,
+    third_person_cameras: SparseStorage<ThirdPersonCameraComponent> => "third_person_camera" since 1,
+    units: Storage<UnitComponent> => "unit" since 1,
+    resistances: Storage<ResistancesComponent> => "resistances" since 3,
+    invulnerabilities: Storage<InvulnerabilityComponent> => "invulnerability" since 3,
+    dead: Storage<DeadComponent> => "dead" since 3,
+    overwatches: Storage<OverwatchComponent> => "overwatch" since 5,
+    attacks: Storage<AttackComponent> => "attack" since 6,
+    hitboxes: Storage<HitboxComponent> => "hitbox" since 6,
+    staminas: Storage<StaminaComponent> => "stamina" since 7,
+    dodges: Storage<DodgeComponent> => "dodge" since 7,
 }
 
 impl World {
-    pub const MAX_ENTITIES: usize = 1000;
+    /// The most ticks a single dispatch will run to catch up, so a slow frame can't cause a spiral of ever slower frames.
+    pub const MAX_TICKS_PER_DISPATCH: u32 = 5;
+
+    /// The seed for the world's random number generator
+    pub const DEFAULT_SEED: u64 = 0x5EB71C;
 
-    /// Initialize a new world that represents the game engine
+    /// Initialize a new world that represents the game engine, with the demo scenario
     pub fn new() -> Self {
-        let sim_executions_per_second = 60;
-        let frame_duration = Duration::milliseconds(
-            MILLISECONDS_IN_SECOND as i64 / sim_executions_per_second as i64,
-        );
+        WorldBuilder::new()
+            .scenario(Scenario::Demo)
+            .build()
+            .expect("Demo world should be valid")
+    }
 
-        let start = Instant::now();
-
-        let mut world = Self {
-            frame_duration: frame_duration,
-            last_frame_execution: start,
-            next_entity: 0,
-            parents: generate_storage(),
-            ailments: generate_storage(),
-            engine_inputs: generate_storage(),
-            facing_direction: generate_storage(),
-            hitpoints: generate_storage(),
-            players: generate_storage(),
-            transforms: generate_storage(),
-            velocities: generate_storage(),
-            move_speeds: generate_storage(),
-            gd_nodes: generate_storage(),
-            targets: generate_storage(),
-            targetables: generate_storage(),
-            ais: generate_storage(),
-            enemies: generate_storage(),
-            aabbs: generate_storage(),
-            voxel_chunks: generate_storage(),
-            meshes: generate_storage(),
-            third_person_cameras: generate_storage(),
-            units: generate_storage(),
-        };
-
-        assemblages::assemblage_player(&mut world);
-        assemblages::assemblage_basic_enemy(&mut world);
-        assemblages::assemblage_basic_voxel_chunk(&mut world);
-
-        return world;
+    /// Configure a new world, such as an empty one for tests or servers
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
     }
 
     /// Retrieve the set of entities to process
-    pub fn entities(&self) -> std::ops::Range<usize> {
-        0..self.next_entity
+    pub fn entities(&self) -> Vec<Entity> {
+        self.entity_allocator.alive()
+    }
+
+    /// The first entity controlled by a player, if there is one
+    pub fn player(&self) -> Option<Entity> {
+        let entities = self.entities();
+
+        (&self.players).join(&entities).map(|(e, _)| e).next()
+    }
+
+    /// Returns true if the entity has not been deleted. Use this to detect stale references to other entities.
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entity_allocator.is_alive(entity)
     }
 
     /// Register a set of inputs for processing
     pub fn register_player_inputs(&mut self, inputs: &Vec<InputType>) {
-        for e in self.entities() {
-            let player = &self.players[e];
-            let engine_inputs = &self.engine_inputs[e];
+        let entities = self.entities();
 
-            if player.is_none() || engine_inputs.is_none() {
-                continue;
-            }
+        for (_, (_, engine_inputs)) in (&self.players, &mut self.engine_inputs).join(&entities) {
+            engine_inputs.inputs.extend(inputs.iter().cloned());
+        }
+    }
+
+    /// Replace the clock that drives the simulation, such as with a `ManualClock` for tests. Any accumulated time is discarded.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock + Send>) {
+        self.last_clock_time = clock.now();
+        self.clock = clock;
+        self.accumulator = Duration::zero();
+    }
+
+    /// The number of ticks that have been run
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
 
-            let mut engine_inputs = engine_inputs.clone().unwrap();
-            engine_inputs.inputs.append(&mut (inputs.clone()));
+    /// The simulated time each tick covers
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    /// How far between the last tick and the next one the simulation is, from 0 to 1. Renderers can use this to interpolate.
+    pub fn interpolation_alpha(&self) -> FixedNumber {
+        let frame = self.frame_duration.whole_nanoseconds();
+        // Round, as frames are rarely a whole number of nanoseconds
+        let alpha = ((self.accumulator.whole_nanoseconds() << 12) + frame / 2) / frame;
 
-            self.engine_inputs[e] = Some(engine_inputs);
+        FixedNumber::from_bits(alpha as i32)
+    }
+
+    /// Run a tick for each frame's worth of time that has passed since the last dispatch, returning the number of ticks run.
+    /// Time beyond the catch up limit is dropped, slowing the simulation instead of stalling.
+    pub fn dispatch(&mut self) -> u32 {
+        let now = self.clock.now();
+        self.accumulator += now - self.last_clock_time;
+        self.last_clock_time = now;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.frame_duration && ticks < self.max_ticks_per_dispatch {
+            self.step();
+
+            self.accumulator -= self.frame_duration;
+            ticks += 1;
+        }
+
+        if self.accumulator >= self.frame_duration {
+            // Keep the partial frame so the interpolation alpha stays smooth
+            let remainder =
+                self.accumulator.whole_nanoseconds() % self.frame_duration.whole_nanoseconds();
+
+            self.accumulator = Duration::nanoseconds(remainder as i64);
         }
+
+        return ticks;
     }
 
-    /// Return true if ready to run, or false if not
-    fn ready_to_run(&self) -> bool {
-        let now = Instant::now() - self.last_frame_execution;
-        let run_game_sim = self.frame_duration <= now;
+    /// Run a single tick immediately, regardless of the clock. Used to resimulate after a rollback.
+    pub fn step(&mut self) {
+        // Take the scheduler out so systems can borrow the world mutably
+        if let Some(mut scheduler) = self.scheduler.take() {
+            scheduler.run(self);
+            self.scheduler = Some(scheduler);
+        }
+
+        self.maintain();
+        self.tick += 1;
 
-        return run_game_sim;
+        self.record_history();
     }
 
-    /// Dispatch all systems and execute if ready to run
-    pub fn dispatch(&mut self) {
-        if self.ready_to_run() {
-            systems::character_action_system(self);
-            systems::position_update_system(self);
+    /// Register a system to run on each dispatch. Fails if called from a running system.
+    pub fn add_system(&mut self, descriptor: SystemDescriptor) -> Result<(), String> {
+        self.scheduler_mut()?.add_system(descriptor)
+    }
 
-            self.maintain();
-            self.last_frame_execution = Instant::now();
+    /// Enable or disable a system at runtime. Fails if called from a running system.
+    pub fn set_system_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        self.scheduler_mut()?.set_enabled(name, enabled)
+    }
+
+    /// The names of the registered systems, in the order they run. Empty while the systems are running.
+    pub fn system_order(&self) -> Vec<&'static str> {
+        match &self.scheduler {
+            Some(scheduler) => scheduler.order(),
+            None => vec![],
         }
     }
 
-    /// Add a new entity to the world
-    pub fn add_entity(&mut self) -> Entity {
-        let e = self.next_entity;
+    /// How long each system took on the last dispatch, in the order they run. Empty while the systems are running.
+    pub fn system_timings(&self) -> Vec<(&'static str, Duration)> {
+        match &self.scheduler {
+            Some(scheduler) => scheduler.timings(),
+            None => vec![],
+        }
+    }
 
-        self.next_entity += 1;
+    fn scheduler_mut(&mut self) -> Result<&mut Scheduler, String> {
+        match self.scheduler.as_mut() {
+            Some(scheduler) => Ok(scheduler),
+            None => Err(String::from(
+                "Systems can't be changed while the scheduler is running",
+            )),
+        }
+    }
 
-        return e;
+    /// Add a new entity to the world
+    pub fn add_entity(&mut self) -> Entity {
+        return self.entity_allocator.allocate();
     }
 
     fn maintain(&mut self) {
-        //TODO: shift entities over, delete any missing entities, update indexes.
         // General cleanup function.
-        systems::input_cleanup_system(self);
+        let deleted_entities = std::mem::replace(&mut self.deleted_entities, vec![]);
+        for e in deleted_entities {
+            self.remove_entity(e);
+        }
+
+        self.events.update();
     }
 
-    fn delete_entity(&mut self, entity: Entity) {
-        unimplemented!();
+    /// Mark an entity for deletion. It is removed at the end of the current dispatch, so systems may still see it until then.
+    pub fn delete_entity(&mut self, entity: Entity) {
+        // Deleting twice is harmless, as dead entities are skipped when removing
+        if self.is_alive(entity) {
+            self.deleted_entities.push(entity);
+        }
+    }
+
+    /// Immediately remove the entity, its children and all of their components
+    fn remove_entity(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        for child in self.children(entity) {
+            self.remove_entity(child);
+        }
+
+        self.detach(entity);
+        self.entity_allocator.free(entity);
+
+        self.clear_components(entity);
     }
 }
 
-fn generate_storage<TComponent>() -> Storage<TComponent> {
-    let mut v = Vec::<Option<TComponent>>::with_capacity(World::MAX_ENTITIES);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn World_delete_entity_is_deferred() {
+        let mut world = World::new();
+        let e = world.add_entity();
+        world.hitpoints[e] = Some(HitPointComponent {
+            value: 10,
+            max_value: 10,
+        });
+
+        world.delete_entity(e);
+
+        assert_eq!(true, world.is_alive(e));
+        assert!(world.hitpoints[e].is_some());
 
-    for _ in 0..World::MAX_ENTITIES {
-        v.push(None);
+        world.maintain();
+
+        assert_eq!(false, world.is_alive(e));
+        assert!(world.hitpoints[e].is_none());
+        assert_eq!(false, world.entities().contains(&e));
+    }
+
+    #[test]
+    fn World_delete_entity_recycles_slot() {
+        let mut world = World::new();
+        let enemy = world.add_entity();
+        world.hitpoints[enemy] = Some(HitPointComponent {
+            value: 10,
+            max_value: 10,
+        });
+
+        let player = world.add_entity();
+        world.targets[player] = Some(TargetComponent { entity: enemy });
+
+        world.delete_entity(enemy);
+        world.maintain();
+
+        let new_entity = world.add_entity();
+
+        assert_eq!(enemy.index(), new_entity.index());
+        assert!(world.hitpoints[new_entity].is_none());
+
+        // The stale target is detectable and doesn't alias the new entity
+        let target = world.targets[player].as_ref().unwrap().entity;
+        world.hitpoints[new_entity] = Some(HitPointComponent {
+            value: 5,
+            max_value: 5,
+        });
+
+        assert_eq!(false, world.is_alive(target));
+        assert!(world.hitpoints[target].is_none());
+    }
+
+    #[test]
+    fn World_spawn_many_entities() {
+        let mut world = World::new();
+        let mut spawned = vec![];
+
+        for i in 0..100_000 {
+            let e = world.add_entity();
+            world.transforms[e] = Some(TransformComponent::new());
+
+            if i % 1000 == 0 {
+                world.third_person_cameras[e] = Some(ThirdPersonCameraComponent::new());
+            }
+
+            spawned.push(e);
+        }
+
+        assert!(world.entities().len() >= 100_000);
+        assert!(world.transforms[spawned[99_999]].is_some());
+        assert!(world.third_person_cameras[spawned[99_000]].is_some());
+        assert!(world.third_person_cameras[spawned[99_001]].is_none());
+
+        for e in spawned.iter().step_by(2) {
+            world.delete_entity(*e);
+        }
+        world.maintain();
+
+        assert!(world.transforms[spawned[0]].is_none());
+        assert!(world.transforms[spawned[1]].is_some());
+        assert!(world.third_person_cameras[spawned[0]].is_none());
+
+        // Freed slots are reused before growing
+        let e = world.add_entity();
+        assert!(e.index() <= spawned[99_999].index());
+    }
+
+    #[test]
+    fn World_player_inputs_move_player() {
+        let mut world = World::new();
+        let player = world.player().unwrap();
+
+        world.register_player_inputs(&vec![InputType::Held(0, EngineInputs::MoveRight)]);
+        world.step();
+
+        let speed = world.move_speeds[player].as_ref().unwrap().value;
+        assert_eq!(speed, world.velocities[player].as_ref().unwrap().value.x);
+        assert_eq!(speed, world.transforms[player].as_ref().unwrap().position.x);
+        assert!(world.engine_inputs[player]
+            .as_ref()
+            .unwrap()
+            .inputs
+            .is_empty());
+    }
+
+    #[test]
+    fn World_add_system() {
+        fn spawn(world: &mut World) {
+            world.add_entity();
+        }
+
+        let mut world = World::new();
+        let entity_count = world.entities().len();
+
+        world
+            .add_system(SystemDescriptor::new("spawn", Stage::Logic, spawn).after("facing_update"))
+            .unwrap();
+        world.step();
+
+        assert_eq!(entity_count + 1, world.entities().len());
+        assert_eq!(
+            vec![
+                "character_action",
+                "dodge",
+                "facing_update",
+                "ailment_update",
+                "invulnerability_update",
+                "attack_update",
+                "spawn",
+                "overwatch",
+                "position_update",
+                "world_transform_update",
+                "death",
+                "input_cleanup"
+            ],
+            world
+                .system_timings()
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<&str>>()
+        );
+
+        world.set_system_enabled("spawn", false).unwrap();
+        world.step();
+
+        assert_eq!(entity_count + 1, world.entities().len());
+    }
+
+    #[test]
+    fn World_systems_cant_change_mid_tick() {
+        fn toggle(world: &mut World) {
+            fn noop(_: &mut World) {}
+
+            assert!(world.set_system_enabled("toggle", false).is_err());
+            assert!(world
+                .add_system(SystemDescriptor::new("noop", Stage::Logic, noop))
+                .is_err());
+        }
+
+        let mut world = World::builder()
+            .without_engine_systems()
+            .system(SystemDescriptor::new("toggle", Stage::Logic, toggle))
+            .build()
+            .unwrap();
+
+        world.step();
+
+        assert_eq!(vec!["toggle"], world.system_order());
+    }
+
+    fn manual_world() -> (World, ManualClock) {
+        let mut world = World::new();
+        let clock = ManualClock::new();
+
+        world.set_clock(Box::new(clock.clone()));
+
+        (world, clock)
     }
 
-    return v;
+    #[test]
+    fn World_can_move_between_threads() {
+        let (mut world, clock) = manual_world();
+
+        let handle = std::thread::spawn(move || {
+            clock.advance(world.frame_duration);
+            world.dispatch();
+
+            world
+        });
+
+        assert_eq!(1, handle.join().unwrap().tick());
+    }
+
+    #[test]
+    fn World_dispatch_waits_for_a_full_frame() {
+        let (mut world, clock) = manual_world();
+
+        assert_eq!(0, world.dispatch());
+
+        clock.advance(world.frame_duration / 2);
+        assert_eq!(0, world.dispatch());
+        assert_eq!(0, world.tick());
+
+        clock.advance(world.frame_duration / 2);
+        assert_eq!(1, world.dispatch());
+        assert_eq!(1, world.tick());
+    }
+
+    #[test]
+    fn World_dispatch_catches_up() {
+        let (mut world, clock) = manual_world();
+
+        clock.advance(world.frame_duration * 3);
+
+        assert_eq!(3, world.dispatch());
+        assert_eq!(3, world.tick());
+        assert_eq!(0, world.dispatch());
+    }
+
+    #[test]
+    fn World_dispatch_caps_catch_up() {
+        let (mut world, clock) = manual_world();
+
+        clock.advance(world.frame_duration * 100 + world.frame_duration / 4);
+
+        assert_eq!(World::MAX_TICKS_PER_DISPATCH, world.dispatch());
+        assert_eq!(World::MAX_TICKS_PER_DISPATCH as u64, world.tick());
+
+        // The dropped time is not run later
+        assert_eq!(0, world.dispatch());
+        assert_eq!(FixedNumber::from_bits(1024), world.interpolation_alpha());
+    }
+
+    #[test]
+    fn World_interpolation_alpha() {
+        let (mut world, clock) = manual_world();
+        let zero: FixedNumber = 0.into();
+
+        assert_eq!(zero, world.interpolation_alpha());
+
+        clock.advance(world.frame_duration + world.frame_duration / 2);
+        world.dispatch();
+
+        assert_eq!(FixedNumber::from_bits(2048), world.interpolation_alpha());
+    }
+
+    #[test]
+    fn World_events_between_stages() {
+        fn producer(world: &mut World) {
+            for e in world.entities() {
+                if world.enemies[e].is_some() {
+                    world.events.damage_dealt.publish(events::DamageDealt {
+                        source: None,
+                        target: e,
+                        amount: 3,
+                        damage_type: components::DamageType::Physical,
+                    });
+                }
+            }
+        }
+
+        // Runs before the producer each tick, so only sees the previous tick's events
+        fn consumer(world: &mut World) {
+            for event in world.events.damage_dealt.read().to_vec() {
+                let hitpoints = world.hitpoints[event.target].as_mut().unwrap();
+                hitpoints.value -= event.amount;
+            }
+        }
+
+        let mut world = World::new();
+        let enemy = world
+            .entities()
+            .into_iter()
+            .find(|e| world.enemies[*e].is_some())
+            .unwrap();
+        world.hitpoints[enemy] = Some(HitPointComponent {
+            value: 10,
+            max_value: 10,
+        });
+
+        world
+            .add_system(SystemDescriptor::new("producer", Stage::Physics, producer))
+            .unwrap();
+        world
+            .add_system(SystemDescriptor::new("consumer", Stage::Input, consumer))
+            .unwrap();
+
+        world.step();
+        assert_eq!(10, world.hitpoints[enemy].as_ref().unwrap().value);
+
+        world.set_system_enabled("producer", false).unwrap();
+
+        world.step();
+        assert_eq!(7, world.hitpoints[enemy].as_ref().unwrap().value);
+
+        // Events are only readable for a single tick
+        world.step();
+        assert_eq!(7, world.hitpoints[enemy].as_ref().unwrap().value);
+    }
+
+    #[test]
+    fn World_pressed_inputs_publish_actions() {
+        let mut world = World::new();
+
+        world.register_player_inputs(&vec![
+            InputType::Pressed(0, EngineInputs::Jump),
+            InputType::Held(0, EngineInputs::MoveLeft),
+        ]);
+        world.step();
+
+        let actions: Vec<EngineInputs> = world
+            .events
+            .input_action
+            .read()
+            .iter()
+            .map(|event| event.action)
+            .collect();
+
+        assert_eq!(vec![EngineInputs::Jump], actions);
+    }
+
+    #[test]
+    fn World_delete_entity_twice() {
+        let mut world = World::new();
+        let e = world.add_entity();
+        let entity_count = world.entities().len();
+
+        world.delete_entity(e);
+        world.delete_entity(e);
+        world.maintain();
+        world.delete_entity(e);
+        world.maintain();
+
+        assert_eq!(entity_count - 1, world.entities().len());
+    }
 }
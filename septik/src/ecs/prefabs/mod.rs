@@ -0,0 +1,815 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use pest::Parser;
+
+use super::{
+    components::{
+        gfx_components::{Mesh, MeshComponent},
+        unit_components::UnitComponent,
+        AabbComponent, AiComponent, Ailment, AilmentTypes, AilmentsComponent, DamageType,
+        EnemyComponent, EngineInputsComponent, FacingComponent, HitPointComponent,
+        InvulnerabilityComponent, MoveSpeedComponent, PlayerComponent, ResistancesComponent,
+        StaminaComponent, TargetableComponent, ThirdPersonCameraComponent, TransformComponent,
+        VelocityComponent, VoxelChunkComponent,
+    },
+    Entity, World,
+};
+use crate::lib_core::{
+    math::{FixedNumber, Range, Rotation3d, Vec3d},
+    Aabb, Direction,
+};
+
+#[derive(Parser)]
+#[grammar = "ecs/prefabs/prefab.pest"]
+struct PrefabParser;
+
+/// The prefabs every world starts with
+const DEFAULT_PREFABS: &str = include_str!("../../../assets/prefabs/default.prefab");
+
+/// A component with the values set by a prefab.
+/// Components that reference other entities or engine nodes, such as parents and targets, can't be set by prefabs.
+#[derive(Clone, Debug)]
+pub enum PrefabComponent {
+    Ai(AiComponent),
+    Aabb(AabbComponent),
+    Ailments(AilmentsComponent),
+    Enemy(EnemyComponent),
+    EngineInputs(EngineInputsComponent),
+    Facing(FacingComponent),
+    Hitpoints(HitPointComponent),
+    Invulnerability(InvulnerabilityComponent),
+    Mesh(MeshComponent),
+    MoveSpeed(MoveSpeedComponent),
+    Player(PlayerComponent),
+    Resistances(ResistancesComponent),
+    Stamina(StaminaComponent),
+    Targetable(TargetableComponent),
+    ThirdPersonCamera(ThirdPersonCameraComponent),
+    Transform(TransformComponent),
+    Unit(UnitComponent),
+    Velocity(VelocityComponent),
+    VoxelChunk(VoxelChunkComponent),
+}
+
+/// A named set of components that can be spawned as a new entity
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    name: String,
+    components: Vec<PrefabComponent>,
+}
+
+impl Prefab {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn components(&self) -> &[PrefabComponent] {
+        &self.components
+    }
+}
+
+/// All prefabs that have been loaded, by name
+pub struct PrefabLibrary {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl PrefabLibrary {
+    pub fn new() -> Self {
+        return Self {
+            prefabs: HashMap::new(),
+        };
+    }
+
+    /// Parse and validate prefab definitions. Nothing is added if any prefab is invalid.
+    /// Prefabs with the same name as one already loaded replace it.
+    pub fn load(&mut self, source: &str) -> Result<(), String> {
+        let prefabs = parse_prefabs(source)?;
+
+        for prefab in prefabs {
+            self.prefabs.insert(prefab.name.clone(), prefab);
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// The names of all loaded prefabs, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.prefabs.keys().map(|name| name.as_str()).collect();
+        names.sort();
+
+        names
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefabs.len()
+    }
+}
+
+impl World {
+    /// Load the built in prefabs
+    pub(super) fn load_default_prefabs(&mut self) {
+        self.prefabs
+            .load(DEFAULT_PREFABS)
+            .expect("Default prefabs should be valid");
+    }
+
+    /// Load prefab definitions, replacing any existing prefabs with the same names
+    pub fn load_prefabs(&mut self, source: &str) -> Result<(), String> {
+        self.prefabs.load(source)
+    }
+
+    /// Load prefab definitions from a file, so new prefabs can be added without recompiling
+    pub fn load_prefab_file(&mut self, path: &str) -> Result<(), String> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => return Err(format!("Unable to read '{}': {}", path, e)),
+        };
+
+        match self.load_prefabs(&source) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    pub fn prefabs(&self) -> &PrefabLibrary {
+        &self.prefabs
+    }
+
+    /// Add a new entity with the prefab's components
+    pub fn spawn_prefab(&mut self, name: &str) -> Result<Entity, String> {
+        let components = match self.prefabs.get(name) {
+            Some(prefab) => prefab.components.clone(),
+            None => return Err(format!("Unknown prefab '{}'", name)),
+        };
+
+        let e = self.add_entity();
+
+        for component in components {
+            match component {
+                PrefabComponent::Ai(c) => self.ais[e] = Some(c),
+                PrefabComponent::Aabb(c) => self.aabbs[e] = Some(c),
+                PrefabComponent::Ailments(c) => self.ailments[e] = Some(c),
+                PrefabComponent::Enemy(c) => self.enemies[e] = Some(c),
+                PrefabComponent::EngineInputs(c) => self.engine_inputs[e] = Some(c),
+                PrefabComponent::Facing(c) => self.facing_direction[e] = Some(c),
+                PrefabComponent::Hitpoints(c) => self.hitpoints[e] = Some(c),
+                PrefabComponent::Invulnerability(c) => self.invulnerabilities[e] = Some(c),
+                PrefabComponent::Mesh(c) => self.meshes[e] = Some(c),
+                PrefabComponent::MoveSpeed(c) => self.move_speeds[e] = Some(c),
+                PrefabComponent::Player(c) => self.players[e] = Some(c),
+                PrefabComponent::Resistances(c) => self.resistances[e] = Some(c),
+                PrefabComponent::Stamina(c) => self.staminas[e] = Some(c),
+                PrefabComponent::Targetable(c) => self.targetables[e] = Some(c),
+                PrefabComponent::ThirdPersonCamera(c) => self.third_person_cameras[e] = Some(c),
+                PrefabComponent::Transform(c) => self.transforms[e] = Some(c),
+                PrefabComponent::Unit(c) => self.units[e] = Some(c),
+                PrefabComponent::Velocity(c) => self.velocities[e] = Some(c),
+                PrefabComponent::VoxelChunk(c) => self.voxel_chunks[e] = Some(c),
+            }
+        }
+
+        // Units take their hit points from their wounds, unless the prefab gives them some
+        if let Some(unit) = &self.units[e] {
+            if self.hitpoints[e].is_none() {
+                self.hitpoints[e] = Some(unit.hitpoints());
+            }
+        }
+
+        Ok(e)
+    }
+}
+
+/// A field value, before it has been checked against the component
+#[derive(Clone, Debug)]
+enum Value {
+    Number(String),
+    Vector(String, String, String),
+    Identifier(String),
+    List(Vec<Value>),
+    Object(Fields),
+}
+
+impl Value {
+    fn as_fixed(&self) -> Result<FixedNumber, String> {
+        match self {
+            Value::Number(number) => FixedNumber::parse(number),
+            _ => Err(format!("Expected a number, found {:?}", self)),
+        }
+    }
+
+    fn as_integer<T: FromStr>(&self) -> Result<T, String> {
+        match self {
+            Value::Number(number) => match number.parse() {
+                Ok(value) => Ok(value),
+                Err(_) => Err(format!(
+                    "'{}' is not a valid integer for this field",
+                    number
+                )),
+            },
+            _ => Err(format!("Expected an integer, found {:?}", self)),
+        }
+    }
+
+    fn as_vec3d(&self) -> Result<Vec3d, String> {
+        match self {
+            Value::Vector(x, y, z) => Ok(Vec3d::new(
+                FixedNumber::parse(x)?,
+                FixedNumber::parse(y)?,
+                FixedNumber::parse(z)?,
+            )),
+            _ => Err(format!("Expected a vector, found {:?}", self)),
+        }
+    }
+
+    /// Rotations are written as a (pitch, yaw, roll) vector
+    fn as_rotation(&self) -> Result<Rotation3d, String> {
+        let radians = self.as_vec3d()?;
+
+        return Ok(Rotation3d {
+            pitch_radians: radians.x,
+            yaw_radians: radians.y,
+            roll_radians: radians.z,
+        });
+    }
+
+    fn as_identifier(&self) -> Result<&str, String> {
+        match self {
+            Value::Identifier(identifier) => Ok(identifier),
+            _ => Err(format!("Expected a name, found {:?}", self)),
+        }
+    }
+
+    fn as_list(&self) -> Result<&Vec<Value>, String> {
+        match self {
+            Value::List(values) => Ok(values),
+            _ => Err(format!("Expected a list, found {:?}", self)),
+        }
+    }
+
+    fn as_object(&self) -> Result<Fields, String> {
+        match self {
+            Value::Object(fields) => Ok(fields.clone()),
+            _ => Err(format!("Expected a struct, found {:?}", self)),
+        }
+    }
+}
+
+/// The fields set on a component. Each field is taken as it is used, so any left over are unknown.
+#[derive(Clone, Debug)]
+struct Fields {
+    fields: Vec<(String, Value)>,
+}
+
+impl Fields {
+    /// Remove the field, returning its value if it was set
+    fn take(&mut self, name: &str) -> Option<Value> {
+        let index = self.fields.iter().position(|(field, _)| field == name)?;
+
+        Some(self.fields.remove(index).1)
+    }
+
+    /// Remove a field that has no default
+    fn take_required(&mut self, name: &str) -> Result<Value, String> {
+        match self.take(name) {
+            Some(value) => Ok(value),
+            None => Err(format!("Missing field '{}'", name)),
+        }
+    }
+
+    /// Error if any fields were not used
+    fn finish(&self) -> Result<(), String> {
+        match self.fields.first() {
+            Some((name, _)) => Err(format!("Unknown field '{}'", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_prefabs(source: &str) -> Result<Vec<Prefab>, String> {
+    let pairs = match PrefabParser::parse(Rule::prefabs, source) {
+        Ok(pairs) => pairs,
+        Err(e) => return Err(format!("{}", e)),
+    };
+
+    let mut prefabs: Vec<Prefab> = vec![];
+
+    for pair in pairs.flatten() {
+        if pair.as_rule() != Rule::prefab {
+            continue;
+        }
+
+        let prefab = parse_prefab(pair)?;
+
+        if prefabs.iter().any(|p| p.name == prefab.name) {
+            return Err(format!(
+                "Prefab '{}' is defined more than once",
+                prefab.name
+            ));
+        }
+
+        prefabs.push(prefab);
+    }
+
+    Ok(prefabs)
+}
+
+fn parse_prefab(pair: pest::iterators::Pair<Rule>) -> Result<Prefab, String> {
+    let mut pairs = pair.into_inner();
+    let name = pairs.next().unwrap().as_str().to_string();
+
+    let mut names: Vec<String> = vec![];
+    let mut components = vec![];
+
+    for pair in pairs {
+        let mut inner = pair.into_inner();
+        let component_name = inner.next().unwrap().as_str().to_string();
+        let fields = read_object(inner.next().unwrap())?;
+
+        if names.contains(&component_name) {
+            return Err(format!(
+                "Prefab '{}': component '{}' is listed more than once",
+                name, component_name
+            ));
+        }
+
+        match build_component(&component_name, fields) {
+            Ok(component) => components.push(component),
+            Err(e) => {
+                return Err(format!(
+                    "Prefab '{}': component '{}': {}",
+                    name, component_name, e
+                ))
+            }
+        }
+
+        names.push(component_name);
+    }
+
+    return Ok(Prefab {
+        name: name,
+        components: components,
+    });
+}
+
+fn read_object(pair: pest::iterators::Pair<Rule>) -> Result<Fields, String> {
+    let mut fields: Vec<(String, Value)> = vec![];
+
+    for field in pair.into_inner() {
+        let mut inner = field.into_inner();
+        let name = inner.next().unwrap().as_str().to_string();
+        let value = read_value(inner.next().unwrap())?;
+
+        if fields.iter().any(|(field, _)| *field == name) {
+            return Err(format!("Field '{}' is set more than once", name));
+        }
+
+        fields.push((name, value));
+    }
+
+    return Ok(Fields { fields: fields });
+}
+
+fn read_value(pair: pest::iterators::Pair<Rule>) -> Result<Value, String> {
+    let pair = pair.into_inner().next().unwrap();
+
+    match pair.as_rule() {
+        Rule::number => Ok(Value::Number(pair.as_str().to_string())),
+        Rule::identifier => Ok(Value::Identifier(pair.as_str().to_string())),
+        Rule::vector => {
+            let numbers: Vec<String> = pair
+                .into_inner()
+                .map(|number| number.as_str().to_string())
+                .collect();
+
+            Ok(Value::Vector(
+                numbers[0].clone(),
+                numbers[1].clone(),
+                numbers[2].clone(),
+            ))
+        }
+        Rule::list => {
+            let mut values = vec![];
+            for value in pair.into_inner() {
+                values.push(read_value(value)?);
+            }
+
+            Ok(Value::List(values))
+        }
+        Rule::object => Ok(Value::Object(read_object(pair)?)),
+        rule => Err(format!("Unexpected {:?}", rule)),
+    }
+}
+
+/// Validate the fields against the named component and build it, using the component's defaults for fields that aren't set
+fn build_component(name: &str, mut fields: Fields) -> Result<PrefabComponent, String> {
+    let component = match name {
+        "ai" => PrefabComponent::Ai(AiComponent::new()),
+        "enemy" => PrefabComponent::Enemy(EnemyComponent::new()),
+        "player" => PrefabComponent::Player(PlayerComponent::new()),
+        "targetable" => PrefabComponent::Targetable(TargetableComponent {}),
+        "engine_inputs" => PrefabComponent::EngineInputs(EngineInputsComponent::new()),
+        "voxel_chunk" => PrefabComponent::VoxelChunk(VoxelChunkComponent::new()),
+        "aabb" => {
+            let min = fields.take_required("min")?.as_vec3d()?;
+            let max = fields.take_required("max")?.as_vec3d()?;
+
+            PrefabComponent::Aabb(AabbComponent::new(Aabb::new(min, max)))
+        }
+        "ailments" => {
+            let mut ailments = vec![];
+
+            if let Some(list) = fields.take("ailments") {
+                for value in list.as_list()? {
+                    ailments.push(build_ailment(value.as_object()?)?);
+                }
+            }
+
+            PrefabComponent::Ailments(AilmentsComponent { ailments: ailments })
+        }
+        "facing" => {
+            let direction = parse_direction(fields.take_required("direction")?.as_identifier()?)?;
+
+            PrefabComponent::Facing(FacingComponent::new(direction))
+        }
+        "hitpoints" => {
+            let max_value: u32 = fields.take_required("max_value")?.as_integer()?;
+            let value = match fields.take("value") {
+                Some(value) => value.as_integer()?,
+                None => max_value,
+            };
+
+            if value > max_value {
+                return Err(format!(
+                    "value {} is more than max_value {}",
+                    value, max_value
+                ));
+            }
+
+            PrefabComponent::Hitpoints(HitPointComponent {
+                value: value,
+                max_value: max_value,
+            })
+        }
+        "invulnerability" => {
+            let ticks_on_hit = fields.take_required("ticks_on_hit")?.as_integer()?;
+
+            PrefabComponent::Invulnerability(InvulnerabilityComponent::new(ticks_on_hit))
+        }
+        "mesh" => {
+            let mesh = match fields.take_required("mesh")?.as_identifier()? {
+                "Monkey" => Mesh::Monkey,
+                mesh => return Err(format!("Unknown mesh '{}'", mesh)),
+            };
+
+            PrefabComponent::Mesh(MeshComponent::new(mesh))
+        }
+        "move_speed" => {
+            let value = fields.take_required("value")?.as_fixed()?;
+
+            PrefabComponent::MoveSpeed(MoveSpeedComponent::new(value))
+        }
+        "resistances" => {
+            let mut resistances = ResistancesComponent::new();
+
+            for (name, damage_type) in [
+                ("physical", DamageType::Physical),
+                ("bleed", DamageType::Bleed),
+                ("poison", DamageType::Poison),
+            ]
+            .iter()
+            {
+                if let Some(value) = fields.take(name) {
+                    let percentage: u8 = value.as_integer()?;
+                    if percentage > 100 {
+                        return Err(format!(
+                            "{} resistance {} is more than 100",
+                            name, percentage
+                        ));
+                    }
+
+                    resistances.set(*damage_type, percentage);
+                }
+            }
+
+            PrefabComponent::Resistances(resistances)
+        }
+        "stamina" => {
+            let max_value = fields.take_required("max_value")?.as_integer()?;
+            let regen_per_tick = match fields.take("regen_per_tick") {
+                Some(value) => value.as_integer()?,
+                None => 1,
+            };
+
+            PrefabComponent::Stamina(StaminaComponent::new(max_value, regen_per_tick))
+        }
+        "third_person_camera" => {
+            let mut camera = ThirdPersonCameraComponent::new();
+
+            if let Some(value) = fields.take("relative_position") {
+                camera.relative_position = value.as_vec3d()?;
+                camera.default_relative_position = camera.relative_position;
+            }
+            if let Some(value) = fields.take("rotation") {
+                camera.rotation = value.as_rotation()?;
+            }
+
+            PrefabComponent::ThirdPersonCamera(camera)
+        }
+        "transform" => {
+            let mut transform = TransformComponent::new();
+
+            if let Some(value) = fields.take("position") {
+                transform.position = value.as_vec3d()?;
+            }
+            if let Some(value) = fields.take("rotation") {
+                transform.rotation = value.as_rotation()?;
+            }
+
+            PrefabComponent::Transform(transform)
+        }
+        "unit" => {
+            let mut unit = UnitComponent::default();
+
+            {
+                let mut stat = |name: &str, stat: &mut u8| -> Result<(), String> {
+                    if let Some(value) = fields.take(name) {
+                        *stat = value.as_integer()?;
+                    }
+
+                    Ok(())
+                };
+
+                stat("weapon_skill", &mut unit.weapon_skill)?;
+                stat("ballistic_skill", &mut unit.ballistic_skill)?;
+                stat("melee_str", &mut unit.melee_str)?;
+                stat("toughness", &mut unit.toughness)?;
+                stat("wounds", &mut unit.wounds)?;
+                stat("initiative", &mut unit.initiative)?;
+                stat("armor_save", &mut unit.armor_save)?;
+                stat("melee_atks", &mut unit.melee_atks)?;
+                stat("base_size", &mut unit.base_size)?;
+            }
+
+            PrefabComponent::Unit(unit)
+        }
+        "velocity" => {
+            let mut velocity = VelocityComponent::new();
+
+            if let Some(value) = fields.take("value") {
+                velocity.value = value.as_vec3d()?;
+            }
+            if let Some(value) = fields.take("rotational_velocity") {
+                velocity.rotational_velocity = value.as_rotation()?;
+            }
+
+            PrefabComponent::Velocity(velocity)
+        }
+        _ => return Err(String::from("Unknown component")),
+    };
+
+    fields.finish()?;
+
+    Ok(component)
+}
+
+fn build_ailment(mut fields: Fields) -> Result<Ailment, String> {
+    let modifier_type = match fields.take_required("modifier_type")?.as_identifier()? {
+        "Bleed" => AilmentTypes::Bleed,
+        "Poison" => AilmentTypes::Poison,
+        "Curse" => AilmentTypes::Curse,
+        "Sloth" => AilmentTypes::Sloth,
+        "BrainWorms" => AilmentTypes::BrainWorms,
+        ailment => return Err(format!("Unknown ailment '{}'", ailment)),
+    };
+
+    let modifier_multiplier = match fields.take("modifier_multiplier") {
+        Some(value) => value.as_integer()?,
+        None => 1,
+    };
+
+    let status_meter = match fields.take("status_meter") {
+        Some(value) => Range {
+            value: value.as_integer()?,
+        },
+        None => Range { value: 0 },
+    };
+
+    fields.finish()?;
+
+    return Ok(Ailment {
+        modifier_type: modifier_type,
+        modifier_multiplier: modifier_multiplier,
+        status_meter: status_meter,
+        active: false,
+    });
+}
+
+fn parse_direction(name: &str) -> Result<Direction, String> {
+    for index in 0..Direction::size {
+        let direction = Direction::from_index(index);
+
+        if format!("{:?}", direction) == name {
+            return Ok(direction);
+        }
+    }
+
+    Err(format!("Unknown direction '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn PrefabLibrary_loads_defaults() {
+        let mut library = PrefabLibrary::new();
+        library.load(DEFAULT_PREFABS).unwrap();
+
+        assert_eq!(
+            vec!["basic_enemy", "basic_unit", "basic_voxel_chunk", "player"],
+            library.names()
+        );
+    }
+
+    #[test]
+    fn PrefabLibrary_load_values() {
+        let mut library = PrefabLibrary::new();
+        library
+            .load(
+                "
+                // A tougher enemy
+                prefab brute {
+                    enemy {}
+                    transform { position: (1.5, -2, 0), rotation: (0, 0.5, 0) }
+                    hitpoints { max_value: 30 }
+                    unit { toughness: 4, wounds: 3, }
+                    ailments {
+                        ailments: [
+                            { modifier_type: Poison, modifier_multiplier: 2 },
+                            { modifier_type: Sloth, status_meter: 50 },
+                        ]
+                    }
+                }
+                ",
+            )
+            .unwrap();
+
+        let components = library.get("brute").unwrap().components();
+        assert_eq!(5, components.len());
+
+        match &components[1] {
+            PrefabComponent::Transform(transform) => {
+                assert_eq!(FixedNumber::from_bits(6144), transform.position.x);
+                assert_eq!(FixedNumber::from_bits(-8192), transform.position.y);
+                assert_eq!(FixedNumber::from_bits(2048), transform.rotation.yaw_radians);
+            }
+            c => panic!("Unexpected {:?}", c),
+        }
+
+        match &components[2] {
+            PrefabComponent::Hitpoints(hitpoints) => {
+                assert_eq!(30, hitpoints.value);
+                assert_eq!(30, hitpoints.max_value);
+            }
+            c => panic!("Unexpected {:?}", c),
+        }
+
+        match &components[3] {
+            PrefabComponent::Unit(unit) => {
+                assert_eq!(4, unit.toughness);
+                assert_eq!(3, unit.wounds);
+                assert_eq!(1, unit.weapon_skill);
+            }
+            c => panic!("Unexpected {:?}", c),
+        }
+
+        match &components[4] {
+            PrefabComponent::Ailments(ailments) => {
+                assert_eq!(AilmentTypes::Poison, ailments.ailments[0].modifier_type);
+                assert_eq!(2, ailments.ailments[0].modifier_multiplier);
+                assert_eq!(1, ailments.ailments[1].modifier_multiplier);
+                assert_eq!(50, ailments.ailments[1].status_meter.value);
+            }
+            c => panic!("Unexpected {:?}", c),
+        }
+    }
+
+    #[test]
+    fn PrefabLibrary_validates() {
+        let invalid = vec![
+            "prefab a { not_a_component {} }",
+            "prefab a { transform { scale: (1, 1, 1) } }",
+            "prefab a { transform { position: 1 } }",
+            "prefab a { move_speed {} }",
+            "prefab a { facing { direction: Up } }",
+            "prefab a { unit { wounds: 300 } }",
+            "prefab a { unit { wounds: -1 } }",
+            "prefab a { hitpoints { value: 5, max_value: 2 } }",
+            "prefab a { move_speed { value: 9999999 } }",
+            "prefab a { transform {} transform {} }",
+            "prefab a { transform { position: (1, 1, 1), position: (1, 1, 1) } }",
+            "prefab a { ailments { ailments: [{ modifier_type: Gout }] } }",
+            "prefab a { resistances { physical: 150 } }",
+            "prefab a { resistances { fire: 10 } }",
+            "prefab a { invulnerability {} }",
+            "prefab a { stamina { regen_per_tick: 2 } }",
+            "prefab a {} prefab a {}",
+            "prefab a { transform { }",
+        ];
+
+        for source in invalid {
+            let mut library = PrefabLibrary::new();
+
+            assert!(library.load(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn PrefabLibrary_load_is_atomic() {
+        let mut library = PrefabLibrary::new();
+
+        assert!(library
+            .load("prefab a { enemy {} } prefab b { enemy { speed: 1 } }")
+            .is_err());
+        assert_eq!(0, library.len());
+
+        library.load("prefab a { enemy {} }").unwrap();
+        library.load("prefab a { player {} }").unwrap();
+
+        match library.get("a").unwrap().components()[0] {
+            PrefabComponent::Player(_) => {}
+            ref c => panic!("Unexpected {:?}", c),
+        }
+    }
+
+    #[test]
+    fn World_spawn_prefab() {
+        let mut world = World::new();
+        world
+            .load_prefabs("prefab scout { enemy {} move_speed { value: 12 } facing { direction: SouthWest } }")
+            .unwrap();
+
+        let e = world.spawn_prefab("scout").unwrap();
+
+        assert!(world.enemies[e].is_some());
+        let speed: FixedNumber = 12.into();
+        assert_eq!(speed, world.move_speeds[e].as_ref().unwrap().value);
+        assert_eq!(
+            Direction::SouthWest,
+            world.facing_direction[e].as_ref().unwrap().direction
+        );
+        assert!(world.transforms[e].is_none());
+
+        assert!(world.spawn_prefab("missing").is_err());
+
+        world
+            .load_prefabs("prefab knight { resistances { physical: 40, bleed: 100 } invulnerability { ticks_on_hit: 20 } }")
+            .unwrap();
+
+        let e = world.spawn_prefab("knight").unwrap();
+        let resistances = world.resistances[e].as_ref().unwrap();
+
+        assert_eq!(40, resistances.get(DamageType::Physical));
+        assert_eq!(100, resistances.get(DamageType::Bleed));
+        assert_eq!(0, resistances.get(DamageType::Poison));
+        assert_eq!(
+            Some(InvulnerabilityComponent::new(20)),
+            world.invulnerabilities[e]
+        );
+
+        world
+            .load_prefabs("prefab squad { unit { wounds: 3 } } prefab boss { unit { wounds: 3 } hitpoints { max_value: 500 } }")
+            .unwrap();
+
+        let squad = world.spawn_prefab("squad").unwrap();
+        assert_eq!(30, world.hitpoints[squad].as_ref().unwrap().max_value);
+
+        let boss = world.spawn_prefab("boss").unwrap();
+        assert_eq!(500, world.hitpoints[boss].as_ref().unwrap().max_value);
+    }
+
+    #[test]
+    fn World_new_spawns_default_prefabs() {
+        let world = World::new();
+        let entities = world.entities();
+
+        assert_eq!(3, entities.len());
+        assert!(world.players[entities[0]].is_some());
+        assert!(world.third_person_cameras[entities[0]].is_some());
+        assert!(world.enemies[entities[1]].is_some());
+        assert_eq!(
+            Vec3d::new(100.into(), 100.into(), 0.into()),
+            world.transforms[entities[1]].as_ref().unwrap().position
+        );
+        assert!(world.voxel_chunks[entities[2]].is_some());
+        assert_eq!(
+            Some(StaminaComponent::new(100, 1)),
+            world.staminas[entities[0]]
+        );
+    }
+}
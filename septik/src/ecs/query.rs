@@ -0,0 +1,259 @@
+use super::{Entity, SparseStorage, Storage};
+
+/// A set of storages that can be iterated together, yielding only the entities that have every component.
+/// Implemented for `&Storage`, `&mut Storage`, their sparse equivalents, `Maybe`, `Not` and tuples of them.
+pub trait Join: Sized {
+    type Item;
+
+    /// Retrieve the components for the entity, or None if any are missing.
+    ///
+    /// # Safety
+    /// Each entity may only be fetched once while the returned items are alive, as mutable components would otherwise alias.
+    unsafe fn fetch(&mut self, entity: Entity) -> Option<Self::Item>;
+
+    /// Iterate over the given entities that have all components. Entities must be sorted and unique, as returned by `World::entities()`.
+    fn join<'e>(self, entities: &'e [Entity]) -> JoinIter<'e, Self> {
+        return JoinIter {
+            storages: self,
+            entities: entities.iter(),
+            last_index: None,
+        };
+    }
+}
+
+pub struct JoinIter<'e, J: Join> {
+    storages: J,
+    entities: std::slice::Iter<'e, Entity>,
+    last_index: Option<usize>,
+}
+
+impl<'e, J: Join> Iterator for JoinIter<'e, J> {
+    type Item = (Entity, J::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(entity) = self.entities.next() {
+            // Strictly increasing indexes guarantee no slot is fetched twice
+            if let Some(last_index) = self.last_index {
+                assert!(
+                    entity.index() > last_index,
+                    "Joined entities must be sorted and unique"
+                );
+            }
+            self.last_index = Some(entity.index());
+
+            // SAFETY: entities are unique, checked above, so mutable components never alias
+            if let Some(item) = unsafe { self.storages.fetch(*entity) } {
+                return Some((*entity, item));
+            }
+        }
+
+        None
+    }
+}
+
+/// Optionally fetch a component, without filtering out entities that lack it.
+pub struct Maybe<J>(pub J);
+
+impl<J: Join> Join for Maybe<J> {
+    type Item = Option<J::Item>;
+
+    unsafe fn fetch(&mut self, entity: Entity) -> Option<Self::Item> {
+        Some(self.0.fetch(entity))
+    }
+}
+
+/// Only include entities that do not have the component.
+pub struct Not<J>(pub J);
+
+impl<J: Join> Join for Not<J> {
+    type Item = ();
+
+    unsafe fn fetch(&mut self, entity: Entity) -> Option<Self::Item> {
+        match self.0.fetch(entity) {
+            Some(_) => None,
+            None => Some(()),
+        }
+    }
+}
+
+macro_rules! impl_join_for_storage {
+    ($storage:ident) => {
+        impl<'a, T> Join for &'a $storage<T> {
+            type Item = &'a T;
+
+            unsafe fn fetch(&mut self, entity: Entity) -> Option<Self::Item> {
+                let storage: &'a $storage<T> = *self;
+
+                storage[entity].as_ref()
+            }
+        }
+
+        impl<'a, T> Join for &'a mut $storage<T> {
+            type Item = &'a mut T;
+
+            unsafe fn fetch(&mut self, entity: Entity) -> Option<Self::Item> {
+                // SAFETY: the slot pointer is valid for 'a as the storage can't grow while borrowed, and the caller guarantees it is only handed out once
+                let slot = self.slot_ptr(entity)?;
+
+                (*slot).as_mut()
+            }
+        }
+    };
+}
+
+impl_join_for_storage!(Storage);
+impl_join_for_storage!(SparseStorage);
+
+macro_rules! impl_join_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Join),+> Join for ($($name,)+) {
+            type Item = ($($name::Item,)+);
+
+            #[allow(non_snake_case)]
+            unsafe fn fetch(&mut self, entity: Entity) -> Option<Self::Item> {
+                let ($($name,)+) = self;
+
+                Some(($($name.fetch(entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_join_for_tuple!(A);
+impl_join_for_tuple!(A, B);
+impl_join_for_tuple!(A, B, C);
+impl_join_for_tuple!(A, B, C, D);
+impl_join_for_tuple!(A, B, C, D, E);
+impl_join_for_tuple!(A, B, C, D, E, F);
+impl_join_for_tuple!(A, B, C, D, E, F, G);
+impl_join_for_tuple!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities(count: usize) -> Vec<Entity> {
+        (0..count).map(|i| Entity::new(i, 0)).collect()
+    }
+
+    #[test]
+    fn Join_single_storage() {
+        let entities = entities(4);
+        let mut numbers = Storage::new();
+        numbers[entities[1]] = Some(1);
+        numbers[entities[3]] = Some(3);
+
+        let joined: Vec<(Entity, &i32)> = (&numbers).join(&entities).collect();
+
+        assert_eq!(vec![(entities[1], &1), (entities[3], &3)], joined);
+    }
+
+    #[test]
+    fn Join_only_entities_with_all_components() {
+        let entities = entities(4);
+        let mut numbers = Storage::new();
+        let mut names = SparseStorage::new();
+        numbers[entities[0]] = Some(0);
+        numbers[entities[2]] = Some(2);
+        names[entities[2]] = Some("two");
+        names[entities[3]] = Some("three");
+
+        let joined: Vec<(Entity, (&i32, &&str))> = (&numbers, &names).join(&entities).collect();
+
+        assert_eq!(vec![(entities[2], (&2, &"two"))], joined);
+    }
+
+    #[test]
+    fn Join_mutable() {
+        let entities = entities(3);
+        let mut numbers = Storage::new();
+        let mut offsets = Storage::new();
+        for e in entities.iter() {
+            numbers[*e] = Some(1);
+            offsets[*e] = Some(e.index() as i32);
+        }
+
+        for (_, (number, offset)) in (&mut numbers, &offsets).join(&entities) {
+            *number += *offset;
+        }
+
+        assert_eq!(Some(1), numbers[entities[0]]);
+        assert_eq!(Some(2), numbers[entities[1]]);
+        assert_eq!(Some(3), numbers[entities[2]]);
+    }
+
+    #[test]
+    fn Join_mutable_references_are_held() {
+        let entities = entities(3);
+        let mut numbers = SparseStorage::new();
+        for e in entities.iter() {
+            numbers[*e] = Some(0);
+        }
+
+        let mut references: Vec<&mut i32> =
+            (&mut numbers).join(&entities).map(|(_, n)| n).collect();
+        for (i, number) in references.iter_mut().enumerate() {
+            **number = i as i32;
+        }
+
+        assert_eq!(Some(2), numbers[entities[2]]);
+    }
+
+    #[test]
+    fn Join_maybe() {
+        let entities = entities(3);
+        let mut numbers = Storage::new();
+        let mut names = Storage::new();
+        for e in entities.iter() {
+            numbers[*e] = Some(e.index());
+        }
+        names[entities[1]] = Some("one");
+
+        let joined: Vec<(usize, Option<&&str>)> = (&numbers, Maybe(&names))
+            .join(&entities)
+            .map(|(_, (number, name))| (*number, name))
+            .collect();
+
+        assert_eq!(vec![(0, None), (1, Some(&"one")), (2, None)], joined);
+    }
+
+    #[test]
+    fn Join_not() {
+        let entities = entities(3);
+        let mut numbers = Storage::new();
+        let mut excluded = Storage::new();
+        for e in entities.iter() {
+            numbers[*e] = Some(e.index());
+        }
+        excluded[entities[1]] = Some(());
+
+        let joined: Vec<usize> = (&numbers, Not(&excluded))
+            .join(&entities)
+            .map(|(_, (number, _))| *number)
+            .collect();
+
+        assert_eq!(vec![0, 2], joined);
+    }
+
+    #[test]
+    fn Join_skips_stale_entities() {
+        let mut numbers = Storage::new();
+        numbers[Entity::new(0, 1)] = Some(1);
+
+        let stale = vec![Entity::new(0, 0)];
+
+        assert_eq!(0, (&mut numbers).join(&stale).count());
+    }
+
+    #[test]
+    #[should_panic]
+    fn Join_duplicate_entities_panics() {
+        let mut numbers = Storage::new();
+        let e = Entity::new(0, 0);
+        numbers[e] = Some(1);
+
+        let duplicates = vec![e, e];
+
+        let _: Vec<(Entity, &mut i32)> = (&mut numbers).join(&duplicates).collect();
+    }
+}
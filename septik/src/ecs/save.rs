@@ -0,0 +1,665 @@
+use super::{
+    components::{
+        gfx_components::{Mesh, MeshComponent},
+        unit_components::UnitComponent,
+        AabbComponent, AiComponent, Ailment, AilmentTypes, AilmentsComponent, AttackComponent,
+        AttackType, DamageType, DeadComponent, DodgeComponent, EnemyComponent,
+        EngineInputsComponent, FacingComponent, GdNodeComponent, HitboxComponent,
+        InvulnerabilityComponent, MoveSpeedComponent, OverwatchArea, OverwatchComponent,
+        PlayerComponent, ResistancesComponent, StaminaComponent, TargetComponent,
+        TargetableComponent, ThirdPersonCameraComponent, TransformComponent, VelocityComponent,
+        VoxelChunkComponent,
+    },
+    events::{AilmentTriggered, DamageDealt, EntityDied, Events, InputAction, OverwatchTriggered},
+    PrefabComponent, World,
+};
+use crate::lib_core::serialization::{SaveReader, SaveWriter, Serializable};
+
+use std::fmt::Debug;
+
+use time::Duration;
+
+/// Identifies the data as a saved world
+const MAGIC: [u8; 4] = *b"SPTK";
+
+/// The version of the save format. Bump this when the layout changes, and read older layouts by checking `SaveReader::version`.
+pub const SAVE_VERSION: u16 = 9;
+
+/// Fixes up a world loaded from an older save, such as filling in components that didn't exist yet
+pub type Migration = fn(&mut World) -> Result<(), String>;
+
+/// Migrations run in order after loading an older save, paired with the version that introduced them.
+/// Each is run if the save is older than its version.
+const MIGRATIONS: &[(u16, Migration)] = &[(7, give_players_stamina)];
+
+/// Implement `Serializable` for a struct by writing each of its fields in order
+macro_rules! serializable_struct {
+    ($($ty:ident { $($field:ident),* };)*) => {
+        $(
+            impl Serializable for $ty {
+                #[allow(unused_variables)]
+                fn serialize(&self, writer: &mut SaveWriter) {
+                    $(
+                        writer.write(&self.$field);
+                    )*
+                }
+
+                #[allow(unused_variables)]
+                fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+                    return Ok(Self {
+                        $(
+                            $field: reader.read()?,
+                        )*
+                    });
+                }
+            }
+        )*
+    };
+}
+
+serializable_struct!(
+    ThirdPersonCameraComponent { relative_position, default_relative_position, rotation };
+    VoxelChunkComponent { chunk };
+    TargetComponent { entity };
+    AabbComponent { aabb };
+    AiComponent {};
+    EnemyComponent {};
+    TargetableComponent {};
+    GdNodeComponent { id };
+    MoveSpeedComponent { value };
+    VelocityComponent { value, rotational_velocity };
+    TransformComponent { position, rotation };
+    PlayerComponent {};
+    FacingComponent { direction };
+    EngineInputsComponent { inputs };
+    AilmentsComponent { ailments };
+    MeshComponent { mesh };
+    InvulnerabilityComponent { remaining_ticks, ticks_on_hit };
+    DeadComponent { killer };
+    UnitComponent {
+        weapon_skill,
+        ballistic_skill,
+        melee_str,
+        toughness,
+        wounds,
+        initiative,
+        armor_save,
+        melee_atks,
+        base_size
+    };
+    EntityDied { entity, killer };
+    AilmentTriggered { entity, ailment };
+    InputAction { entity, action };
+    OverwatchComponent { area, shots, strength };
+    AttackComponent {
+        attack_type,
+        elapsed,
+        startup_frames,
+        active_frames,
+        recovery_frames,
+        hit,
+        hitbox
+    };
+    HitboxComponent { owner };
+    StaminaComponent { meter, regen_per_tick };
+    DodgeComponent { direction, remaining_ticks, cooldown_ticks };
+    OverwatchTriggered { watcher, target, wounds_removed, slain };
+);
+
+impl Serializable for AilmentTypes {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&(self.index() as u8));
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(AilmentTypes::Bleed),
+            1 => Ok(AilmentTypes::Poison),
+            2 => Ok(AilmentTypes::Curse),
+            3 => Ok(AilmentTypes::Sloth),
+            4 => Ok(AilmentTypes::BrainWorms),
+            index => Err(format!("Invalid ailment {}", index)),
+        }
+    }
+}
+
+impl Serializable for Ailment {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.modifier_type);
+        writer.write(&self.modifier_multiplier);
+        writer.write(&self.status_meter);
+        writer.write(&self.active);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let modifier_type = reader.read()?;
+        let modifier_multiplier = reader.read()?;
+        let status_meter = reader.read()?;
+
+        // Version 1 didn't track lasting effects
+        let active = if reader.version() >= 2 {
+            reader.read()?
+        } else {
+            false
+        };
+
+        return Ok(Self {
+            modifier_type: modifier_type,
+            modifier_multiplier: modifier_multiplier,
+            status_meter: status_meter,
+            active: active,
+        });
+    }
+}
+
+impl Serializable for DamageType {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&(self.index() as u8));
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(DamageType::Physical),
+            1 => Ok(DamageType::Bleed),
+            2 => Ok(DamageType::Poison),
+            index => Err(format!("Invalid damage type {}", index)),
+        }
+    }
+}
+
+impl Serializable for ResistancesComponent {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        for percentage in self.percentages.iter() {
+            writer.write(percentage);
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let mut resistances = Self::new();
+
+        for percentage in resistances.percentages.iter_mut() {
+            *percentage = reader.read()?;
+        }
+
+        Ok(resistances)
+    }
+}
+
+impl Serializable for DamageDealt {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.source);
+        writer.write(&self.target);
+        writer.write(&self.amount);
+        writer.write(&self.damage_type);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let source = reader.read()?;
+        let target = reader.read()?;
+        let amount = reader.read()?;
+
+        // Version 2 and earlier didn't have damage types
+        let damage_type = if reader.version() >= 3 {
+            reader.read()?
+        } else {
+            DamageType::Physical
+        };
+
+        return Ok(Self {
+            source: source,
+            target: target,
+            amount: amount,
+            damage_type: damage_type,
+        });
+    }
+}
+
+impl Serializable for OverwatchArea {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        match self {
+            OverwatchArea::Cone { direction, range } => {
+                writer.write(&0u8);
+                writer.write(direction);
+                writer.write(range);
+            }
+            OverwatchArea::Area { center, radius } => {
+                writer.write(&1u8);
+                writer.write(center);
+                writer.write(radius);
+            }
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(OverwatchArea::Cone {
+                direction: reader.read()?,
+                range: reader.read()?,
+            }),
+            1 => Ok(OverwatchArea::Area {
+                center: reader.read()?,
+                radius: reader.read()?,
+            }),
+            tag => Err(format!("Invalid overwatch area {}", tag)),
+        }
+    }
+}
+
+impl Serializable for Events {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.damage_dealt);
+        writer.write(&self.entity_died);
+        writer.write(&self.ailment_triggered);
+        writer.write(&self.input_action);
+        writer.write(&self.overwatch_triggered);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let mut events = Self::new();
+
+        events.damage_dealt = reader.read()?;
+        events.entity_died = reader.read()?;
+        events.ailment_triggered = reader.read()?;
+        events.input_action = reader.read()?;
+
+        // Version 4 and earlier didn't have overwatch
+        if reader.version() >= 5 {
+            events.overwatch_triggered = reader.read()?;
+        }
+
+        return Ok(events);
+    }
+}
+
+impl Serializable for AttackType {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        let tag: u8 = match self {
+            AttackType::Horizontal => 0,
+            AttackType::Vertical => 1,
+        };
+
+        writer.write(&tag);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(AttackType::Horizontal),
+            1 => Ok(AttackType::Vertical),
+            tag => Err(format!("Invalid attack type {}", tag)),
+        }
+    }
+}
+
+impl Serializable for Mesh {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        let tag: u8 = match self {
+            Mesh::Monkey => 0,
+        };
+
+        writer.write(&tag);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(Mesh::Monkey),
+            tag => Err(format!("Invalid mesh {}", tag)),
+        }
+    }
+}
+
+impl World {
+    /// Serialize the simulation state and tick rate to the binary save format. Systems, the clock and the snapshot history are not saved.
+    pub fn save(&self) -> Vec<u8> {
+        let mut writer = SaveWriter::new();
+
+        writer.write_bytes(&MAGIC);
+        writer.write(&SAVE_VERSION);
+
+        writer.write(&self.tick);
+        writer.write(&(self.frame_duration.whole_nanoseconds() as i64));
+        writer.write(&self.entity_allocator);
+        writer.write(&self.deleted_entities);
+        writer.write(&self.rng);
+        writer.write(&self.events);
+        writer.write(&self.turns);
+        self.write_components(&mut writer, 1..=SAVE_VERSION);
+
+        writer.into_bytes()
+    }
+
+    /// Create a world from a save, migrating it if it was made by an older version
+    pub fn load(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = SaveReader::new(bytes, SAVE_VERSION);
+
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(String::from("Not a save file"));
+        }
+
+        let version: u16 = reader.read()?;
+        if version == 0 || version > SAVE_VERSION {
+            return Err(format!(
+                "Unsupported save version {}, expected at most {}",
+                version, SAVE_VERSION
+            ));
+        }
+        reader.set_version(version);
+
+        // Start empty, so nothing from a scenario is left over
+        let mut world = Self::builder().build()?;
+
+        world.tick = reader.read()?;
+
+        // Version 8 and earlier didn't store the tick rate, so the default is used
+        if version >= 9 {
+            let nanoseconds: i64 = reader.read()?;
+            if nanoseconds <= 0 {
+                return Err(format!("Invalid frame duration {}ns", nanoseconds));
+            }
+
+            world.frame_duration = Duration::nanoseconds(nanoseconds);
+        }
+
+        world.entity_allocator = reader.read()?;
+        world.deleted_entities = reader.read()?;
+        world.rng = reader.read()?;
+        world.events = reader.read()?;
+
+        if version >= 8 {
+            world.turns = reader.read()?;
+            world.read_components(&mut reader, 1..=SAVE_VERSION)?;
+        } else {
+            // The turn state used to sit between the storages added in versions 3 and 5
+            world.read_components(&mut reader, 1..=3)?;
+            if version >= 4 {
+                world.turns = reader.read()?;
+            }
+            world.read_components(&mut reader, 5..=SAVE_VERSION)?;
+        }
+
+        if reader.remaining() != 0 {
+            return Err(format!(
+                "{} unexpected bytes at the end of the save",
+                reader.remaining()
+            ));
+        }
+
+        for (migration_version, migration) in MIGRATIONS {
+            if version < *migration_version {
+                migration(&mut world)?;
+            }
+        }
+
+        Ok(world)
+    }
+
+    /// Human readable dump of the world's state in a RON like format, for debugging. Can't be loaded back.
+    pub fn dump(&self) -> String {
+        let mut dump = String::new();
+
+        dump.push_str("World(\n");
+        dump.push_str(&format!("    version: {},\n", SAVE_VERSION));
+        dump.push_str(&format!("    tick: {},\n", self.tick));
+        dump.push_str(&format!("    rng: {:?},\n", self.rng));
+        dump.push_str(&format!("    turns: {:?},\n", self.turns));
+        dump.push_str("    entities: [\n");
+
+        for entity in self.entities() {
+            dump.push_str("        (\n");
+            dump.push_str(&format!("            entity: {:?},\n", entity));
+
+            self.dump_components(&mut dump, entity);
+
+            dump.push_str("        ),\n");
+        }
+
+        dump.push_str("    ],\n)\n");
+
+        dump
+    }
+}
+
+/// Version 7 added stamina, which players need to dodge. Give them the stamina of the player prefab.
+fn give_players_stamina(world: &mut World) -> Result<(), String> {
+    let stamina = world
+        .prefabs()
+        .get("player")
+        .and_then(|prefab| {
+            prefab
+                .components()
+                .iter()
+                .find_map(|component| match component {
+                    PrefabComponent::Stamina(stamina) => Some(stamina.clone()),
+                    _ => None,
+                })
+        })
+        .ok_or(String::from("The player prefab has no stamina"))?;
+
+    for e in world.entities() {
+        if world.players[e].is_some() && world.staminas[e].is_none() {
+            world.staminas[e] = Some(stamina.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a single line for the component, if the entity has one
+pub(super) fn dump_component<T: Debug>(dump: &mut String, name: &str, component: &Option<T>) {
+    if let Some(component) = component {
+        dump.push_str(&format!("            {}: {:?},\n", name, component));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::Entity;
+    use crate::lib_core::{math::Range, EngineInputs, InputType};
+
+    fn populated_world() -> World {
+        let mut world = World::new();
+        let inputs = vec![InputType::Held(0, EngineInputs::MoveForward)];
+
+        for _ in 0..3 {
+            world.register_player_inputs(&inputs);
+            world.step();
+        }
+
+        let unit = world.add_entity();
+        world.units[unit] = Some(UnitComponent::default());
+        world.ailments[unit] = Some(AilmentsComponent {
+            ailments: vec![Ailment {
+                modifier_type: AilmentTypes::Poison,
+                modifier_multiplier: 2,
+                status_meter: Range { value: 40 },
+                active: true,
+            }],
+        });
+
+        let mut chunk = VoxelChunkComponent::new();
+        chunk.chunk.voxels[3][2][1].active = false;
+        world.voxel_chunks[unit] = Some(chunk);
+
+        let mut resistances = ResistancesComponent::new();
+        resistances.set(DamageType::Bleed, 25);
+        world.resistances[unit] = Some(resistances);
+        world.invulnerabilities[unit] = Some(InvulnerabilityComponent {
+            remaining_ticks: 4,
+            ticks_on_hit: 10,
+        });
+
+        world.overwatches[unit] = Some(OverwatchComponent::new(
+            OverwatchArea::Cone {
+                direction: crate::lib_core::Direction::West,
+                range: 12.into(),
+            },
+            2,
+            3,
+        ));
+        // Players stay in the world when dead
+        let player = world.entities()[0];
+        world.dead[player] = Some(DeadComponent { killer: Some(unit) });
+
+        let child = world.add_entity();
+        world.transforms[child] = Some(TransformComponent::new());
+        world.attach(child, unit).unwrap();
+
+        world.events.entity_died.publish(EntityDied {
+            entity: child,
+            killer: Some(unit),
+        });
+        world
+            .events
+            .overwatch_triggered
+            .publish(OverwatchTriggered {
+                watcher: unit,
+                target: child,
+                wounds_removed: 1,
+                slain: false,
+            });
+
+        let deleted = world.add_entity();
+        world.delete_entity(deleted);
+        world.step();
+
+        world
+    }
+
+    #[test]
+    fn World_save_and_load() {
+        let world = populated_world();
+        let bytes = world.save();
+
+        let loaded = World::load(&bytes).unwrap();
+
+        assert_eq!(world.tick(), loaded.tick());
+        assert_eq!(world.checksum(), loaded.checksum());
+        assert_eq!(world.entities(), loaded.entities());
+        assert_eq!(
+            world.events.entity_died.read(),
+            loaded.events.entity_died.read()
+        );
+        assert_eq!(bytes, loaded.save());
+    }
+
+    #[test]
+    fn World_save_keeps_tick_rate() {
+        let world = World::builder().tick_rate(20).build().unwrap();
+
+        let loaded = World::load(&world.save()).unwrap();
+
+        assert_eq!(Duration::milliseconds(50), loaded.frame_duration());
+    }
+
+    #[test]
+    fn World_loaded_world_simulates_the_same() {
+        let mut world = populated_world();
+        let mut loaded = World::load(&world.save()).unwrap();
+        let inputs = vec![InputType::Held(0, EngineInputs::MoveLeft)];
+
+        for _ in 0..5 {
+            world.register_player_inputs(&inputs);
+            world.step();
+
+            loaded.register_player_inputs(&inputs);
+            loaded.step();
+
+            assert_eq!(world.checksum(), loaded.checksum());
+        }
+    }
+
+    #[test]
+    fn Ailment_reads_version_1() {
+        let mut writer = SaveWriter::new();
+        writer.write(&AilmentTypes::Sloth);
+        writer.write(&3u8);
+        writer.write(&Range { value: 7 });
+
+        let bytes = writer.into_bytes();
+        let ailment: Ailment = SaveReader::new(&bytes, 1).read().unwrap();
+
+        assert_eq!(AilmentTypes::Sloth, ailment.modifier_type);
+        assert_eq!(3, ailment.modifier_multiplier);
+        assert_eq!(7, ailment.status_meter.value);
+        assert_eq!(false, ailment.active);
+    }
+
+    #[test]
+    fn DamageDealt_reads_version_2() {
+        let mut world = World::builder().build().unwrap();
+        let target = world.add_entity();
+
+        let mut writer = SaveWriter::new();
+        writer.write(&None::<Entity>);
+        writer.write(&target);
+        writer.write(&6u32);
+
+        let bytes = writer.into_bytes();
+        let event: DamageDealt = SaveReader::new(&bytes, 2).read().unwrap();
+
+        assert_eq!(target, event.target);
+        assert_eq!(6, event.amount);
+        assert_eq!(DamageType::Physical, event.damage_type);
+    }
+
+    #[test]
+    fn World_load_migrates_version_6() {
+        let world = populated_world();
+        let player = world.player().unwrap();
+
+        // Version 6 stored the turn state between the storages and had no stamina or dodges
+        let mut writer = SaveWriter::new();
+        writer.write_bytes(&MAGIC);
+        writer.write(&6u16);
+        writer.write(&world.tick);
+        writer.write(&world.entity_allocator);
+        writer.write(&world.deleted_entities);
+        writer.write(&world.rng);
+        writer.write(&world.events);
+        world.write_components(&mut writer, 1..=3);
+        writer.write(&world.turns);
+        world.write_components(&mut writer, 5..=6);
+
+        let loaded = World::load(&writer.into_bytes()).unwrap();
+
+        assert_eq!(Some(StaminaComponent::new(100, 1)), world.staminas[player]);
+        assert!(world.dodges[player].is_none());
+
+        // The player gets back the stamina the save couldn't hold, so nothing else differs
+        assert_eq!(world.checksum(), loaded.checksum());
+        assert_eq!(Some(StaminaComponent::new(100, 1)), loaded.staminas[player]);
+    }
+
+    #[test]
+    fn World_load_rejects_bad_saves() {
+        let bytes = populated_world().save();
+
+        assert!(World::load(&[]).is_err());
+        assert!(World::load(b"NOPE\x01\x00").is_err());
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(SAVE_VERSION + 1).to_le_bytes());
+        assert!(World::load(&newer).is_err());
+
+        assert!(World::load(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(World::load(&extra).is_err());
+    }
+
+    #[test]
+    fn World_dump() {
+        let world = populated_world();
+        let dump = world.dump();
+
+        assert!(dump.starts_with("World(\n"));
+        assert!(dump.contains(&format!("tick: {},", world.tick())));
+        assert!(dump.contains("unit: UnitComponent { weapon_skill: 1,"));
+        assert!(dump.contains("modifier_type: Poison"));
+        assert_eq!(
+            world.entities().len(),
+            dump.matches("entity: Entity").count()
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use super::World;
+
+use time::{Duration, Instant};
+
+pub type System = fn(&mut World);
+
+/// The stages systems run in, in order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Input,
+    Logic,
+    Physics,
+    Cleanup,
+}
+
+impl Stage {
+    pub const all: [Stage; 4] = [Stage::Input, Stage::Logic, Stage::Physics, Stage::Cleanup];
+}
+
+/// A system to register, along with where it should run.
+pub struct SystemDescriptor {
+    name: &'static str,
+    stage: Stage,
+    system: System,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+}
+
+impl SystemDescriptor {
+    pub fn new(name: &'static str, stage: Stage, system: System) -> Self {
+        return Self {
+            name: name,
+            stage: stage,
+            system: system,
+            before: vec![],
+            after: vec![],
+        };
+    }
+
+    /// Run this system before the named one. Ignored if the named system isn't registered.
+    pub fn before(mut self, name: &'static str) -> Self {
+        self.before.push(name);
+        self
+    }
+
+    /// Run this system after the named one. Ignored if the named system isn't registered.
+    pub fn after(mut self, name: &'static str) -> Self {
+        self.after.push(name);
+        self
+    }
+}
+
+struct SystemEntry {
+    descriptor: SystemDescriptor,
+    enabled: bool,
+    last_duration: Duration,
+}
+
+/// Runs systems by stage, respecting the before/after constraints between them. Systems within a stage with no constraints run in the order they were added.
+pub struct Scheduler {
+    systems: Vec<SystemEntry>,
+    order: Vec<usize>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        return Self {
+            systems: vec![],
+            order: vec![],
+        };
+    }
+
+    /// Register a system. Fails if the name is taken or the constraints can't be satisfied.
+    pub fn add_system(&mut self, descriptor: SystemDescriptor) -> Result<(), String> {
+        if self.index_of(descriptor.name).is_some() {
+            return Err(format!(
+                "A system named '{}' already exists",
+                descriptor.name
+            ));
+        }
+
+        self.systems.push(SystemEntry {
+            descriptor: descriptor,
+            enabled: true,
+            last_duration: Duration::zero(),
+        });
+
+        match self.build_order() {
+            Ok(order) => {
+                self.order = order;
+                Ok(())
+            }
+            Err(e) => {
+                self.systems.pop();
+                Err(e)
+            }
+        }
+    }
+
+    /// Enable or disable a system at runtime
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        match self.index_of(name) {
+            Some(index) => {
+                self.systems[index].enabled = enabled;
+                Ok(())
+            }
+            None => Err(format!("No system named '{}'", name)),
+        }
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        match self.index_of(name) {
+            Some(index) => self.systems[index].enabled,
+            None => false,
+        }
+    }
+
+    /// The names of the systems, in the order they run
+    pub fn order(&self) -> Vec<&'static str> {
+        self.order
+            .iter()
+            .map(|i| self.systems[*i].descriptor.name)
+            .collect()
+    }
+
+    /// How long each system took the last time it ran, in the order they run
+    pub fn timings(&self) -> Vec<(&'static str, Duration)> {
+        self.order
+            .iter()
+            .map(|i| {
+                let system = &self.systems[*i];
+                (system.descriptor.name, system.last_duration)
+            })
+            .collect()
+    }
+
+    /// Run all enabled systems
+    pub fn run(&mut self, world: &mut World) {
+        for i in 0..self.order.len() {
+            let system = &mut self.systems[self.order[i]];
+
+            if !system.enabled {
+                continue;
+            }
+
+            let start = Instant::now();
+            (system.descriptor.system)(world);
+            system.last_duration = Instant::now() - start;
+        }
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.systems
+            .iter()
+            .position(|system| system.descriptor.name == name)
+    }
+
+    /// Sort the systems by stage, then topologically within each stage
+    fn build_order(&self) -> Result<Vec<usize>, String> {
+        // Edges from systems that must run first to the systems that must run after
+        let mut edges = vec![];
+
+        for (index, system) in self.systems.iter().enumerate() {
+            for name in system.descriptor.before.iter() {
+                if let Some(other) = self.index_of(name) {
+                    edges.push((index, other));
+                }
+            }
+
+            for name in system.descriptor.after.iter() {
+                if let Some(other) = self.index_of(name) {
+                    edges.push((other, index));
+                }
+            }
+        }
+
+        for (first, second) in edges.iter() {
+            let first = &self.systems[*first].descriptor;
+            let second = &self.systems[*second].descriptor;
+
+            if first.stage > second.stage {
+                return Err(format!(
+                    "'{}' in {:?} can't run before '{}' in {:?}",
+                    first.name, first.stage, second.name, second.stage
+                ));
+            }
+        }
+
+        let mut order = vec![];
+
+        for stage in Stage::all.iter() {
+            let mut remaining: Vec<usize> = (0..self.systems.len())
+                .filter(|i| self.systems[*i].descriptor.stage == *stage)
+                .collect();
+
+            while remaining.len() > 0 {
+                // Take the first system in registration order that has nothing left to wait on
+                let next = remaining.iter().position(|candidate| {
+                    !edges
+                        .iter()
+                        .any(|(first, second)| second == candidate && remaining.contains(first))
+                });
+
+                match next {
+                    Some(position) => order.push(remaining.remove(position)),
+                    None => {
+                        let names: Vec<&str> = remaining
+                            .iter()
+                            .map(|i| self.systems[*i].descriptor.name)
+                            .collect();
+
+                        return Err(format!(
+                            "Cyclic system ordering in {:?} between {:?}",
+                            stage, names
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_world: &mut World) {}
+
+    fn spawn(world: &mut World) {
+        world.add_entity();
+    }
+
+    fn scheduler(descriptors: Vec<SystemDescriptor>) -> Scheduler {
+        let mut scheduler = Scheduler::new();
+
+        for descriptor in descriptors {
+            scheduler.add_system(descriptor).unwrap();
+        }
+
+        scheduler
+    }
+
+    #[test]
+    fn Scheduler_orders_by_stage() {
+        let scheduler = scheduler(vec![
+            SystemDescriptor::new("cleanup", Stage::Cleanup, noop),
+            SystemDescriptor::new("physics", Stage::Physics, noop),
+            SystemDescriptor::new("input", Stage::Input, noop),
+            SystemDescriptor::new("logic", Stage::Logic, noop),
+        ]);
+
+        assert_eq!(
+            vec!["input", "logic", "physics", "cleanup"],
+            scheduler.order()
+        );
+    }
+
+    #[test]
+    fn Scheduler_keeps_registration_order_within_stage() {
+        let scheduler = scheduler(vec![
+            SystemDescriptor::new("a", Stage::Logic, noop),
+            SystemDescriptor::new("b", Stage::Logic, noop),
+            SystemDescriptor::new("c", Stage::Logic, noop),
+        ]);
+
+        assert_eq!(vec!["a", "b", "c"], scheduler.order());
+    }
+
+    #[test]
+    fn Scheduler_before_and_after() {
+        let scheduler = scheduler(vec![
+            SystemDescriptor::new("a", Stage::Logic, noop).after("c"),
+            SystemDescriptor::new("b", Stage::Logic, noop),
+            SystemDescriptor::new("c", Stage::Logic, noop),
+            SystemDescriptor::new("d", Stage::Logic, noop).before("b"),
+            SystemDescriptor::new("e", Stage::Logic, noop).after("missing"),
+        ]);
+
+        assert_eq!(vec!["c", "a", "d", "b", "e"], scheduler.order());
+    }
+
+    #[test]
+    fn Scheduler_duplicate_name() {
+        let mut scheduler = scheduler(vec![SystemDescriptor::new("a", Stage::Logic, noop)]);
+
+        assert!(scheduler
+            .add_system(SystemDescriptor::new("a", Stage::Input, noop))
+            .is_err());
+        assert_eq!(vec!["a"], scheduler.order());
+    }
+
+    #[test]
+    fn Scheduler_cycle() {
+        let mut scheduler = scheduler(vec![
+            SystemDescriptor::new("a", Stage::Logic, noop).after("b")
+        ]);
+
+        let result =
+            scheduler.add_system(SystemDescriptor::new("b", Stage::Logic, noop).after("a"));
+
+        assert!(result.is_err());
+        assert_eq!(vec!["a"], scheduler.order());
+    }
+
+    #[test]
+    fn Scheduler_constraint_across_stages() {
+        let mut scheduler = scheduler(vec![
+            SystemDescriptor::new("input", Stage::Input, noop),
+            SystemDescriptor::new("logic", Stage::Logic, noop).after("input"),
+        ]);
+
+        assert!(scheduler
+            .add_system(SystemDescriptor::new("cleanup", Stage::Cleanup, noop).before("logic"))
+            .is_err());
+    }
+
+    #[test]
+    fn Scheduler_enable_and_disable() {
+        let mut world = World::new();
+        let entity_count = world.entities().len();
+        let mut scheduler = scheduler(vec![SystemDescriptor::new("spawn", Stage::Logic, spawn)]);
+
+        scheduler.set_enabled("spawn", false).unwrap();
+        scheduler.run(&mut world);
+
+        assert_eq!(false, scheduler.is_enabled("spawn"));
+        assert_eq!(entity_count, world.entities().len());
+
+        scheduler.set_enabled("spawn", true).unwrap();
+        scheduler.run(&mut world);
+
+        assert_eq!(true, scheduler.is_enabled("spawn"));
+        assert_eq!(entity_count + 1, world.entities().len());
+        assert!(scheduler.set_enabled("missing", true).is_err());
+    }
+
+    #[test]
+    fn Scheduler_timings() {
+        let mut world = World::new();
+        let mut scheduler = scheduler(vec![
+            SystemDescriptor::new("spawn", Stage::Logic, spawn),
+            SystemDescriptor::new("noop", Stage::Input, noop),
+        ]);
+
+        scheduler.run(&mut world);
+
+        let timings = scheduler.timings();
+        assert_eq!("noop", timings[0].0);
+        assert_eq!("spawn", timings[1].0);
+        assert!(timings
+            .iter()
+            .all(|(_, duration)| *duration >= Duration::zero()));
+    }
+}
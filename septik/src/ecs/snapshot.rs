@@ -0,0 +1,374 @@
+use super::{events::Events, ComponentStorages, Entity, EntityAllocator, TurnState, World};
+use crate::lib_core::math::Rng;
+
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+/// FNV-1a hasher. Unlike the std hasher, it is not randomly seeded and hashes integers the same on every platform, so checksums can be compared across machines.
+pub struct ChecksumHasher {
+    hash: u64,
+}
+
+impl ChecksumHasher {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    pub fn new() -> Self {
+        return Self {
+            hash: Self::OFFSET_BASIS,
+        };
+    }
+}
+
+impl Hasher for ChecksumHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    // Integers are always written as little endian, and usizes as u64s, so the hash doesn't depend on the platform
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
+
+/// A copy of all simulation state in the world at the end of a tick. Systems, the clock and the backends are not included.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    tick: u64,
+    checksum: u64,
+    entity_allocator: EntityAllocator,
+    deleted_entities: Vec<Entity>,
+    events: Events,
+    rng: Rng,
+    turns: Option<TurnState>,
+    components: ComponentStorages,
+}
+
+impl WorldSnapshot {
+    /// The tick the snapshot was taken on
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The checksum of the world when the snapshot was taken
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+}
+
+/// Ring buffer of the snapshots for the most recent ticks.
+pub struct SnapshotHistory {
+    capacity: usize,
+    snapshots: VecDeque<WorldSnapshot>,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> Self {
+        return Self {
+            capacity: capacity,
+            snapshots: VecDeque::with_capacity(capacity),
+        };
+    }
+
+    /// Add a snapshot, dropping the oldest one if full
+    pub fn push(&mut self, snapshot: WorldSnapshot) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Retrieve the snapshot for the tick, if it is still held
+    pub fn get(&self, tick: u64) -> Option<&WorldSnapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.tick == tick)
+    }
+
+    pub fn latest(&self) -> Option<&WorldSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Drop any snapshots after the tick, as they are no longer valid after rolling back
+    pub fn truncate_after(&mut self, tick: u64) {
+        while let Some(snapshot) = self.snapshots.back() {
+            if snapshot.tick <= tick {
+                break;
+            }
+
+            self.snapshots.pop_back();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+}
+
+impl World {
+    /// Copy the current state of the world
+    pub fn snapshot(&self) -> WorldSnapshot {
+        return WorldSnapshot {
+            tick: self.tick,
+            checksum: self.checksum(),
+            entity_allocator: self.entity_allocator.clone(),
+            deleted_entities: self.deleted_entities.clone(),
+            events: self.events.clone(),
+            rng: self.rng,
+            turns: self.turns.clone(),
+            components: self.copy_components(),
+        };
+    }
+
+    /// Reset the world to the snapshot's state
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+        let snapshot = snapshot.clone();
+
+        self.tick = snapshot.tick;
+        self.entity_allocator = snapshot.entity_allocator;
+        self.deleted_entities = snapshot.deleted_entities;
+        self.events = snapshot.events;
+        self.rng = snapshot.rng;
+        self.turns = snapshot.turns;
+        self.restore_components(snapshot.components);
+    }
+
+    /// Deterministic hash of all simulation state. Two worlds fed the same inputs will have the same checksum on every tick.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = ChecksumHasher::new();
+
+        self.tick.hash(&mut hasher);
+        self.entity_allocator.hash(&mut hasher);
+        self.deleted_entities.hash(&mut hasher);
+        self.rng.hash(&mut hasher);
+        self.turns.hash(&mut hasher);
+        self.events.hash(&mut hasher);
+        self.hash_components(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Keep snapshots of the last `capacity` ticks so the world can be rolled back
+    pub fn enable_history(&mut self, capacity: usize) {
+        let mut history = SnapshotHistory::new(capacity);
+        history.push(self.snapshot());
+
+        self.history = Some(history);
+    }
+
+    pub fn history(&self) -> Option<&SnapshotHistory> {
+        self.history.as_ref()
+    }
+
+    /// Restore the world to the end of the given tick. Snapshots after it are dropped, as they will be resimulated.
+    pub fn rollback(&mut self, tick: u64) -> Result<(), String> {
+        let snapshot = match &self.history {
+            Some(history) => match history.get(tick) {
+                Some(snapshot) => snapshot.clone(),
+                None => return Err(format!("No snapshot for tick {}", tick)),
+            },
+            None => return Err(String::from("History is not enabled")),
+        };
+
+        self.restore(&snapshot);
+
+        if let Some(history) = self.history.as_mut() {
+            history.truncate_after(tick);
+        }
+
+        Ok(())
+    }
+
+    /// Save a snapshot of the current tick, if history is enabled
+    pub(super) fn record_history(&mut self) {
+        if self.history.is_none() {
+            return;
+        }
+
+        let snapshot = self.snapshot();
+
+        if let Some(history) = self.history.as_mut() {
+            history.push(snapshot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::events;
+    use crate::lib_core::{EngineInputs, InputType};
+
+    fn run(world: &mut World, inputs: &Vec<InputType>, ticks: usize) {
+        for _ in 0..ticks {
+            world.register_player_inputs(inputs);
+            world.step();
+        }
+    }
+
+    #[test]
+    fn ChecksumHasher_is_stable() {
+        let mut hasher = ChecksumHasher::new();
+        hasher.write(b"a");
+
+        // Reference FNV-1a value
+        assert_eq!(0xaf63dc4c8601ec8c, hasher.finish());
+
+        let mut a = ChecksumHasher::new();
+        let mut b = ChecksumHasher::new();
+        (5 as usize).hash(&mut a);
+        (5 as u64).hash(&mut b);
+
+        assert_eq!(a.finish(), b.finish());
+    }
+
+    #[test]
+    fn SnapshotHistory_is_a_ring_buffer() {
+        let mut world = World::new();
+        let mut history = SnapshotHistory::new(3);
+
+        for _ in 0..5 {
+            world.step();
+            history.push(world.snapshot());
+        }
+
+        assert_eq!(3, history.len());
+        assert!(history.get(2).is_none());
+        assert!(history.get(3).is_some());
+        assert_eq!(5, history.latest().unwrap().tick());
+
+        history.truncate_after(3);
+        assert_eq!(1, history.len());
+        assert_eq!(3, history.latest().unwrap().tick());
+    }
+
+    #[test]
+    fn World_same_inputs_same_checksum() {
+        let mut a = World::new();
+        let mut b = World::new();
+        let inputs = vec![InputType::Held(0, EngineInputs::MoveForward)];
+
+        for _ in 0..10 {
+            run(&mut a, &inputs, 1);
+            run(&mut b, &inputs, 1);
+
+            assert_eq!(a.checksum(), b.checksum());
+        }
+
+        run(&mut a, &vec![InputType::Held(0, EngineInputs::MoveLeft)], 1);
+        run(&mut b, &inputs, 1);
+
+        assert_ne!(a.checksum(), b.checksum());
+    }
+
+    #[test]
+    fn World_checksum_covers_rng_and_entities() {
+        let a = World::new();
+
+        let mut b = World::new();
+        b.rng.next_u32();
+        assert_ne!(a.checksum(), b.checksum());
+
+        let mut c = World::new();
+        c.add_entity();
+        assert_ne!(a.checksum(), c.checksum());
+
+        let mut d = World::new();
+        d.events.entity_died.publish(events::EntityDied {
+            entity: Entity::new(0, 0),
+            killer: None,
+        });
+        assert_ne!(a.checksum(), d.checksum());
+    }
+
+    #[test]
+    fn World_snapshot_and_restore() {
+        let mut world = World::new();
+        let inputs = vec![InputType::Held(0, EngineInputs::MoveForward)];
+
+        run(&mut world, &inputs, 3);
+        let snapshot = world.snapshot();
+        let roll = world.rng.next_u32();
+
+        run(&mut world, &inputs, 3);
+        let spawned = world.add_entity();
+
+        world.restore(&snapshot);
+
+        assert_eq!(3, world.tick());
+        assert_eq!(snapshot.checksum(), world.checksum());
+        assert_eq!(false, world.is_alive(spawned));
+        assert_eq!(roll, world.rng.next_u32());
+    }
+
+    #[test]
+    fn World_rollback_and_resimulate() {
+        let mut world = World::new();
+        let forward = vec![InputType::Held(0, EngineInputs::MoveForward)];
+        let left = vec![InputType::Held(0, EngineInputs::MoveLeft)];
+
+        world.enable_history(20);
+
+        run(&mut world, &forward, 5);
+        let expected = world.checksum();
+
+        // A late input changed what happened on tick 3, so roll back to the end of tick 2
+        run(&mut world, &left, 5);
+        world.rollback(2).unwrap();
+
+        assert_eq!(2, world.tick());
+        assert_eq!(3, world.history().unwrap().len());
+
+        run(&mut world, &forward, 3);
+
+        assert_eq!(expected, world.checksum());
+        assert_eq!(
+            expected,
+            world.history().unwrap().latest().unwrap().checksum()
+        );
+    }
+
+    #[test]
+    fn World_rollback_errors() {
+        let mut world = World::new();
+
+        assert!(world.rollback(0).is_err());
+
+        world.enable_history(2);
+        run(&mut world, &vec![], 5);
+
+        assert!(world.rollback(1).is_err());
+        assert!(world.rollback(4).is_ok());
+    }
+}
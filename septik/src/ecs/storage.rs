@@ -0,0 +1,612 @@
+use super::Entity;
+use crate::lib_core::serialization::{SaveReader, SaveWriter, Serializable};
+
+use std::hash::{Hash, Hasher};
+
+/// Shared interface for component storages. Indexing with a stale entity reads as None, while writing through one panics.
+pub trait ComponentStorage<T>:
+    std::ops::Index<Entity, Output = Option<T>> + std::ops::IndexMut<Entity>
+{
+    /// Remove whatever component is in the entity's slot, regardless of generation
+    fn clear(&mut self, entity: Entity);
+
+    /// The number of components stored
+    fn len(&self) -> usize;
+
+    /// The entity's slot, if it owns one. Never grows the storage, and stale entities return None.
+    fn slot_mut(&mut self, entity: Entity) -> Option<&mut Option<T>>;
+
+    /// Retrieve the entity's component
+    fn get(&self, entity: Entity) -> Option<&T> {
+        self[entity].as_ref()
+    }
+
+    /// Retrieve the entity's component for modification. Stale entities return None.
+    fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.slot_mut(entity)?.as_mut()
+    }
+
+    /// Set the entity's component, returning the previous one. Panics on a stale entity, the same as writing through `IndexMut`.
+    fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        self[entity].replace(component)
+    }
+
+    /// Remove the entity's component. Stale entities return None.
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.slot_mut(entity)?.take()
+    }
+}
+
+/// Dense component storage, indexed by entity. Grows as entities are added. Best suited for common components.
+/// Components are tagged with the generation of the entity that owns them, so stale entities read as None.
+#[derive(Clone)]
+pub struct Storage<T> {
+    components: Vec<Option<T>>,
+    generations: Vec<u32>,
+    empty: Option<T>,
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Self {
+        return Self {
+            components: vec![],
+            generations: vec![],
+            empty: None,
+        };
+    }
+
+    /// Create a storage with room for `capacity` entities before it has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        return Self {
+            components: Vec::with_capacity(capacity),
+            generations: Vec::with_capacity(capacity),
+            empty: None,
+        };
+    }
+
+    /// Make sure the entity has a slot
+    fn grow(&mut self, entity: Entity) {
+        while self.components.len() <= entity.index() {
+            self.components.push(None);
+            self.generations.push(0);
+        }
+    }
+
+    /// Pointer to the entity's slot, if it owns one. Never grows the storage, so previously returned pointers stay valid.
+    pub(super) fn slot_ptr(&mut self, entity: Entity) -> Option<*mut Option<T>> {
+        let index = entity.index();
+
+        if index >= self.components.len() || self.generations[index] != entity.generation() {
+            return None;
+        }
+
+        // Go through the raw pointer so no reference to the other slots is created
+        Some(unsafe { self.components.as_mut_ptr().add(index) })
+    }
+}
+
+impl<T> ComponentStorage<T> for Storage<T> {
+    fn clear(&mut self, entity: Entity) {
+        if entity.index() < self.components.len() {
+            self.components[entity.index()] = None;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.components.iter().filter(|c| c.is_some()).count()
+    }
+
+    fn slot_mut(&mut self, entity: Entity) -> Option<&mut Option<T>> {
+        let index = entity.index();
+
+        if index >= self.components.len() || self.generations[index] != entity.generation() {
+            return None;
+        }
+
+        Some(&mut self.components[index])
+    }
+}
+
+impl<T: Hash> Hash for Storage<T> {
+    /// Only hashes living components, so the hash doesn't depend on how much the storage has grown
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for (index, component) in self.components.iter().enumerate() {
+            if let Some(component) = component {
+                index.hash(state);
+                self.generations[index].hash(state);
+                component.hash(state);
+            }
+        }
+    }
+}
+
+impl<T: Serializable> Serializable for Storage<T> {
+    /// Only living components are written, along with the entity that owns them
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.len());
+
+        for (index, component) in self.components.iter().enumerate() {
+            if let Some(component) = component {
+                writer.write(&Entity::new(index, self.generations[index]));
+                writer.write(component);
+            }
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let mut storage = Self::new();
+        let len: usize = reader.read()?;
+
+        for _ in 0..len {
+            let entity: Entity = reader.read()?;
+
+            let occupied = match storage.components.get(entity.index()) {
+                Some(component) => component.is_some(),
+                None => false,
+            };
+            if occupied {
+                return Err(format!("Multiple components for {:?}", entity));
+            }
+
+            storage[entity] = Some(reader.read()?);
+        }
+
+        Ok(storage)
+    }
+}
+
+impl<T> std::ops::Index<Entity> for Storage<T> {
+    type Output = Option<T>;
+
+    fn index(&self, entity: Entity) -> &Self::Output {
+        let index = entity.index();
+
+        if index >= self.components.len() || self.generations[index] != entity.generation() {
+            return &self.empty;
+        }
+
+        &self.components[index]
+    }
+}
+
+impl<T> std::ops::IndexMut<Entity> for Storage<T> {
+    fn index_mut(&mut self, entity: Entity) -> &mut Self::Output {
+        self.grow(entity);
+
+        let index = entity.index();
+        let generation = self.generations[index];
+
+        if generation != entity.generation() {
+            // Writing through a stale handle would clobber the entity that now owns the slot
+            if generation > entity.generation() {
+                panic!(
+                    "Attempted to modify a component of deleted entity {:?}",
+                    entity
+                );
+            }
+
+            // The slot belonged to an older, deleted entity
+            self.generations[index] = entity.generation();
+            self.components[index] = None;
+        }
+
+        &mut self.components[index]
+    }
+}
+
+/// Sparse set component storage. Only allocates a slot per entity index, with the components packed together. Best suited for rare components.
+#[derive(Clone)]
+pub struct SparseStorage<T> {
+    sparse: Vec<Option<usize>>,
+    entities: Vec<Entity>,
+    components: Vec<Option<T>>,
+    empty: Option<T>,
+}
+
+impl<T> SparseStorage<T> {
+    pub fn new() -> Self {
+        return Self {
+            sparse: vec![],
+            entities: vec![],
+            components: vec![],
+            empty: None,
+        };
+    }
+
+    /// Create a storage with room for `capacity` entity indexes. Space for the components themselves is only allocated as they're added.
+    pub fn with_capacity(capacity: usize) -> Self {
+        return Self {
+            sparse: Vec::with_capacity(capacity),
+            entities: vec![],
+            components: vec![],
+            empty: None,
+        };
+    }
+
+    /// The packed index of the entity's slot
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        match self.sparse.get(entity.index()) {
+            Some(dense_index) => *dense_index,
+            None => None,
+        }
+    }
+
+    /// Pointer to the entity's slot, if it owns one. Never grows the storage, so previously returned pointers stay valid.
+    pub(super) fn slot_ptr(&mut self, entity: Entity) -> Option<*mut Option<T>> {
+        match self.dense_index(entity) {
+            Some(dense_index) if self.entities[dense_index] == entity => {
+                // Go through the raw pointer so no reference to the other slots is created
+                Some(unsafe { self.components.as_mut_ptr().add(dense_index) })
+            }
+            _ => None,
+        }
+    }
+
+    /// Remove the slot, moving the last one into its place to keep them packed
+    fn swap_remove(&mut self, dense_index: usize) -> Option<T> {
+        let removed = self.entities.swap_remove(dense_index);
+        let component = self.components.swap_remove(dense_index);
+        self.sparse[removed.index()] = None;
+
+        if dense_index < self.entities.len() {
+            let moved = self.entities[dense_index];
+            self.sparse[moved.index()] = Some(dense_index);
+        }
+
+        component
+    }
+}
+
+impl<T> ComponentStorage<T> for SparseStorage<T> {
+    fn clear(&mut self, entity: Entity) {
+        if let Some(dense_index) = self.dense_index(entity) {
+            self.swap_remove(dense_index);
+        }
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        match self.dense_index(entity) {
+            Some(dense_index) if self.entities[dense_index] == entity => {
+                self.swap_remove(dense_index)
+            }
+            _ => None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.components.iter().filter(|c| c.is_some()).count()
+    }
+
+    fn slot_mut(&mut self, entity: Entity) -> Option<&mut Option<T>> {
+        match self.dense_index(entity) {
+            Some(dense_index) if self.entities[dense_index] == entity => {
+                Some(&mut self.components[dense_index])
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<T: Hash> Hash for SparseStorage<T> {
+    /// Hashes in entity order, so the hash doesn't depend on the order components were added
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for dense_index in self.sparse.iter() {
+            if let Some(dense_index) = dense_index {
+                if let Some(component) = &self.components[*dense_index] {
+                    self.entities[*dense_index].hash(state);
+                    component.hash(state);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Serializable> Serializable for SparseStorage<T> {
+    /// Written in entity order, the same as `Storage`, so either can be read back as the other
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.len());
+
+        for dense_index in self.sparse.iter() {
+            if let Some(dense_index) = dense_index {
+                if let Some(component) = &self.components[*dense_index] {
+                    writer.write(&self.entities[*dense_index]);
+                    writer.write(component);
+                }
+            }
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let mut storage = Self::new();
+        let len: usize = reader.read()?;
+
+        for _ in 0..len {
+            let entity: Entity = reader.read()?;
+
+            if storage.dense_index(entity).is_some() {
+                return Err(format!("Multiple components for {:?}", entity));
+            }
+
+            storage[entity] = Some(reader.read()?);
+        }
+
+        Ok(storage)
+    }
+}
+
+impl<T> std::ops::Index<Entity> for SparseStorage<T> {
+    type Output = Option<T>;
+
+    fn index(&self, entity: Entity) -> &Self::Output {
+        match self.dense_index(entity) {
+            Some(dense_index) if self.entities[dense_index] == entity => {
+                &self.components[dense_index]
+            }
+            _ => &self.empty,
+        }
+    }
+}
+
+impl<T> std::ops::IndexMut<Entity> for SparseStorage<T> {
+    /// Reserves a slot for entities without one, so prefer `get_mut` for lookups
+    fn index_mut(&mut self, entity: Entity) -> &mut Self::Output {
+        let dense_index = match self.dense_index(entity) {
+            Some(dense_index) => {
+                let owner = self.entities[dense_index];
+
+                if owner.generation() > entity.generation() {
+                    panic!(
+                        "Attempted to modify a component of deleted entity {:?}",
+                        entity
+                    );
+                } else if owner.generation() < entity.generation() {
+                    // The slot belonged to an older, deleted entity
+                    self.entities[dense_index] = entity;
+                    self.components[dense_index] = None;
+                }
+
+                dense_index
+            }
+            None => {
+                // Drop slots earlier lookups reserved but never filled
+                while let Some(None) = self.components.last() {
+                    self.swap_remove(self.components.len() - 1);
+                }
+
+                while self.sparse.len() <= entity.index() {
+                    self.sparse.push(None);
+                }
+
+                let dense_index = self.entities.len();
+
+                self.entities.push(entity);
+                self.components.push(None);
+                self.sparse[entity.index()] = Some(dense_index);
+
+                dense_index
+            }
+        };
+
+        &mut self.components[dense_index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn Storage_insert_and_get() {
+        let mut storage = Storage::new();
+        let e = Entity::new(3, 0);
+
+        assert_eq!(None, storage.get(e));
+
+        storage.insert(e, 5);
+        assert_eq!(Some(&5), storage.get(e));
+        assert_eq!(Some(5), storage[e]);
+
+        *storage.get_mut(e).unwrap() += 1;
+        assert_eq!(Some(6), storage.remove(e));
+        assert_eq!(None, storage[e]);
+    }
+
+    #[test]
+    fn Storage_index_mut() {
+        let mut storage = Storage::new();
+        let e = Entity::new(0, 0);
+
+        storage[e] = Some("a");
+
+        assert_eq!(Some("a"), storage[e]);
+    }
+
+    #[test]
+    fn Storage_grows() {
+        let mut storage = Storage::new();
+        let e = Entity::new(5000, 0);
+
+        assert_eq!(None, storage[e]);
+
+        storage[e] = Some(1);
+
+        assert_eq!(Some(1), storage[e]);
+        assert_eq!(1, storage.len());
+    }
+
+    #[test]
+    fn Storage_stale_entity_reads_none() {
+        let mut storage = Storage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[old] = Some(1);
+        storage[new] = Some(2);
+
+        assert_eq!(None, storage[old]);
+        assert_eq!(Some(2), storage[new]);
+    }
+
+    #[test]
+    fn Storage_newer_generation_does_not_inherit_component() {
+        let mut storage = Storage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[old] = Some(1);
+
+        assert_eq!(None, storage[new]);
+        assert_eq!(None, storage.get_mut(new));
+    }
+
+    #[test]
+    fn Storage_get_mut_and_remove_do_not_grow_or_panic() {
+        let mut storage = Storage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[new] = Some(2);
+
+        assert_eq!(None, storage.get_mut(old));
+        assert_eq!(None, storage.remove(old));
+        assert_eq!(Some(2), storage[new]);
+
+        assert_eq!(None, storage.get_mut(Entity::new(5000, 0)));
+        assert_eq!(None, storage.remove(Entity::new(5000, 0)));
+        assert_eq!(2, storage.components.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn Storage_stale_entity_write_panics() {
+        let mut storage = Storage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[new] = Some(2);
+        storage[old] = Some(1);
+    }
+
+    #[test]
+    fn Storage_clear() {
+        let mut storage = Storage::new();
+        let e = Entity::new(1, 0);
+
+        storage[e] = Some(1);
+        storage.clear(Entity::new(1, 5));
+        storage.clear(Entity::new(100, 0));
+
+        assert_eq!(None, storage[e]);
+        assert_eq!(0, storage.len());
+    }
+
+    #[test]
+    fn SparseStorage_insert_and_get() {
+        let mut storage = SparseStorage::new();
+        let e = Entity::new(3000, 0);
+
+        assert_eq!(None, storage.get(e));
+
+        storage.insert(e, 5);
+        assert_eq!(Some(&5), storage.get(e));
+        assert_eq!(Some(5), storage[e]);
+
+        *storage.get_mut(e).unwrap() += 1;
+        assert_eq!(Some(6), storage.remove(e));
+        assert_eq!(None, storage[e]);
+    }
+
+    #[test]
+    fn SparseStorage_only_packs_used_components() {
+        let mut storage = SparseStorage::new();
+
+        storage[Entity::new(10000, 0)] = Some(1);
+        storage[Entity::new(3, 0)] = Some(2);
+
+        assert_eq!(2, storage.components.len());
+        assert_eq!(2, storage.len());
+    }
+
+    #[test]
+    fn SparseStorage_clear_keeps_others() {
+        let mut storage = SparseStorage::new();
+        let a = Entity::new(0, 0);
+        let b = Entity::new(5, 0);
+        let c = Entity::new(9, 0);
+
+        storage[a] = Some("a");
+        storage[b] = Some("b");
+        storage[c] = Some("c");
+
+        storage.clear(a);
+        storage.clear(Entity::new(50, 0));
+
+        assert_eq!(None, storage[a]);
+        assert_eq!(Some("b"), storage[b]);
+        assert_eq!(Some("c"), storage[c]);
+        assert_eq!(2, storage.len());
+
+        storage.clear(c);
+        assert_eq!(Some("b"), storage[b]);
+        assert_eq!(1, storage.len());
+    }
+
+    #[test]
+    fn SparseStorage_lookups_stay_packed() {
+        let mut storage = SparseStorage::new();
+        let a = Entity::new(2, 0);
+        let missing = Entity::new(7, 0);
+
+        storage[a] = Some("a");
+        assert_eq!(Some("a"), storage.remove(a));
+        assert_eq!(None, storage.get_mut(missing));
+        assert_eq!(None, storage.remove(missing));
+        assert_eq!(0, storage.entities.len());
+
+        storage[a] = Some("a");
+        assert!(storage[missing].as_mut().is_none());
+        storage[Entity::new(9, 0)] = Some("b");
+
+        assert_eq!(2, storage.entities.len());
+        assert_eq!(None, storage[missing]);
+        assert_eq!(Some("a"), storage[a]);
+    }
+
+    #[test]
+    fn SparseStorage_stale_entity_reads_none() {
+        let mut storage = SparseStorage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[old] = Some(1);
+        assert_eq!(None, storage[new]);
+
+        storage[new] = Some(2);
+        assert_eq!(None, storage[old]);
+        assert_eq!(Some(2), storage[new]);
+    }
+
+    #[test]
+    fn SparseStorage_get_mut_and_remove_stale_entity() {
+        let mut storage = SparseStorage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[new] = Some(2);
+
+        assert_eq!(None, storage.get_mut(old));
+        assert_eq!(None, storage.remove(old));
+        assert_eq!(Some(2), storage[new]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn SparseStorage_stale_entity_write_panics() {
+        let mut storage = SparseStorage::new();
+        let old = Entity::new(1, 0);
+        let new = Entity::new(1, 1);
+
+        storage[new] = Some(2);
+        storage[old] = Some(1);
+    }
+}
@@ -0,0 +1,351 @@
+use super::damage::{self, Damage};
+use crate::ecs::{
+    components::{AilmentTypes, AilmentsComponent, DamageType},
+    events, Entity, Join, World,
+};
+
+/// Hit points lost when Bleed triggers, per multiplier
+pub const BLEED_DAMAGE: u32 = 5;
+
+/// Hit points lost each interval while poisoned, per multiplier
+pub const POISON_DAMAGE: u32 = 1;
+
+/// Ticks between each loss of hit points while poisoned
+pub const POISON_DAMAGE_INTERVAL: u8 = 15;
+
+/// How much the meter of an active ailment drains each tick
+pub const ACTIVE_DRAIN_PER_TICK: u8 = 1;
+
+/// While slothed, movement is divided by this and action windows are multiplied by it
+pub const SLOTH_SLOWDOWN: i32 = 2;
+
+/// This system triggers ailments whose meters are full and applies their effects
+pub fn ailment_system(world: &mut World) {
+    let entities = world.entities();
+    let events = &mut world.events;
+
+    // Damage is applied after triggering, as it needs the whole world
+    let mut damages = vec![];
+    let mut curses = vec![];
+
+    for (e, ailments) in (&mut world.ailments).join(&entities) {
+        // Brainworms go first, so any meters they fill trigger this tick
+        trigger_brainworms(e, ailments, events);
+
+        for ailment in ailments.ailments.iter_mut() {
+            let multiplier = ailment.modifier_multiplier as u32;
+
+            if ailment.active {
+                ailment.status_meter.saturating_sub(ACTIVE_DRAIN_PER_TICK);
+
+                if ailment.modifier_type == AilmentTypes::Poison
+                    && ailment.status_meter.value % POISON_DAMAGE_INTERVAL == 0
+                {
+                    damages.push((
+                        e,
+                        Damage::new(POISON_DAMAGE * multiplier, DamageType::Poison, None),
+                    ));
+                }
+
+                if ailment.status_meter.is_empty() {
+                    ailment.active = false;
+                }
+
+                continue;
+            }
+
+            if !ailment.status_meter.is_full() {
+                continue;
+            }
+
+            match ailment.modifier_type {
+                AilmentTypes::Bleed => {
+                    damages.push((
+                        e,
+                        Damage::new(BLEED_DAMAGE * multiplier, DamageType::Bleed, None),
+                    ));
+                    ailment.status_meter.drain();
+                }
+                AilmentTypes::Curse => {
+                    curses.push(e);
+                    ailment.status_meter.drain();
+                }
+                AilmentTypes::Poison | AilmentTypes::Sloth => {
+                    // Lasting effects are applied while the meter drains
+                    ailment.active = true;
+                }
+                AilmentTypes::BrainWorms => {
+                    // Triggered above
+                    continue;
+                }
+            }
+
+            events.ailment_triggered.publish(events::AilmentTriggered {
+                entity: e,
+                ailment: ailment.modifier_type,
+            });
+        }
+    }
+
+    for (e, damage) in damages {
+        damage::apply_damage(world, e, damage);
+    }
+
+    for e in curses {
+        damage::kill(world, e, None);
+    }
+}
+
+/// Multiply the meters of all other inactive ailments, if the brainworms meter is full
+fn trigger_brainworms(e: Entity, ailments: &mut AilmentsComponent, events: &mut events::Events) {
+    let multiplier = match ailments
+        .ailments
+        .iter()
+        .find(|a| a.modifier_type == AilmentTypes::BrainWorms && a.status_meter.is_full())
+    {
+        Some(brainworms) => 1 + brainworms.modifier_multiplier as u32,
+        None => return,
+    };
+
+    for ailment in ailments.ailments.iter_mut() {
+        if ailment.modifier_type == AilmentTypes::BrainWorms {
+            ailment.status_meter.drain();
+        } else if !ailment.active {
+            let value = ailment.status_meter.value as u32 * multiplier;
+            ailment.status_meter.value = value.min(std::u8::MAX as u32) as u8;
+        }
+    }
+
+    events.ailment_triggered.publish(events::AilmentTriggered {
+        entity: e,
+        ailment: AilmentTypes::BrainWorms,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::Ailment;
+    use crate::ecs::test_fixtures::{character, hitpoints};
+    use crate::lib_core::math::Range;
+
+    fn afflicted(ailments: Vec<Ailment>, hitpoints: u32) -> (World, Entity) {
+        let mut world = World::builder().build().unwrap();
+        let e = character(&mut world, hitpoints);
+
+        world.ailments[e] = Some(AilmentsComponent { ailments: ailments });
+
+        (world, e)
+    }
+
+    fn full(modifier_type: AilmentTypes, multiplier: u8) -> Ailment {
+        let mut ailment = Ailment::new(modifier_type);
+        ailment.modifier_multiplier = multiplier;
+        ailment.status_meter.fill();
+
+        ailment
+    }
+
+    fn ailment(world: &World, e: Entity, index: usize) -> Ailment {
+        world.ailments[e].as_ref().unwrap().ailments[index].clone()
+    }
+
+    #[test]
+    fn AilmentsComponent_build_up() {
+        let mut ailments = AilmentsComponent::new();
+
+        ailments.build_up(AilmentTypes::Bleed, 200);
+        ailments.build_up(AilmentTypes::Bleed, 200);
+        ailments.build_up(AilmentTypes::Sloth, 10);
+
+        assert_eq!(2, ailments.ailments.len());
+        assert!(ailments.ailments[0].status_meter.is_full());
+        assert_eq!(10, ailments.ailments[1].status_meter.value);
+
+        ailments.ailments[1].active = true;
+        ailments.build_up(AilmentTypes::Sloth, 10);
+
+        assert_eq!(10, ailments.ailments[1].status_meter.value);
+        assert!(ailments.is_active(AilmentTypes::Sloth));
+        assert!(!ailments.is_active(AilmentTypes::Bleed));
+    }
+
+    #[test]
+    fn ailment_system_ignores_partial_meters() {
+        let mut bleed = Ailment::new(AilmentTypes::Bleed);
+        bleed.status_meter = Range::new(254);
+        let (mut world, e) = afflicted(vec![bleed], 20);
+
+        ailment_system(&mut world);
+
+        assert_eq!(20, hitpoints(&world, e));
+        assert_eq!(254, ailment(&world, e, 0).status_meter.value);
+    }
+
+    #[test]
+    fn ailment_system_bleed_single_loss() {
+        let (mut world, e) = afflicted(vec![full(AilmentTypes::Bleed, 2)], 20);
+
+        ailment_system(&mut world);
+
+        assert_eq!(20 - BLEED_DAMAGE * 2, hitpoints(&world, e));
+        assert!(ailment(&world, e, 0).status_meter.is_empty());
+        assert!(!ailment(&world, e, 0).active);
+
+        world.events.update();
+        assert_eq!(
+            &[events::AilmentTriggered {
+                entity: e,
+                ailment: AilmentTypes::Bleed
+            }],
+            world.events.ailment_triggered.read()
+        );
+        assert_eq!(
+            &[events::DamageDealt {
+                source: None,
+                target: e,
+                amount: BLEED_DAMAGE * 2,
+                damage_type: DamageType::Bleed
+            }],
+            world.events.damage_dealt.read()
+        );
+
+        // Only triggers once
+        ailment_system(&mut world);
+        assert_eq!(20 - BLEED_DAMAGE * 2, hitpoints(&world, e));
+    }
+
+    #[test]
+    fn ailment_system_poison_drains() {
+        let (mut world, e) = afflicted(vec![full(AilmentTypes::Poison, 2)], 100);
+
+        ailment_system(&mut world);
+        assert!(ailment(&world, e, 0).active);
+        assert_eq!(100, hitpoints(&world, e));
+
+        // Build-up is ignored until the meter has drained
+        world.ailments[e]
+            .as_mut()
+            .unwrap()
+            .build_up(AilmentTypes::Poison, 100);
+
+        let mut ticks = 0;
+        while ailment(&world, e, 0).active {
+            ailment_system(&mut world);
+            ticks += 1;
+        }
+
+        let intervals = (Range::max / POISON_DAMAGE_INTERVAL) as u32;
+
+        assert_eq!(Range::max as u32, ticks);
+        assert_eq!(100 - intervals * POISON_DAMAGE * 2, hitpoints(&world, e));
+        assert!(ailment(&world, e, 0).status_meter.is_empty());
+
+        // Wears off completely
+        ailment_system(&mut world);
+        assert_eq!(100 - intervals * POISON_DAMAGE * 2, hitpoints(&world, e));
+    }
+
+    #[test]
+    fn ailment_system_curse_kills() {
+        let (mut world, e) = afflicted(vec![full(AilmentTypes::Curse, 1)], 250);
+
+        ailment_system(&mut world);
+
+        assert_eq!(0, hitpoints(&world, e));
+
+        world.events.update();
+        assert_eq!(
+            &[events::EntityDied {
+                entity: e,
+                killer: None
+            }],
+            world.events.entity_died.read()
+        );
+    }
+
+    #[test]
+    fn ailment_system_sloth_lasts() {
+        let (mut world, e) = afflicted(vec![full(AilmentTypes::Sloth, 1)], 10);
+
+        ailment_system(&mut world);
+
+        assert!(world.ailments[e]
+            .as_ref()
+            .unwrap()
+            .is_active(AilmentTypes::Sloth));
+        assert_eq!(10, hitpoints(&world, e));
+
+        for _ in 0..Range::max {
+            ailment_system(&mut world);
+        }
+
+        assert!(!world.ailments[e]
+            .as_ref()
+            .unwrap()
+            .is_active(AilmentTypes::Sloth));
+    }
+
+    #[test]
+    fn ailment_system_sloth_slows_movement() {
+        let mut world = World::new();
+        let player = world.player().unwrap();
+        let mut sloth = full(AilmentTypes::Sloth, 1);
+        sloth.active = true;
+        world.ailments[player] = Some(AilmentsComponent {
+            ailments: vec![sloth],
+        });
+
+        world.register_player_inputs(&vec![crate::lib_core::InputType::Held(
+            0,
+            crate::lib_core::EngineInputs::MoveRight,
+        )]);
+        world.step();
+
+        let speed = world.move_speeds[player].as_ref().unwrap().value;
+        let slowdown: crate::lib_core::math::FixedNumber = SLOTH_SLOWDOWN.into();
+
+        assert_eq!(
+            speed / slowdown,
+            world.velocities[player].as_ref().unwrap().value.x
+        );
+    }
+
+    #[test]
+    fn ailment_system_brainworms_doubles_meters() {
+        let mut bleed = Ailment::new(AilmentTypes::Bleed);
+        bleed.status_meter = Range::new(100);
+        let mut sloth = Ailment::new(AilmentTypes::Sloth);
+        sloth.status_meter = Range::new(20);
+
+        let (mut world, e) = afflicted(vec![bleed, full(AilmentTypes::BrainWorms, 1), sloth], 20);
+
+        ailment_system(&mut world);
+
+        assert_eq!(200, ailment(&world, e, 0).status_meter.value);
+        assert!(ailment(&world, e, 1).status_meter.is_empty());
+        assert_eq!(40, ailment(&world, e, 2).status_meter.value);
+        assert_eq!(20, hitpoints(&world, e));
+
+        // Filling a meter triggers it on the same tick
+        world.ailments[e].as_mut().unwrap().ailments[1]
+            .status_meter
+            .fill();
+        ailment_system(&mut world);
+
+        assert_eq!(20 - BLEED_DAMAGE, hitpoints(&world, e));
+        assert_eq!(80, ailment(&world, e, 2).status_meter.value);
+    }
+
+    #[test]
+    fn ailment_system_brainworms_multiplier() {
+        let mut poison = Ailment::new(AilmentTypes::Poison);
+        poison.status_meter = Range::new(30);
+
+        let (mut world, e) = afflicted(vec![full(AilmentTypes::BrainWorms, 2), poison], 20);
+
+        ailment_system(&mut world);
+
+        assert_eq!(90, ailment(&world, e, 1).status_meter.value);
+    }
+}
@@ -0,0 +1,476 @@
+use super::{
+    ailments::SLOTH_SLOWDOWN,
+    damage::{self, Damage},
+};
+use crate::ecs::{
+    components::{
+        AabbComponent, AilmentTypes, AilmentsComponent, AttackComponent, AttackPhase, AttackType,
+        DamageType, HitboxComponent,
+    },
+    Entity, Join, World,
+};
+use crate::lib_core::{
+    math::{FixedNumber, Vec3d},
+    Aabb, Direction, EngineInputs, InputType,
+};
+
+/// How an attack type plays out
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AttackProfile {
+    pub startup_frames: u32,
+    pub active_frames: u32,
+    pub recovery_frames: u32,
+    pub damage: u32,
+    /// The ailment built up on each target hit, and by how much
+    pub build_up: Option<(AilmentTypes, u8)>,
+    /// How far in front of the attacker the hitbox's center is
+    pub reach: i32,
+    /// The hitbox's half size across, along and up from the facing direction
+    pub width: i32,
+    pub depth: i32,
+    pub height: i32,
+}
+
+impl AttackProfile {
+    pub fn get(attack_type: AttackType) -> Self {
+        match attack_type {
+            AttackType::Horizontal => Self {
+                startup_frames: 6,
+                active_frames: 4,
+                recovery_frames: 10,
+                damage: 8,
+                build_up: Some((AilmentTypes::Bleed, 40)),
+                reach: 24,
+                width: 32,
+                depth: 12,
+                height: 8,
+            },
+            AttackType::Vertical => Self {
+                startup_frames: 12,
+                active_frames: 3,
+                recovery_frames: 16,
+                damage: 14,
+                build_up: None,
+                reach: 32,
+                width: 8,
+                depth: 20,
+                height: 32,
+            },
+        }
+    }
+
+    /// The hitbox relative to the attacker, enclosing the profile's box turned to face the direction
+    pub fn hitbox(&self, direction: Direction) -> Aabb {
+        let forward = direction.to_vec3d();
+        let reach: FixedNumber = self.reach.into();
+        let width: FixedNumber = self.width.into();
+        let depth: FixedNumber = self.depth.into();
+
+        // The right of forward is perpendicular on the ground, so their parts swap axes
+        let extents = Vec3d::new(
+            forward.x.abs() * depth + forward.z.abs() * width,
+            self.height.into(),
+            forward.z.abs() * depth + forward.x.abs() * width,
+        );
+
+        Aabb::from_center_extents(forward.multiply(reach), extents)
+    }
+}
+
+/// Start an attack, if the character isn't already attacking. Sloth lengthens every frame window.
+pub fn start_attack(world: &mut World, e: Entity, attack_type: AttackType) -> Result<(), String> {
+    if world.attacks[e].is_some() {
+        return Err(format!("{:?} is already attacking", e));
+    }
+
+    if world.dead[e].is_some() {
+        return Err(format!("{:?} is dead", e));
+    }
+
+    let profile = AttackProfile::get(attack_type);
+
+    let slowdown = match &world.ailments[e] {
+        Some(ailments) if ailments.is_active(AilmentTypes::Sloth) => SLOTH_SLOWDOWN as u32,
+        _ => 1,
+    };
+
+    world.attacks[e] = Some(AttackComponent::new(
+        attack_type,
+        profile.startup_frames * slowdown,
+        profile.active_frames * slowdown,
+        profile.recovery_frames * slowdown,
+    ));
+
+    Ok(())
+}
+
+/// This system starts attacks from inputs and advances them, spawning hitboxes during the active frames
+pub fn attack_system(world: &mut World) {
+    let entities = world.entities();
+
+    let mut started = vec![];
+    for (e, engine_inputs) in (&world.engine_inputs).join(&entities) {
+        for input in &engine_inputs.inputs {
+            match input {
+                InputType::Pressed(_, EngineInputs::HorizontalAttack) => {
+                    started.push((e, AttackType::Horizontal))
+                }
+                InputType::Pressed(_, EngineInputs::VerticalAttack) => {
+                    started.push((e, AttackType::Vertical))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (e, attack_type) in started {
+        // Pressing attack mid swing does nothing
+        let _ = start_attack(world, e, attack_type);
+    }
+
+    let attackers: Vec<Entity> = (&world.attacks).join(&entities).map(|(e, _)| e).collect();
+
+    for attacker in attackers {
+        update_attack(world, attacker);
+    }
+}
+
+/// Run a single tick of the attack
+fn update_attack(world: &mut World, attacker: Entity) {
+    let mut attack = match world.attacks[attacker].take() {
+        Some(attack) => attack,
+        None => return,
+    };
+
+    // The dead don't finish their swings
+    if world.dead[attacker].is_some() {
+        if let Some(hitbox) = attack.hitbox.take() {
+            world.delete_entity(hitbox);
+        }
+
+        return;
+    }
+
+    let is_active = attack.phase() == Some(AttackPhase::Active);
+
+    if is_active && attack.hitbox.is_none() {
+        attack.hitbox = spawn_hitbox(world, attacker, attack.attack_type);
+    }
+
+    if is_active {
+        hit_targets(world, attacker, &mut attack);
+    } else if let Some(hitbox) = attack.hitbox.take() {
+        world.delete_entity(hitbox);
+    }
+
+    attack.elapsed += 1;
+
+    if attack.phase().is_none() {
+        if let Some(hitbox) = attack.hitbox.take() {
+            world.delete_entity(hitbox);
+        }
+
+        return;
+    }
+
+    world.attacks[attacker] = Some(attack);
+}
+
+/// Spawn the attack's hitbox as a child of the attacker, so it goes away with them
+fn spawn_hitbox(world: &mut World, attacker: Entity, attack_type: AttackType) -> Option<Entity> {
+    let direction = match &world.facing_direction[attacker] {
+        Some(facing) => facing.direction,
+        None => Direction::North,
+    };
+
+    let hitbox = world.add_entity();
+    world.hitboxes[hitbox] = Some(HitboxComponent { owner: attacker });
+    world.aabbs[hitbox] = Some(AabbComponent::new(
+        AttackProfile::get(attack_type).hitbox(direction),
+    ));
+
+    if world.attach(hitbox, attacker).is_err() {
+        world.delete_entity(hitbox);
+        return None;
+    }
+
+    Some(hitbox)
+}
+
+/// Damage and build up ailments on each targetable entity overlapping the hitbox that hasn't been hit yet
+fn hit_targets(world: &mut World, attacker: Entity, attack: &mut AttackComponent) {
+    let hitbox = match attack
+        .hitbox
+        .and_then(|hitbox| world.aabbs[hitbox].as_ref())
+    {
+        Some(hitbox) => hitbox.aabb,
+        None => return,
+    };
+
+    let origin = match &world.transforms[attacker] {
+        Some(transform) => transform.position,
+        None => return,
+    };
+
+    let hitbox = hitbox.translate(origin);
+    let entities = world.entities();
+
+    let targets: Vec<Entity> = (&world.targetables, &world.aabbs, &world.transforms)
+        .join(&entities)
+        .filter(|(target, (_, aabb, transform))| {
+            *target != attacker
+                && !attack.hit.contains(target)
+                // Targets dodging through the hit aren't hit at all, so they can still be hit later in the active frames
+                && !damage::is_invulnerable(world, *target)
+                && aabb.aabb.translate(transform.position).intersects(&hitbox)
+        })
+        .map(|(target, _)| target)
+        .collect();
+
+    let profile = AttackProfile::get(attack.attack_type);
+
+    for target in targets {
+        attack.hit.push(target);
+
+        damage::apply_damage(
+            world,
+            target,
+            Damage::new(profile.damage, DamageType::Physical, Some(attacker)),
+        );
+
+        if let Some((ailment, amount)) = profile.build_up {
+            if world.dead[target].is_none() {
+                world.ailments[target]
+                    .get_or_insert_with(AilmentsComponent::new)
+                    .build_up(ailment, amount);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::{
+        Ailment, DeadComponent, FacingComponent, PlayerComponent, TargetableComponent,
+        TransformComponent,
+    };
+    use crate::ecs::test_fixtures::hitpoints;
+
+    fn character(world: &mut World, position: (i32, i32, i32)) -> Entity {
+        let e = crate::ecs::test_fixtures::character(world, 100);
+        let mut transform = TransformComponent::new();
+        transform.position = position.into();
+
+        world.transforms[e] = Some(transform);
+        world.facing_direction[e] = Some(FacingComponent::new(Direction::North));
+
+        e
+    }
+
+    fn target(world: &mut World, position: (i32, i32, i32)) -> Entity {
+        let e = character(world, position);
+        world.targetables[e] = Some(TargetableComponent {});
+        world.aabbs[e] = Some(AabbComponent::new(Aabb::new(
+            (-4, -4, -4).into(),
+            (4, 4, 4).into(),
+        )));
+
+        e
+    }
+
+    fn run(world: &mut World, ticks: u32) {
+        for _ in 0..ticks {
+            attack_system(world);
+        }
+    }
+
+    #[test]
+    fn AttackComponent_phases() {
+        let mut attack = AttackComponent::new(AttackType::Horizontal, 2, 1, 2);
+        let mut phases = vec![];
+
+        while let Some(phase) = attack.phase() {
+            phases.push(phase);
+            attack.elapsed += 1;
+        }
+
+        assert_eq!(
+            vec![
+                AttackPhase::Startup,
+                AttackPhase::Startup,
+                AttackPhase::Active,
+                AttackPhase::Recovery,
+                AttackPhase::Recovery
+            ],
+            phases
+        );
+        assert_eq!(5, attack.total_frames());
+    }
+
+    #[test]
+    fn AttackProfile_hitbox_faces_direction() {
+        let profile = AttackProfile::get(AttackType::Horizontal);
+
+        let north = profile.hitbox(Direction::North);
+        assert_eq!(Vec3d::new(0.into(), 0.into(), (-24).into()), north.center());
+        assert_eq!(Vec3d::new(32.into(), 8.into(), 12.into()), north.extents());
+
+        let east = profile.hitbox(Direction::East);
+        assert_eq!(Vec3d::new(24.into(), 0.into(), 0.into()), east.center());
+        assert_eq!(Vec3d::new(12.into(), 8.into(), 32.into()), east.extents());
+
+        let south_west = profile.hitbox(Direction::SouthWest);
+        assert!(south_west.center().x < 0.into());
+        assert!(south_west.center().z > 0.into());
+        assert_eq!(south_west.extents().x, south_west.extents().z);
+    }
+
+    #[test]
+    fn attack_system_starts_from_inputs() {
+        let mut world = World::new();
+        let player = world.player().unwrap();
+
+        world.register_player_inputs(&vec![InputType::Pressed(0, EngineInputs::VerticalAttack)]);
+        world.step();
+
+        let attack = world.attacks[player].as_ref().unwrap();
+        assert_eq!(AttackType::Vertical, attack.attack_type);
+        assert_eq!(1, attack.elapsed);
+
+        // Can't start another mid swing
+        assert!(start_attack(&mut world, player, AttackType::Horizontal).is_err());
+    }
+
+    #[test]
+    fn attack_system_hits_during_active_frames() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = character(&mut world, (0, 0, 0));
+        let in_front = target(&mut world, (10, 0, -24));
+        let behind = target(&mut world, (0, 0, 24));
+        let profile = AttackProfile::get(AttackType::Horizontal);
+
+        start_attack(&mut world, attacker, AttackType::Horizontal).unwrap();
+
+        run(&mut world, profile.startup_frames);
+        assert_eq!(100, hitpoints(&world, in_front));
+        assert!(world.attacks[attacker].as_ref().unwrap().hitbox.is_none());
+
+        run(&mut world, 1);
+        let hitbox = world.attacks[attacker].as_ref().unwrap().hitbox.unwrap();
+        assert_eq!(
+            Some(HitboxComponent { owner: attacker }),
+            world.hitboxes[hitbox]
+        );
+        assert_eq!(Some(attacker), world.parent(hitbox));
+
+        assert_eq!(100 - profile.damage, hitpoints(&world, in_front));
+        assert_eq!(100, hitpoints(&world, behind));
+        assert_eq!(
+            40,
+            world.ailments[in_front].as_ref().unwrap().ailments[0]
+                .status_meter
+                .value
+        );
+
+        // Only hit once per attack
+        run(&mut world, profile.active_frames - 1);
+        assert_eq!(100 - profile.damage, hitpoints(&world, in_front));
+
+        // The hitbox is removed for recovery, and the attack when it's over
+        run(&mut world, 1);
+        assert!(world.attacks[attacker].as_ref().unwrap().hitbox.is_none());
+
+        run(&mut world, profile.recovery_frames - 1);
+        assert!(world.attacks[attacker].is_none());
+    }
+
+    #[test]
+    fn attack_system_hitbox_follows_facing() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = character(&mut world, (0, 0, 0));
+        world.facing_direction[attacker] = Some(FacingComponent::new(Direction::East));
+        let north = target(&mut world, (0, 0, -30));
+        let east = target(&mut world, (30, 0, 0));
+
+        start_attack(&mut world, attacker, AttackType::Vertical).unwrap();
+        run(
+            &mut world,
+            AttackProfile::get(AttackType::Vertical).startup_frames + 1,
+        );
+
+        assert_eq!(100, hitpoints(&world, north));
+        assert_eq!(100 - 14, hitpoints(&world, east));
+        assert!(world.ailments[east].is_none());
+    }
+
+    #[test]
+    fn attack_system_sloth_lengthens_windows() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = character(&mut world, (0, 0, 0));
+        let mut sloth = Ailment::new(AilmentTypes::Sloth);
+        sloth.active = true;
+        world.ailments[attacker] = Some(AilmentsComponent {
+            ailments: vec![sloth],
+        });
+
+        start_attack(&mut world, attacker, AttackType::Horizontal).unwrap();
+
+        let profile = AttackProfile::get(AttackType::Horizontal);
+        let attack = world.attacks[attacker].as_ref().unwrap();
+        let slowdown = SLOTH_SLOWDOWN as u32;
+
+        assert_eq!(profile.startup_frames * slowdown, attack.startup_frames);
+        assert_eq!(profile.active_frames * slowdown, attack.active_frames);
+        assert_eq!(profile.recovery_frames * slowdown, attack.recovery_frames);
+    }
+
+    #[test]
+    fn attack_system_respects_invulnerability() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = character(&mut world, (0, 0, 0));
+        let dodging = target(&mut world, (0, 0, -24));
+        let mut invulnerability = crate::ecs::components::InvulnerabilityComponent::new(0);
+        invulnerability.remaining_ticks = 100;
+        world.invulnerabilities[dodging] = Some(invulnerability);
+
+        start_attack(&mut world, attacker, AttackType::Horizontal).unwrap();
+        run(&mut world, 20);
+
+        assert_eq!(100, hitpoints(&world, dodging));
+        assert!(world.ailments[dodging].is_none());
+    }
+
+    #[test]
+    fn attack_system_cancels_when_attacker_dies() {
+        let mut world = World::builder().build().unwrap();
+        // Dead players stick around, unlike everyone else
+        let attacker = character(&mut world, (0, 0, 0));
+        world.players[attacker] = Some(PlayerComponent::new());
+        let in_front = target(&mut world, (0, 0, -24));
+        let profile = AttackProfile::get(AttackType::Horizontal);
+
+        start_attack(&mut world, attacker, AttackType::Horizontal).unwrap();
+        run(&mut world, profile.startup_frames);
+
+        world.dead[attacker] = Some(DeadComponent { killer: None });
+        run(&mut world, 1);
+
+        assert!(world.attacks[attacker].is_none());
+        assert_eq!(100, hitpoints(&world, in_front));
+
+        // Started mid active frames, so the hitbox has to go too
+        world.dead[attacker] = None;
+        start_attack(&mut world, attacker, AttackType::Horizontal).unwrap();
+        run(&mut world, profile.startup_frames + 1);
+        let hitbox = world.attacks[attacker].as_ref().unwrap().hitbox.unwrap();
+
+        world.dead[attacker] = Some(DeadComponent { killer: None });
+        run(&mut world, 1);
+        world.step();
+
+        assert!(world.attacks[attacker].is_none());
+        assert!(world.is_alive(attacker));
+        assert!(!world.is_alive(hitbox));
+    }
+}
@@ -0,0 +1,578 @@
+use super::damage::{self, Damage};
+use crate::ecs::{
+    components::{
+        unit_components::{UnitComponent, HIT_POINTS_PER_WOUND},
+        DamageType,
+    },
+    Entity, World,
+};
+
+/// The highest roll on a six sided die
+const D6_MAX: u8 = 6;
+
+/// A roll of 1 always fails
+const AUTOMATIC_FAILURE: u8 = 1;
+
+/// The steps an attack goes through before it removes a wound
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CombatStep {
+    ToHit,
+    ToWound,
+    ArmorSave,
+}
+
+/// A single die rolled while resolving an attack
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CombatRoll {
+    /// The attack this roll belongs to, starting at 0
+    pub attack: u8,
+    pub step: CombatStep,
+    pub roll: u8,
+    /// The minimum roll needed to pass
+    pub target: u8,
+    /// Whether the step went the attacker's way. A passed armor save is a failure for the attacker.
+    pub success: bool,
+}
+
+/// Everything that happened while one unit attacked another
+#[derive(Clone, Debug, PartialEq)]
+pub struct CombatLog {
+    pub attacker: Entity,
+    pub defender: Entity,
+    pub rolls: Vec<CombatRoll>,
+    pub hits: u8,
+    pub wounds: u8,
+    pub saves: u8,
+    /// Wounds that got through and took hit points from the defender
+    pub wounds_removed: u8,
+    /// Whether the defender was killed
+    pub slain: bool,
+}
+
+impl CombatLog {
+    fn new(attacker: Entity, defender: Entity) -> Self {
+        return Self {
+            attacker: attacker,
+            defender: defender,
+            rolls: vec![],
+            hits: 0,
+            wounds: 0,
+            saves: 0,
+            wounds_removed: 0,
+            slain: false,
+        };
+    }
+
+    /// A human readable account of every roll, one per line
+    pub fn describe(&self) -> String {
+        let mut log = format!("{:?} attacks {:?}\n", self.attacker, self.defender);
+
+        for roll in &self.rolls {
+            let outcome = match (roll.step, roll.success) {
+                (CombatStep::ToHit, true) => "hit",
+                (CombatStep::ToHit, false) => "miss",
+                (CombatStep::ToWound, true) => "wound",
+                (CombatStep::ToWound, false) => "no wound",
+                (CombatStep::ArmorSave, true) => "save failed",
+                (CombatStep::ArmorSave, false) => "saved",
+            };
+
+            log.push_str(&format!(
+                "    attack {}: {:?} rolled {} needing {}+, {}\n",
+                roll.attack + 1,
+                roll.step,
+                roll.roll,
+                roll.target,
+                outcome
+            ));
+        }
+
+        log.push_str(&format!(
+            "    {} hits, {} wounds, {} saved, {} wounds removed",
+            self.hits, self.wounds, self.saves, self.wounds_removed
+        ));
+
+        if self.slain {
+            log.push_str(", slain");
+        }
+
+        log
+    }
+}
+
+/// The roll needed to hit in melee, comparing the attacker's weapon skill against the defender's
+pub fn melee_to_hit(attacker_weapon_skill: u8, defender_weapon_skill: u8) -> u8 {
+    let attacker = attacker_weapon_skill as u32;
+    let defender = defender_weapon_skill as u32;
+
+    if attacker > defender {
+        3
+    } else if defender > attacker * 2 {
+        5
+    } else {
+        4
+    }
+}
+
+/// The roll needed to hit at range. Better ballistic skill lowers the roll, down to 2+.
+pub fn ranged_to_hit(ballistic_skill: u8) -> u8 {
+    let target = (D6_MAX + 1) as i32 - ballistic_skill as i32;
+
+    target.max(2).min(D6_MAX as i32) as u8
+}
+
+/// The roll needed to wound, comparing strength against toughness. Returns None if the attack is too weak to wound.
+pub fn to_wound(strength: u8, toughness: u8) -> Option<u8> {
+    let difference = strength as i32 - toughness as i32;
+
+    match difference {
+        d if d >= 2 => Some(2),
+        1 => Some(3),
+        0 => Some(4),
+        -1 => Some(5),
+        -2 | -3 => Some(6),
+        _ => None,
+    }
+}
+
+/// Resolve all of the attacker's melee attacks against the defender
+pub fn resolve_melee(
+    world: &mut World,
+    attacker: Entity,
+    defender: Entity,
+) -> Result<CombatLog, String> {
+    let (attacking, defending) = units(world, attacker, defender)?;
+    let to_hit = melee_to_hit(attacking.weapon_skill, defending.weapon_skill);
+
+    return Ok(resolve(
+        world,
+        attacker,
+        defender,
+        attacking.melee_atks,
+        to_hit,
+        attacking.melee_str,
+    ));
+}
+
+/// Resolve shots from the attacker against the defender, hitting with the attacker's ballistic skill
+pub fn resolve_ranged(
+    world: &mut World,
+    attacker: Entity,
+    defender: Entity,
+    shots: u8,
+    strength: u8,
+) -> Result<CombatLog, String> {
+    let (attacking, _) = units(world, attacker, defender)?;
+    let to_hit = ranged_to_hit(attacking.ballistic_skill);
+
+    return Ok(resolve(world, attacker, defender, shots, to_hit, strength));
+}
+
+/// Copy the stat lines of both units, failing if either isn't one or the defender has no hit points to lose
+fn units(
+    world: &World,
+    attacker: Entity,
+    defender: Entity,
+) -> Result<(UnitComponent, UnitComponent), String> {
+    let unit = |e: Entity| -> Result<_, String> {
+        match &world.units[e] {
+            Some(unit) if world.is_alive(e) => Ok(unit.clone()),
+            _ => Err(format!("{:?} is not a unit", e)),
+        }
+    };
+
+    let (attacking, defending) = (unit(attacker)?, unit(defender)?);
+
+    for e in [attacker, defender].iter() {
+        if world.dead[*e].is_some() {
+            return Err(format!("{:?} is dead", e));
+        }
+    }
+
+    if world.hitpoints[defender].is_none() {
+        return Err(format!("{:?} has no hit points", defender));
+    }
+
+    return Ok((attacking, defending));
+}
+
+/// Roll each attack through to hit, to wound and armor saves. Each wound that gets through is dealt as physical damage,
+/// so the defender's resistances apply. Attacks stop once the defender is dead or invulnerable.
+fn resolve(
+    world: &mut World,
+    attacker: Entity,
+    defender: Entity,
+    attacks: u8,
+    to_hit: u8,
+    strength: u8,
+) -> CombatLog {
+    let mut log = CombatLog::new(attacker, defender);
+
+    for attack in 0..attacks {
+        if world.dead[defender].is_some() || damage::is_invulnerable(world, defender) {
+            break;
+        }
+
+        let (toughness, armor_save) = match &world.units[defender] {
+            Some(unit) => (unit.toughness, unit.armor_save),
+            None => break,
+        };
+
+        if !roll(world, &mut log, attack, CombatStep::ToHit, to_hit) {
+            continue;
+        }
+        log.hits += 1;
+
+        let to_wound = match to_wound(strength, toughness) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        if !roll(world, &mut log, attack, CombatStep::ToWound, to_wound) {
+            continue;
+        }
+        log.wounds += 1;
+
+        // A save of 0, or one that can't be rolled, means the defender has no armor
+        if armor_save > 0 && armor_save <= D6_MAX {
+            if !roll(world, &mut log, attack, CombatStep::ArmorSave, armor_save) {
+                log.saves += 1;
+                continue;
+            }
+        }
+
+        let wound = Damage::new(HIT_POINTS_PER_WOUND, DamageType::Physical, Some(attacker));
+        if damage::apply_damage(world, defender, wound) > 0 {
+            log.wounds_removed += 1;
+        }
+    }
+
+    log.slain = world.dead[defender].is_some();
+
+    log
+}
+
+/// Roll a d6 for the step and log it, returning whether it went the attacker's way
+fn roll(world: &mut World, log: &mut CombatLog, attack: u8, step: CombatStep, target: u8) -> bool {
+    let roll = world.rng.d6();
+    let passed = roll != AUTOMATIC_FAILURE && roll >= target;
+
+    // The defender rolls saves, so a pass is bad for the attacker
+    let success = match step {
+        CombatStep::ArmorSave => !passed,
+        _ => passed,
+    };
+
+    log.rolls.push(CombatRoll {
+        attack: attack,
+        step: step,
+        roll: roll,
+        target: target,
+        success: success,
+    });
+
+    success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::{InvulnerabilityComponent, ResistancesComponent};
+    use crate::ecs::test_fixtures::hitpoints;
+
+    fn unit(world: &mut World, weapon_skill: u8, strength: u8, toughness: u8) -> Entity {
+        let e = crate::ecs::test_fixtures::unit(world);
+        let unit = world.units[e].as_mut().unwrap();
+        unit.weapon_skill = weapon_skill;
+        unit.melee_str = strength;
+        unit.toughness = toughness;
+
+        e
+    }
+
+    fn set_wounds(world: &mut World, e: Entity, wounds: u8) {
+        let unit = world.units[e].as_mut().unwrap();
+        unit.wounds = wounds;
+        world.hitpoints[e] = Some(unit.hitpoints());
+    }
+
+    #[test]
+    fn melee_to_hit_chart() {
+        let chart = [
+            // (attacker, defender, roll needed)
+            (1, 1, 4),
+            (4, 4, 4),
+            (5, 4, 3),
+            (10, 1, 3),
+            (3, 4, 4),
+            (3, 6, 4),
+            (3, 7, 5),
+            (1, 3, 5),
+            (2, 4, 4),
+            (0, 1, 5),
+            (255, 255, 4),
+            (127, 255, 5),
+        ];
+
+        for (attacker, defender, expected) in chart.iter() {
+            assert_eq!(
+                *expected,
+                melee_to_hit(*attacker, *defender),
+                "WS {} vs WS {}",
+                attacker,
+                defender
+            );
+        }
+    }
+
+    #[test]
+    fn ranged_to_hit_chart() {
+        let chart = [
+            (0, 6),
+            (1, 6),
+            (2, 5),
+            (3, 4),
+            (4, 3),
+            (5, 2),
+            (6, 2),
+            (10, 2),
+        ];
+
+        for (ballistic_skill, expected) in chart.iter() {
+            assert_eq!(
+                *expected,
+                ranged_to_hit(*ballistic_skill),
+                "BS {}",
+                ballistic_skill
+            );
+        }
+    }
+
+    #[test]
+    fn to_wound_chart() {
+        let chart = [
+            // (strength, toughness, roll needed)
+            (4, 4, Some(4)),
+            (5, 4, Some(3)),
+            (6, 4, Some(2)),
+            (10, 1, Some(2)),
+            (3, 4, Some(5)),
+            (2, 4, Some(6)),
+            (1, 4, Some(6)),
+            (1, 5, None),
+            (0, 10, None),
+            (1, 1, Some(4)),
+            (255, 0, Some(2)),
+        ];
+
+        for (strength, toughness, expected) in chart.iter() {
+            assert_eq!(
+                *expected,
+                to_wound(*strength, *toughness),
+                "S {} vs T {}",
+                strength,
+                toughness
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_melee_log_is_consistent() {
+        let mut world = World::builder().seed(7).build().unwrap();
+        let attacker = unit(&mut world, 4, 4, 4);
+        let defender = unit(&mut world, 3, 3, 3);
+        world.units[attacker].as_mut().unwrap().melee_atks = 20;
+        set_wounds(&mut world, defender, 50);
+        world.units[defender].as_mut().unwrap().armor_save = 5;
+
+        let log = resolve_melee(&mut world, attacker, defender).unwrap();
+
+        let count = |step: CombatStep, success: bool| {
+            log.rolls
+                .iter()
+                .filter(|r| r.step == step && r.success == success)
+                .count() as u8
+        };
+
+        assert_eq!(
+            20,
+            count(CombatStep::ToHit, true) + count(CombatStep::ToHit, false)
+        );
+        assert_eq!(log.hits, count(CombatStep::ToHit, true));
+        assert_eq!(
+            log.hits,
+            count(CombatStep::ToWound, true) + count(CombatStep::ToWound, false)
+        );
+        assert_eq!(log.wounds, count(CombatStep::ToWound, true));
+        assert_eq!(log.saves, count(CombatStep::ArmorSave, false));
+        assert_eq!(log.wounds - log.saves, log.wounds_removed);
+        assert_eq!(
+            (50 - log.wounds_removed as u32) * HIT_POINTS_PER_WOUND,
+            hitpoints(&world, defender)
+        );
+        assert!(!log.slain);
+
+        for roll in &log.rolls {
+            let target = match roll.step {
+                CombatStep::ToHit => 3,
+                CombatStep::ToWound => 3,
+                CombatStep::ArmorSave => 5,
+            };
+
+            assert_eq!(target, roll.target);
+            assert!(roll.roll >= 1 && roll.roll <= 6);
+        }
+
+        let description = log.describe();
+        assert_eq!(log.rolls.len() + 2, description.lines().count());
+    }
+
+    #[test]
+    fn resolve_melee_is_deterministic() {
+        let fight = |seed: u64| {
+            let mut world = World::builder().seed(seed).build().unwrap();
+            let attacker = unit(&mut world, 4, 4, 4);
+            let defender = unit(&mut world, 4, 4, 4);
+            world.units[attacker].as_mut().unwrap().melee_atks = 10;
+            set_wounds(&mut world, defender, 20);
+
+            resolve_melee(&mut world, attacker, defender).unwrap()
+        };
+
+        assert_eq!(fight(3), fight(3));
+        assert_ne!(fight(3).rolls, fight(4).rolls);
+    }
+
+    #[test]
+    fn resolve_melee_slays() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = unit(&mut world, 10, 10, 1);
+        let defender = unit(&mut world, 1, 1, 1);
+        world.units[attacker].as_mut().unwrap().melee_atks = 100;
+        set_wounds(&mut world, defender, 2);
+
+        let log = resolve_melee(&mut world, attacker, defender).unwrap();
+
+        assert!(log.slain);
+        assert_eq!(2, log.wounds_removed);
+        assert_eq!(0, log.saves);
+        assert!(log.rolls.iter().all(|r| r.step != CombatStep::ArmorSave));
+        assert_eq!(0, hitpoints(&world, defender));
+        assert_eq!(
+            Some(attacker),
+            world.dead[defender].as_ref().unwrap().killer
+        );
+
+        // Stops attacking once the defender is slain
+        assert!(
+            log.rolls
+                .iter()
+                .filter(|r| r.step == CombatStep::ToHit)
+                .count()
+                < 100
+        );
+        assert!(log.describe().ends_with("slain"));
+    }
+
+    #[test]
+    fn resolve_melee_wounds_are_resisted() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = unit(&mut world, 10, 10, 1);
+        let defender = unit(&mut world, 1, 1, 1);
+        world.units[attacker].as_mut().unwrap().melee_atks = 100;
+        set_wounds(&mut world, defender, 4);
+
+        let mut resistances = ResistancesComponent::new();
+        resistances.set(DamageType::Physical, 50);
+        world.resistances[defender] = Some(resistances);
+
+        let log = resolve_melee(&mut world, attacker, defender).unwrap();
+
+        // Each wound only takes half of its hit points
+        assert!(log.slain);
+        assert_eq!(8, log.wounds_removed);
+
+        world.events.update();
+        let dealt = world.events.damage_dealt.read();
+        assert_eq!(8, dealt.len());
+        assert!(dealt.iter().all(
+            |event| event.amount == HIT_POINTS_PER_WOUND / 2 && event.source == Some(attacker)
+        ));
+    }
+
+    #[test]
+    fn resolve_melee_respects_invulnerability_frames() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = unit(&mut world, 10, 10, 1);
+        let defender = unit(&mut world, 1, 1, 1);
+        world.units[attacker].as_mut().unwrap().melee_atks = 20;
+        set_wounds(&mut world, defender, 4);
+        world.invulnerabilities[defender] = Some(InvulnerabilityComponent::new(10));
+
+        // The first wound starts the i-frames, which stop the rest of the attacks
+        let log = resolve_melee(&mut world, attacker, defender).unwrap();
+
+        assert_eq!(1, log.wounds);
+        assert_eq!(1, log.wounds_removed);
+        assert_eq!(3 * HIT_POINTS_PER_WOUND, hitpoints(&world, defender));
+
+        let log = resolve_melee(&mut world, attacker, defender).unwrap();
+
+        assert!(log.rolls.is_empty());
+        assert_eq!(0, log.wounds_removed);
+        assert_eq!(3 * HIT_POINTS_PER_WOUND, hitpoints(&world, defender));
+    }
+
+    #[test]
+    fn resolve_melee_too_weak_to_wound() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = unit(&mut world, 10, 1, 1);
+        let defender = unit(&mut world, 1, 1, 10);
+        world.units[attacker].as_mut().unwrap().melee_atks = 10;
+
+        let log = resolve_melee(&mut world, attacker, defender).unwrap();
+
+        assert!(log.hits > 0);
+        assert_eq!(0, log.wounds);
+        assert!(log.rolls.iter().all(|r| r.step == CombatStep::ToHit));
+    }
+
+    #[test]
+    fn resolve_ranged_uses_ballistic_skill() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = unit(&mut world, 1, 1, 1);
+        let defender = unit(&mut world, 1, 1, 1);
+        world.units[attacker].as_mut().unwrap().ballistic_skill = 4;
+        set_wounds(&mut world, defender, 10);
+
+        let log = resolve_ranged(&mut world, attacker, defender, 5, 4).unwrap();
+
+        let hit_rolls: Vec<&CombatRoll> = log
+            .rolls
+            .iter()
+            .filter(|r| r.step == CombatStep::ToHit)
+            .collect();
+
+        assert_eq!(5, hit_rolls.len());
+        assert!(hit_rolls.iter().all(|r| r.target == 3));
+    }
+
+    #[test]
+    fn resolve_requires_units() {
+        let mut world = World::builder().build().unwrap();
+        let attacker = unit(&mut world, 1, 1, 1);
+        let not_a_unit = world.add_entity();
+
+        assert!(resolve_melee(&mut world, attacker, not_a_unit).is_err());
+        assert!(resolve_ranged(&mut world, not_a_unit, attacker, 1, 1).is_err());
+
+        let without_hitpoints = unit(&mut world, 1, 1, 1);
+        world.hitpoints[without_hitpoints] = None;
+        assert!(resolve_melee(&mut world, attacker, without_hitpoints).is_err());
+
+        let dead = unit(&mut world, 1, 1, 1);
+        damage::kill(&mut world, dead, None);
+        assert!(resolve_melee(&mut world, attacker, dead).is_err());
+        assert!(resolve_melee(&mut world, dead, attacker).is_err());
+    }
+}
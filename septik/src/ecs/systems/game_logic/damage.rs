@@ -0,0 +1,398 @@
+use crate::ecs::{
+    components::{DamageType, DeadComponent},
+    events, Entity, Join, World,
+};
+
+/// A single instance of damage to apply to a character
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Damage {
+    pub amount: u32,
+    pub damage_type: DamageType,
+    /// The entity that dealt the damage, if any
+    pub source: Option<Entity>,
+}
+
+impl Damage {
+    pub fn new(amount: u32, damage_type: DamageType, source: Option<Entity>) -> Self {
+        return Self {
+            amount: amount,
+            damage_type: damage_type,
+            source: source,
+        };
+    }
+}
+
+/// Apply damage to the target after resistances, returning the hit points lost.
+/// Physical damage is blocked while the target is invulnerable and starts its invulnerability frames; damage over time is not.
+/// A fatal hit marks the target as dead.
+pub fn apply_damage(world: &mut World, target: Entity, damage: Damage) -> u32 {
+    if world.dead[target].is_some() {
+        return 0;
+    }
+
+    let is_physical = damage.damage_type == DamageType::Physical;
+
+    if is_physical && is_invulnerable(world, target) {
+        return 0;
+    }
+
+    let resistance = match &world.resistances[target] {
+        Some(resistances) => resistances.get(damage.damage_type) as u32,
+        None => 0,
+    };
+
+    let hitpoints = match world.hitpoints[target].as_mut() {
+        Some(hitpoints) => hitpoints,
+        None => return 0,
+    };
+
+    // Done in u64 so large hits can't overflow. Resisting can only lower the amount, so it fits back in a u32.
+    let resisted = damage.amount as u64 * (100 - resistance) as u64 / 100;
+    let amount = hitpoints.saturating_sub(resisted as u32);
+    if amount == 0 {
+        return 0;
+    }
+
+    let is_fatal = hitpoints.is_empty();
+
+    world.events.damage_dealt.publish(events::DamageDealt {
+        source: damage.source,
+        target: target,
+        amount: amount,
+        damage_type: damage.damage_type,
+    });
+
+    if is_physical {
+        if let Some(invulnerability) = world.invulnerabilities[target].as_mut() {
+            invulnerability.remaining_ticks = invulnerability.ticks_on_hit;
+        }
+    }
+
+    if is_fatal {
+        kill(world, target, damage.source);
+    }
+
+    return amount;
+}
+
+/// Whether the target is in its invulnerability frames, such as while dodging
+pub fn is_invulnerable(world: &World, target: Entity) -> bool {
+    match &world.invulnerabilities[target] {
+        Some(invulnerability) => invulnerability.is_invulnerable(),
+        None => false,
+    }
+}
+
+/// Restore hit points up to the target's max, returning the amount healed. The dead can't be healed.
+pub fn heal(world: &mut World, target: Entity, amount: u32) -> u32 {
+    if world.dead[target].is_some() {
+        return 0;
+    }
+
+    let hitpoints = match world.hitpoints[target].as_mut() {
+        Some(hitpoints) => hitpoints,
+        None => return 0,
+    };
+
+    return hitpoints.saturating_add(amount);
+}
+
+/// Bring a dead character back with the given hit points, up to its max
+pub fn revive(world: &mut World, target: Entity, hitpoints: u32) -> Result<(), String> {
+    if world.dead[target].is_none() {
+        return Err(format!("{:?} isn't dead", target));
+    }
+
+    if hitpoints == 0 {
+        return Err(format!("{:?} needs hit points to be revived", target));
+    }
+
+    match world.hitpoints[target].as_mut() {
+        Some(current) => current.value = hitpoints.min(current.max_value),
+        None => return Err(format!("{:?} has no hit points", target)),
+    }
+
+    world.dead[target] = None;
+
+    Ok(())
+}
+
+/// Kill the target regardless of resistances or invulnerability, marking it as dead
+pub fn kill(world: &mut World, target: Entity, killer: Option<Entity>) {
+    if world.dead[target].is_some() {
+        return;
+    }
+
+    if let Some(hitpoints) = world.hitpoints[target].as_mut() {
+        hitpoints.drain();
+    }
+
+    world.dead[target] = Some(DeadComponent { killer: killer });
+    world.events.entity_died.publish(events::EntityDied {
+        entity: target,
+        killer: killer,
+    });
+}
+
+/// This system counts down invulnerability frames
+pub fn invulnerability_system(world: &mut World) {
+    let entities = world.entities();
+
+    for (_, invulnerability) in (&mut world.invulnerabilities).join(&entities) {
+        invulnerability.remaining_ticks = invulnerability.remaining_ticks.saturating_sub(1);
+    }
+}
+
+/// This system marks characters without hit points as dead and despawns them. Players are only marked, so they can be revived with `revive`.
+pub fn death_system(world: &mut World) {
+    let entities = world.entities();
+
+    for e in entities.iter() {
+        let is_depleted = match &world.hitpoints[*e] {
+            Some(hitpoints) => hitpoints.is_empty(),
+            None => false,
+        };
+
+        if is_depleted {
+            kill(world, *e, None);
+        }
+
+        if world.dead[*e].is_some() && world.players[*e].is_none() {
+            world.delete_entity(*e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::{
+        HitPointComponent, InvulnerabilityComponent, PlayerComponent, ResistancesComponent,
+    };
+    use crate::ecs::test_fixtures::hitpoints;
+    use crate::lib_core::{EngineInputs, InputType};
+
+    fn character(hitpoints: u32) -> (World, Entity) {
+        let mut world = World::builder().build().unwrap();
+        let e = crate::ecs::test_fixtures::character(&mut world, hitpoints);
+
+        (world, e)
+    }
+
+    #[test]
+    fn apply_damage_publishes_event() {
+        let (mut world, e) = character(10);
+        let attacker = world.add_entity();
+
+        let dealt = apply_damage(
+            &mut world,
+            e,
+            Damage::new(4, DamageType::Physical, Some(attacker)),
+        );
+
+        assert_eq!(4, dealt);
+        assert_eq!(6, hitpoints(&world, e));
+        assert!(world.dead[e].is_none());
+
+        world.events.update();
+        assert_eq!(
+            &[events::DamageDealt {
+                source: Some(attacker),
+                target: e,
+                amount: 4,
+                damage_type: DamageType::Physical
+            }],
+            world.events.damage_dealt.read()
+        );
+    }
+
+    #[test]
+    fn apply_damage_resistances() {
+        let (mut world, e) = character(100);
+        let mut resistances = ResistancesComponent::new();
+        resistances.set(DamageType::Physical, 50);
+        resistances.set(DamageType::Poison, 100);
+        world.resistances[e] = Some(resistances);
+
+        assert_eq!(
+            5,
+            apply_damage(&mut world, e, Damage::new(10, DamageType::Physical, None))
+        );
+        assert_eq!(
+            0,
+            apply_damage(&mut world, e, Damage::new(10, DamageType::Poison, None))
+        );
+        assert_eq!(
+            10,
+            apply_damage(&mut world, e, Damage::new(10, DamageType::Bleed, None))
+        );
+        assert_eq!(85, hitpoints(&world, e));
+    }
+
+    #[test]
+    fn apply_damage_huge_hit() {
+        let (mut world, e) = character(u32::MAX);
+        let mut resistances = ResistancesComponent::new();
+        resistances.set(DamageType::Physical, 50);
+        world.resistances[e] = Some(resistances);
+
+        assert_eq!(
+            u32::MAX / 2,
+            apply_damage(
+                &mut world,
+                e,
+                Damage::new(u32::MAX, DamageType::Physical, None)
+            )
+        );
+        assert!(world.dead[e].is_none());
+    }
+
+    #[test]
+    fn apply_damage_invulnerability_frames() {
+        let (mut world, e) = character(100);
+        world.invulnerabilities[e] = Some(InvulnerabilityComponent::new(2));
+
+        let hit = Damage::new(10, DamageType::Physical, None);
+
+        assert_eq!(10, apply_damage(&mut world, e, hit));
+        assert_eq!(0, apply_damage(&mut world, e, hit));
+
+        // Damage over time still applies
+        assert_eq!(
+            10,
+            apply_damage(&mut world, e, Damage::new(10, DamageType::Bleed, None))
+        );
+
+        invulnerability_system(&mut world);
+        assert_eq!(0, apply_damage(&mut world, e, hit));
+
+        invulnerability_system(&mut world);
+        assert_eq!(10, apply_damage(&mut world, e, hit));
+        assert_eq!(70, hitpoints(&world, e));
+    }
+
+    #[test]
+    fn apply_damage_fatal() {
+        let (mut world, e) = character(10);
+        let attacker = world.add_entity();
+
+        let dealt = apply_damage(
+            &mut world,
+            e,
+            Damage::new(50, DamageType::Physical, Some(attacker)),
+        );
+
+        assert_eq!(10, dealt);
+        assert_eq!(0, hitpoints(&world, e));
+        assert_eq!(
+            Some(DeadComponent {
+                killer: Some(attacker)
+            }),
+            world.dead[e]
+        );
+
+        // The dead take no further damage
+        assert_eq!(
+            0,
+            apply_damage(&mut world, e, Damage::new(5, DamageType::Bleed, None))
+        );
+
+        world.events.update();
+        assert_eq!(
+            &[events::EntityDied {
+                entity: e,
+                killer: Some(attacker)
+            }],
+            world.events.entity_died.read()
+        );
+    }
+
+    #[test]
+    fn heal_clamps_to_max() {
+        let (mut world, e) = character(10);
+        world.hitpoints[e].as_mut().unwrap().value = 4;
+
+        assert_eq!(3, heal(&mut world, e, 3));
+        assert_eq!(3, heal(&mut world, e, 20));
+        assert_eq!(10, hitpoints(&world, e));
+
+        kill(&mut world, e, None);
+        assert_eq!(0, heal(&mut world, e, 5));
+        assert_eq!(0, hitpoints(&world, e));
+    }
+
+    #[test]
+    fn revive_restores_hitpoints() {
+        let (mut world, e) = character(10);
+
+        assert!(revive(&mut world, e, 5).is_err());
+
+        kill(&mut world, e, None);
+        assert!(revive(&mut world, e, 0).is_err());
+        assert!(world.dead[e].is_some());
+
+        assert!(revive(&mut world, e, 50).is_ok());
+        assert!(world.dead[e].is_none());
+        assert_eq!(10, hitpoints(&world, e));
+
+        // Can be hurt and killed again
+        assert_eq!(
+            10,
+            apply_damage(&mut world, e, Damage::new(10, DamageType::Physical, None))
+        );
+        assert!(world.dead[e].is_some());
+    }
+
+    #[test]
+    fn revive_lets_players_move_again() {
+        let mut world = World::new();
+        let player = world.player().unwrap();
+        let inputs = vec![InputType::Held(0, EngineInputs::MoveRight)];
+        let position = |world: &World| world.transforms[player].as_ref().unwrap().position;
+        world.hitpoints[player] = Some(HitPointComponent::new(10));
+
+        kill(&mut world, player, None);
+        let start = position(&world);
+
+        world.register_player_inputs(&inputs);
+        world.step();
+        assert_eq!(start, position(&world));
+
+        revive(&mut world, player, 1).unwrap();
+        world.register_player_inputs(&inputs);
+        world.step();
+        assert!(position(&world).x > start.x);
+    }
+
+    #[test]
+    fn kill_only_once() {
+        let (mut world, e) = character(10);
+
+        kill(&mut world, e, None);
+        kill(&mut world, e, None);
+
+        world.events.update();
+        assert_eq!(1, world.events.entity_died.read().len());
+    }
+
+    #[test]
+    fn death_system_despawns_non_players() {
+        let (mut world, enemy) = character(10);
+        let player = world.add_entity();
+        world.players[player] = Some(PlayerComponent {});
+        world.hitpoints[player] = Some(HitPointComponent::new(10));
+
+        apply_damage(
+            &mut world,
+            enemy,
+            Damage::new(10, DamageType::Physical, Some(player)),
+        );
+        world.hitpoints[player].as_mut().unwrap().value = 0;
+
+        world.step();
+
+        assert!(!world.is_alive(enemy));
+        assert!(world.is_alive(player));
+        assert_eq!(Some(DeadComponent { killer: None }), world.dead[player]);
+    }
+}
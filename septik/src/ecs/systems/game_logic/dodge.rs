@@ -0,0 +1,376 @@
+use crate::ecs::{
+    components::{DodgeComponent, InvulnerabilityComponent},
+    Entity, Join, World,
+};
+use crate::lib_core::{math::FixedNumber, Direction, EngineInputs, InputType};
+
+/// Ticks the velocity burst lasts
+pub const DODGE_TICKS: u32 = 12;
+
+/// Distance moved each tick of the burst
+pub const DODGE_SPEED: i32 = 24;
+
+/// Ticks the character can't be damaged, starting with the dodge
+pub const DODGE_INVULNERABLE_TICKS: u32 = 8;
+
+/// Ticks from the start of a dodge until the character can dodge again
+pub const DODGE_COOLDOWN: u32 = 30;
+
+/// Stamina spent on each dodge
+pub const DODGE_STAMINA_COST: u32 = 30;
+
+/// Start a dodge in the direction, if the character has the stamina and isn't already dodging or cooling down
+pub fn start_dodge(world: &mut World, e: Entity, direction: Direction) -> Result<(), String> {
+    if world.dodges[e].is_some() {
+        return Err(format!("{:?} can't dodge yet", e));
+    }
+
+    if world.dead[e].is_some() {
+        return Err(format!("{:?} is dead", e));
+    }
+
+    let stamina = match world.staminas[e].as_mut() {
+        Some(stamina) => stamina,
+        None => return Err(format!("{:?} has no stamina", e)),
+    };
+
+    if stamina.meter.value < DODGE_STAMINA_COST {
+        return Err(format!(
+            "{:?} needs {} stamina but has {}",
+            e, DODGE_STAMINA_COST, stamina.meter.value
+        ));
+    }
+
+    stamina.meter.saturating_sub(DODGE_STAMINA_COST);
+
+    world.dodges[e] = Some(DodgeComponent {
+        direction: direction,
+        remaining_ticks: DODGE_TICKS,
+        cooldown_ticks: DODGE_COOLDOWN,
+    });
+
+    let invulnerability =
+        world.invulnerabilities[e].get_or_insert_with(|| InvulnerabilityComponent::new(0));
+    invulnerability.remaining_ticks = invulnerability
+        .remaining_ticks
+        .max(DODGE_INVULNERABLE_TICKS);
+
+    Ok(())
+}
+
+/// This system starts dodges from inputs, applies their velocity bursts and regenerates stamina.
+/// It runs after character actions, so the burst replaces the movement from inputs.
+pub fn dodge_system(world: &mut World) {
+    let entities = world.entities();
+
+    let mut started = vec![];
+    for (e, engine_inputs) in (&world.engine_inputs).join(&entities) {
+        let dodge_pressed = engine_inputs.inputs.iter().any(|input| match input {
+            InputType::Pressed(_, EngineInputs::Dodge) => true,
+            _ => false,
+        });
+
+        if dodge_pressed {
+            started.push((e, dodge_direction(world, e)));
+        }
+    }
+
+    for (e, direction) in started {
+        // Pressing dodge while unable to does nothing
+        let _ = start_dodge(world, e, direction);
+    }
+
+    for (e, stamina) in (&mut world.staminas).join(&entities) {
+        // Stamina doesn't come back mid dodge
+        let is_dodging = match &world.dodges[e] {
+            Some(dodge) => dodge.remaining_ticks > 0,
+            None => false,
+        };
+
+        if !is_dodging {
+            stamina.meter.saturating_add(stamina.regen_per_tick);
+        }
+    }
+
+    let speed: FixedNumber = DODGE_SPEED.into();
+    let mut finished = vec![];
+
+    for (e, dodge) in (&mut world.dodges).join(&entities) {
+        if dodge.remaining_ticks > 0 {
+            dodge.remaining_ticks -= 1;
+
+            if let Some(velocity) = world.velocities[e].as_mut() {
+                velocity.value = dodge.direction.to_vec3d().multiply(speed);
+            }
+        }
+
+        dodge.cooldown_ticks = dodge.cooldown_ticks.saturating_sub(1);
+        if dodge.cooldown_ticks == 0 {
+            finished.push(e);
+        }
+    }
+
+    for e in finished {
+        world.dodges[e] = None;
+    }
+}
+
+/// Snap the way the character is moving to a direction, or use the way it's facing if it isn't moving.
+/// Runs after character actions, so the velocity has already been rotated to match the camera.
+fn dodge_direction(world: &World, e: Entity) -> Direction {
+    let movement = match &world.velocities[e] {
+        Some(velocity) => Direction::from_vec3d(velocity.value),
+        None => None,
+    };
+
+    if let Some(direction) = movement {
+        return direction;
+    }
+
+    match &world.facing_direction[e] {
+        Some(facing) => facing.direction,
+        None => Direction::North,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::{
+        EngineInputsComponent, FacingComponent, StaminaComponent, VelocityComponent,
+    };
+    use crate::lib_core::math::Vec3d;
+
+    fn dodger(world: &mut World) -> Entity {
+        let e = world.add_entity();
+        world.staminas[e] = Some(StaminaComponent::new(100, 2));
+        world.velocities[e] = Some(VelocityComponent::new());
+        world.facing_direction[e] = Some(FacingComponent::new(Direction::South));
+        world.engine_inputs[e] = Some(EngineInputsComponent::new());
+
+        e
+    }
+
+    fn press_dodge(world: &mut World, e: Entity, movement: (i32, i32, i32)) {
+        world.velocities[e].as_mut().unwrap().value = movement.into();
+        world.engine_inputs[e].as_mut().unwrap().inputs =
+            vec![InputType::Pressed(0, EngineInputs::Dodge)];
+    }
+
+    fn clear_inputs(world: &mut World, e: Entity) {
+        world.engine_inputs[e].as_mut().unwrap().inputs.clear();
+    }
+
+    fn stamina(world: &World, e: Entity) -> u32 {
+        world.staminas[e].as_ref().unwrap().meter.value
+    }
+
+    #[test]
+    fn dodge_system_snaps_to_movement() {
+        let cases = vec![
+            ((0, 0, -5), Direction::North),
+            ((5, 0, -5), Direction::NorthEast),
+            ((-5, 0, 5), Direction::SouthWest),
+            ((-5, 0, 0), Direction::West),
+            ((5, 0, -1), Direction::East),
+            // Not moving horizontally, so the facing is used
+            ((0, 8, 0), Direction::South),
+            ((0, 0, 0), Direction::South),
+        ];
+
+        for (movement, expected) in cases {
+            let mut world = World::builder().build().unwrap();
+            let e = dodger(&mut world);
+
+            press_dodge(&mut world, e, movement);
+            dodge_system(&mut world);
+
+            assert_eq!(
+                expected,
+                world.dodges[e].as_ref().unwrap().direction,
+                "{:?}",
+                movement
+            );
+        }
+    }
+
+    #[test]
+    fn dodge_system_velocity_burst() {
+        let mut world = World::builder().build().unwrap();
+        let e = dodger(&mut world);
+
+        press_dodge(&mut world, e, (5, 0, 0));
+        dodge_system(&mut world);
+        clear_inputs(&mut world, e);
+
+        let burst: Vec3d = (DODGE_SPEED, 0, 0).into();
+        assert_eq!(burst, world.velocities[e].as_ref().unwrap().value);
+
+        for _ in 1..DODGE_TICKS {
+            world.velocities[e].as_mut().unwrap().value = Vec3d::default();
+            dodge_system(&mut world);
+            assert_eq!(burst, world.velocities[e].as_ref().unwrap().value);
+        }
+
+        // The burst is over, but the cooldown isn't
+        world.velocities[e].as_mut().unwrap().value = Vec3d::default();
+        dodge_system(&mut world);
+
+        assert_eq!(
+            Vec3d::default(),
+            world.velocities[e].as_ref().unwrap().value
+        );
+        assert_eq!(0, world.dodges[e].as_ref().unwrap().remaining_ticks);
+    }
+
+    #[test]
+    fn dodge_system_invulnerability_frames() {
+        let mut world = World::builder().build().unwrap();
+        let e = dodger(&mut world);
+
+        press_dodge(&mut world, e, (0, 0, 0));
+        dodge_system(&mut world);
+
+        assert_eq!(
+            Some(InvulnerabilityComponent {
+                remaining_ticks: DODGE_INVULNERABLE_TICKS,
+                ticks_on_hit: 0
+            }),
+            world.invulnerabilities[e]
+        );
+
+        // Longer i-frames aren't cut short
+        world.dodges[e] = None;
+        world.invulnerabilities[e].as_mut().unwrap().remaining_ticks = 50;
+        dodge_system(&mut world);
+
+        assert_eq!(
+            50,
+            world.invulnerabilities[e].as_ref().unwrap().remaining_ticks
+        );
+    }
+
+    #[test]
+    fn dodge_system_cooldown() {
+        let mut world = World::builder().build().unwrap();
+        let e = dodger(&mut world);
+        world.staminas[e] = Some(StaminaComponent::new(100, 0));
+
+        press_dodge(&mut world, e, (0, 0, 0));
+        dodge_system(&mut world);
+        assert_eq!(100 - DODGE_STAMINA_COST, stamina(&world, e));
+
+        // Holding dodge through the cooldown doesn't start another
+        for _ in 2..DODGE_COOLDOWN {
+            dodge_system(&mut world);
+        }
+        assert_eq!(100 - DODGE_STAMINA_COST, stamina(&world, e));
+
+        assert_eq!(
+            Err(format!("{:?} can't dodge yet", e)),
+            start_dodge(&mut world, e, Direction::North)
+        );
+        assert_eq!(1, world.dodges[e].as_ref().unwrap().cooldown_ticks);
+
+        clear_inputs(&mut world, e);
+        dodge_system(&mut world);
+        assert!(world.dodges[e].is_none());
+
+        assert!(start_dodge(&mut world, e, Direction::North).is_ok());
+    }
+
+    #[test]
+    fn dodge_system_stamina() {
+        let mut world = World::builder().build().unwrap();
+        let e = dodger(&mut world);
+        world.staminas[e] = Some(StaminaComponent::new(50, 2));
+
+        assert!(start_dodge(&mut world, e, Direction::North).is_ok());
+        assert_eq!(20, stamina(&world, e));
+        world.dodges[e] = None;
+
+        // Not enough stamina left
+        assert!(start_dodge(&mut world, e, Direction::North).is_err());
+        assert!(world.dodges[e].is_none());
+
+        // Regenerates up to the max
+        for _ in 0..5 {
+            dodge_system(&mut world);
+        }
+        assert_eq!(30, stamina(&world, e));
+
+        for _ in 0..100 {
+            dodge_system(&mut world);
+        }
+        assert_eq!(50, stamina(&world, e));
+
+        let without_stamina = world.add_entity();
+        assert!(start_dodge(&mut world, without_stamina, Direction::North).is_err());
+    }
+
+    #[test]
+    fn dodge_system_no_regen_mid_dodge() {
+        let mut world = World::builder().build().unwrap();
+        let e = dodger(&mut world);
+
+        press_dodge(&mut world, e, (0, 0, 0));
+        dodge_system(&mut world);
+        clear_inputs(&mut world, e);
+
+        for _ in 1..DODGE_TICKS {
+            dodge_system(&mut world);
+        }
+        assert_eq!(100 - DODGE_STAMINA_COST, stamina(&world, e));
+
+        dodge_system(&mut world);
+        assert_eq!(100 - DODGE_STAMINA_COST + 2, stamina(&world, e));
+    }
+
+    #[test]
+    fn dodge_system_from_player_inputs() {
+        let mut world = World::new();
+        let player = world.player().unwrap();
+        let start = world.transforms[player].as_ref().unwrap().position;
+
+        world.register_player_inputs(&vec![
+            InputType::Held(0, EngineInputs::MoveBack),
+            InputType::Pressed(0, EngineInputs::Dodge),
+        ]);
+        world.step();
+
+        let moved: Vec3d = (0, 0, DODGE_SPEED).into();
+        assert_eq!(
+            start + moved,
+            world.transforms[player].as_ref().unwrap().position
+        );
+        assert!(world.invulnerabilities[player]
+            .as_ref()
+            .unwrap()
+            .is_invulnerable());
+    }
+
+    #[test]
+    fn dodge_system_follows_turned_movement() {
+        let mut world = World::new();
+        let player = world.player().unwrap();
+        let yaw = FixedNumber::PI() / 2.into();
+        world.transforms[player]
+            .as_mut()
+            .unwrap()
+            .rotation
+            .yaw_radians = yaw;
+
+        world.register_player_inputs(&vec![
+            InputType::Held(0, EngineInputs::MoveForward),
+            InputType::Pressed(0, EngineInputs::Dodge),
+        ]);
+        world.step();
+
+        // Forward is wherever the player has turned to
+        assert_eq!(Direction::West, Direction::from_yaw(yaw));
+        assert_eq!(
+            Direction::West,
+            world.dodges[player].as_ref().unwrap().direction
+        );
+    }
+}
@@ -0,0 +1,6 @@
+pub mod ailments;
+pub mod attacks;
+pub mod combat;
+pub mod damage;
+pub mod dodge;
+pub mod overwatch;
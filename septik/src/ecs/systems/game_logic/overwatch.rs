@@ -0,0 +1,327 @@
+use super::{combat, damage};
+use crate::ecs::{events, Entity, Join, World};
+use crate::lib_core::math::Vec3d;
+
+/// This system interrupts moves into overwatched areas. It runs before positions are updated, so a unit slain by reaction fire never completes its move.
+/// Units in their invulnerability frames, such as mid dodge, pass through without triggering it.
+pub fn overwatch_system(world: &mut World) {
+    let entities = world.entities();
+
+    let moves: Vec<(Entity, Vec3d, Vec3d)> = (&world.velocities, &world.transforms, &world.units)
+        .join(&entities)
+        .filter(|(_, (velocity, _, _))| velocity.value != Vec3d::default())
+        .map(|(e, (velocity, transform, _))| {
+            (e, transform.position, transform.position + velocity.value)
+        })
+        .collect();
+
+    for (mover, start, destination) in moves {
+        let watchers: Vec<Entity> = (&world.overwatches, &world.transforms)
+            .join(&entities)
+            .filter(|(watcher, (overwatch, transform))| {
+                is_hostile(world, *watcher, mover)
+                    && overwatch
+                        .area
+                        .crossed_by(transform.position, start, destination)
+            })
+            .map(|(watcher, _)| watcher)
+            .collect();
+
+        for watcher in watchers {
+            if world.dead[mover].is_some() || damage::is_invulnerable(world, mover) {
+                break;
+            }
+
+            if world.dead[watcher].is_some() {
+                continue;
+            }
+
+            // Overwatch only fires once
+            let overwatch = match world.overwatches[watcher].take() {
+                Some(overwatch) => overwatch,
+                None => continue,
+            };
+
+            let log = match combat::resolve_ranged(
+                world,
+                watcher,
+                mover,
+                overwatch.shots,
+                overwatch.strength,
+            ) {
+                Ok(log) => log,
+                Err(_) => continue,
+            };
+
+            world
+                .events
+                .overwatch_triggered
+                .publish(events::OverwatchTriggered {
+                    watcher: watcher,
+                    target: mover,
+                    wounds_removed: log.wounds_removed,
+                    slain: log.slain,
+                });
+
+            if log.slain {
+                if let Some(velocity) = world.velocities[mover].as_mut() {
+                    velocity.value = Vec3d::default();
+                }
+            }
+        }
+    }
+}
+
+/// Whether the units are on opposing sides, with enemies on one side and everyone else on the other
+fn is_hostile(world: &World, a: Entity, b: Entity) -> bool {
+    a != b && world.enemies[a].is_some() != world.enemies[b].is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::{
+        EnemyComponent, OverwatchArea, OverwatchComponent, StaminaComponent, TransformComponent,
+        VelocityComponent,
+    };
+    use crate::ecs::systems::game_logic::dodge;
+    use crate::lib_core::Direction;
+
+    fn unit(world: &mut World, position: (i32, i32, i32)) -> Entity {
+        let e = crate::ecs::test_fixtures::unit(world);
+        let mut transform = TransformComponent::new();
+        transform.position = position.into();
+
+        world.transforms[e] = Some(transform);
+        world.velocities[e] = Some(VelocityComponent::new());
+
+        e
+    }
+
+    fn cone(range: i32) -> OverwatchComponent {
+        OverwatchComponent::new(
+            OverwatchArea::Cone {
+                direction: Direction::North,
+                range: range.into(),
+            },
+            10,
+            10,
+        )
+    }
+
+    /// An enemy that dies to any wound and a watcher that can't miss
+    fn setup(overwatch: OverwatchComponent) -> (World, Entity, Entity) {
+        let mut world = World::builder().build().unwrap();
+
+        let watcher = unit(&mut world, (0, 0, 0));
+        world.units[watcher].as_mut().unwrap().ballistic_skill = 10;
+        world.overwatches[watcher] = Some(overwatch);
+
+        let enemy = unit(&mut world, (0, 0, -20));
+        world.enemies[enemy] = Some(EnemyComponent::new());
+
+        (world, watcher, enemy)
+    }
+
+    fn move_by(world: &mut World, e: Entity, offset: (i32, i32, i32)) {
+        world.velocities[e].as_mut().unwrap().value = offset.into();
+    }
+
+    #[test]
+    fn OverwatchArea_cone() {
+        let area = OverwatchArea::Cone {
+            direction: Direction::East,
+            range: 10.into(),
+        };
+        let origin: Vec3d = (5, 0, 5).into();
+
+        let inside = [
+            (10, 0, 5),
+            (14, 0, 5),
+            (10, 0, 8),
+            (10, 0, 2),
+            (10, 50, 5),
+            (5, 0, 5),
+        ];
+        let outside = [(16, 0, 5), (0, 0, 5), (6, 0, 10), (5, 0, 0), (14, 0, 14)];
+
+        for point in inside.iter() {
+            assert!(area.contains(origin, (*point).into()), "{:?}", point);
+        }
+        for point in outside.iter() {
+            assert!(!area.contains(origin, (*point).into()), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn OverwatchArea_area() {
+        let area = OverwatchArea::Area {
+            center: (10, 0, 10).into(),
+            radius: 5.into(),
+        };
+        let watcher: Vec3d = (100, 0, 100).into();
+
+        assert!(area.contains(watcher, (10, 0, 10).into()));
+        assert!(area.contains(watcher, (13, 0, 14).into()));
+        assert!(!area.contains(watcher, (16, 0, 10).into()));
+        assert!(!area.contains(watcher, watcher));
+    }
+
+    #[test]
+    fn overwatch_system_interrupts_moves() {
+        let (mut world, watcher, enemy) = setup(cone(15));
+        let start = world.transforms[enemy].as_ref().unwrap().position;
+
+        move_by(&mut world, enemy, (0, 0, 10));
+        overwatch_system(&mut world);
+
+        assert!(world.overwatches[watcher].is_none());
+        assert_eq!(Some(watcher), world.dead[enemy].as_ref().unwrap().killer);
+        assert_eq!(
+            Vec3d::default(),
+            world.velocities[enemy].as_ref().unwrap().value
+        );
+
+        world.events.update();
+        assert_eq!(
+            &[events::OverwatchTriggered {
+                watcher: watcher,
+                target: enemy,
+                wounds_removed: 1,
+                slain: true
+            }],
+            world.events.overwatch_triggered.read()
+        );
+
+        // The move never completes
+        crate::ecs::systems::position_update_system(&mut world);
+        assert_eq!(start, world.transforms[enemy].as_ref().unwrap().position);
+    }
+
+    #[test]
+    fn overwatch_system_ignores_moves_outside() {
+        let (mut world, watcher, enemy) = setup(cone(5));
+
+        move_by(&mut world, enemy, (0, 0, 10));
+        overwatch_system(&mut world);
+
+        assert!(world.overwatches[watcher].is_some());
+        assert!(world.dead[enemy].is_none());
+
+        // Standing still inside the area doesn't trigger it
+        world.transforms[enemy].as_mut().unwrap().position = (0, 0, -2).into();
+        move_by(&mut world, enemy, (0, 0, 0));
+        overwatch_system(&mut world);
+
+        assert!(world.overwatches[watcher].is_some());
+    }
+
+    #[test]
+    fn OverwatchArea_crossed_by() {
+        let cone = OverwatchArea::Cone {
+            direction: Direction::North,
+            range: 10.into(),
+        };
+        let area = OverwatchArea::Area {
+            center: (0, 0, -20).into(),
+            radius: 3.into(),
+        };
+        let watcher = Vec3d::default();
+        let crossed = |area: &OverwatchArea, start: (i32, i32, i32), end: (i32, i32, i32)| {
+            area.crossed_by(watcher, start.into(), end.into())
+        };
+
+        // Passing straight through without stopping inside
+        assert!(crossed(&cone, (-20, 0, -5), (20, 0, -5)));
+        assert!(crossed(&area, (-10, 0, -20), (10, 0, -20)));
+        // Cutting through the arc
+        assert!(crossed(&cone, (-3, 0, -12), (3, 0, -8)));
+        // Ending inside
+        assert!(crossed(&cone, (0, 0, 20), (0, 0, -5)));
+
+        // Passing by
+        assert!(!crossed(&cone, (-20, 0, 5), (20, 0, 5)));
+        assert!(!crossed(&cone, (-20, 0, -15), (20, 0, -15)));
+        assert!(!crossed(&area, (-10, 0, -25), (10, 0, -25)));
+        assert!(!crossed(&area, (-10, 0, -20), (-5, 0, -20)));
+    }
+
+    #[test]
+    fn overwatch_system_interrupts_moves_passing_through() {
+        let (mut world, watcher, enemy) = setup(cone(15));
+        world.transforms[enemy].as_mut().unwrap().position = (-20, 0, -5).into();
+
+        move_by(&mut world, enemy, (40, 0, 0));
+        overwatch_system(&mut world);
+
+        assert!(world.overwatches[watcher].is_none());
+        assert!(world.dead[enemy].is_some());
+    }
+
+    #[test]
+    fn overwatch_system_ignores_allies() {
+        let (mut world, watcher, enemy) = setup(cone(15));
+        world.enemies[enemy] = None;
+
+        move_by(&mut world, enemy, (0, 0, 10));
+        overwatch_system(&mut world);
+
+        assert!(world.overwatches[watcher].is_some());
+        assert!(world.dead[enemy].is_none());
+    }
+
+    #[test]
+    fn overwatch_system_survivor_keeps_moving() {
+        let (mut world, watcher, enemy) = setup(OverwatchComponent::new(
+            OverwatchArea::Area {
+                center: (0, 0, -10).into(),
+                radius: 5.into(),
+            },
+            1,
+            1,
+        ));
+        world.units[enemy].as_mut().unwrap().wounds = 100;
+        world.hitpoints[enemy] = Some(world.units[enemy].as_ref().unwrap().hitpoints());
+
+        move_by(&mut world, enemy, (0, 0, 10));
+        overwatch_system(&mut world);
+
+        assert!(world.overwatches[watcher].is_none());
+        assert!(world.dead[enemy].is_none());
+
+        let velocity: Vec3d = (0, 0, 10).into();
+        assert_eq!(velocity, world.velocities[enemy].as_ref().unwrap().value);
+    }
+
+    #[test]
+    fn overwatch_system_dodging_through_survives() {
+        let (mut world, watcher, enemy) = setup(cone(15));
+        world.staminas[enemy] = Some(StaminaComponent::new(100, 0));
+
+        // Dodges from outside the cone to behind the watcher
+        dodge::start_dodge(&mut world, enemy, Direction::South).unwrap();
+        world.step();
+
+        assert!(world.is_alive(enemy));
+        assert!(world.dead[enemy].is_none());
+
+        let position: Vec3d = (0, 0, dodge::DODGE_SPEED - 20).into();
+        assert_eq!(position, world.transforms[enemy].as_ref().unwrap().position);
+
+        // Still watching, as it never fired
+        assert!(world.overwatches[watcher].is_some());
+        world.events.update();
+        assert!(world.events.overwatch_triggered.read().is_empty());
+    }
+
+    #[test]
+    fn overwatch_system_is_registered() {
+        let (mut world, watcher, enemy) = setup(cone(15));
+        move_by(&mut world, enemy, (0, 0, 10));
+
+        world.step();
+
+        assert!(world.overwatches[watcher].is_none());
+        assert!(!world.is_alive(enemy));
+    }
+}
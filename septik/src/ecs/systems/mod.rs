@@ -2,27 +2,110 @@ pub mod game_engine;
 pub mod game_logic;
 pub mod platform_specific;
 
-use crate::ecs::{components, Entity, World};
+use crate::ecs::components::AilmentTypes;
+use crate::ecs::{events, Join, Maybe, Scheduler, Stage, SystemDescriptor, World};
 use crate::lib_core::math::{Axi, Rotation3d};
 
 use crate::lib_core::{math::FixedNumber, math::Vec3d, Direction, EngineInputs, InputType};
 
+/// Register the engine's systems
+pub fn register_systems(scheduler: &mut Scheduler) -> Result<(), String> {
+    scheduler.add_system(SystemDescriptor::new(
+        "character_action",
+        Stage::Input,
+        character_action_system,
+    ))?;
+    scheduler.add_system(
+        SystemDescriptor::new("dodge", Stage::Input, game_logic::dodge::dodge_system)
+            .after("character_action"),
+    )?;
+    scheduler.add_system(SystemDescriptor::new(
+        "facing_update",
+        Stage::Logic,
+        facing_update_system,
+    ))?;
+    scheduler.add_system(SystemDescriptor::new(
+        "ailment_update",
+        Stage::Logic,
+        game_logic::ailments::ailment_system,
+    ))?;
+    scheduler.add_system(SystemDescriptor::new(
+        "invulnerability_update",
+        Stage::Logic,
+        game_logic::damage::invulnerability_system,
+    ))?;
+    scheduler.add_system(SystemDescriptor::new(
+        "attack_update",
+        Stage::Logic,
+        game_logic::attacks::attack_system,
+    ))?;
+    scheduler.add_system(
+        SystemDescriptor::new(
+            "overwatch",
+            Stage::Physics,
+            game_logic::overwatch::overwatch_system,
+        )
+        .before("position_update"),
+    )?;
+    scheduler.add_system(SystemDescriptor::new(
+        "position_update",
+        Stage::Physics,
+        position_update_system,
+    ))?;
+    scheduler.add_system(
+        SystemDescriptor::new(
+            "world_transform_update",
+            Stage::Physics,
+            world_transform_update_system,
+        )
+        .after("position_update"),
+    )?;
+    scheduler.add_system(SystemDescriptor::new(
+        "death",
+        Stage::Cleanup,
+        game_logic::damage::death_system,
+    ))?;
+    scheduler.add_system(SystemDescriptor::new(
+        "input_cleanup",
+        Stage::Cleanup,
+        input_cleanup_system,
+    ))?;
+
+    Ok(())
+}
+
 /// This system applies character actions from inputs
 pub fn character_action_system(world: &mut World) {
-    for e in world.entities() {
-        let velocity = &world.velocities[e];
-        let engine_inputs = &world.engine_inputs[e];
-        let move_speed = &world.move_speeds[e];
-
-        if velocity.is_none() || engine_inputs.is_none() || move_speed.is_none() {
+    let entities = world.entities();
+    let storages = (
+        &mut world.velocities,
+        &world.engine_inputs,
+        &world.move_speeds,
+        Maybe(&world.transforms),
+        Maybe(&mut world.third_person_cameras),
+        Maybe(&world.ailments),
+        Maybe(&world.dead),
+    );
+
+    for (e, (velocity, engine_inputs, move_speed, transform, camera, ailments, dead)) in
+        storages.join(&entities)
+    {
+        // The dead can't move or act until they're revived
+        if dead.is_some() {
+            velocity.value = Vec3d::default();
+            velocity.rotational_velocity = Rotation3d::default();
             continue;
         }
 
-        let move_speed = move_speed.as_ref().unwrap();
-        let engine_inputs = engine_inputs.as_ref().unwrap();
-
         let mut movement_vec = Vec3d::default();
 
+        let move_speed = match ailments {
+            Some(ailments) if ailments.is_active(AilmentTypes::Sloth) => {
+                move_speed.value / game_logic::ailments::SLOTH_SLOWDOWN.into()
+            }
+            _ => move_speed.value,
+        };
+
         let mut summed_cursor_deltas = Vec3d::default();
 
         for input in &engine_inputs.inputs {
@@ -30,25 +113,31 @@ pub fn character_action_system(world: &mut World) {
                 InputType::CursorNormalized(_, cursor_pos) => {
                     summed_cursor_deltas += *cursor_pos;
                 }
+                InputType::Pressed(_, input_type) => {
+                    world.events.input_action.publish(events::InputAction {
+                        entity: e,
+                        action: *input_type,
+                    });
+                }
                 InputType::Held(_, input_type) => {
                     match input_type {
                         EngineInputs::MoveForward => {
-                            movement_vec.z -= move_speed.value;
+                            movement_vec.z -= move_speed;
                         }
                         EngineInputs::MoveBack => {
-                            movement_vec.z += move_speed.value;
+                            movement_vec.z += move_speed;
                         }
                         EngineInputs::MoveRight => {
-                            movement_vec.x += move_speed.value;
+                            movement_vec.x += move_speed;
                         }
                         EngineInputs::MoveLeft => {
-                            movement_vec.x -= move_speed.value;
+                            movement_vec.x -= move_speed;
                         }
                         EngineInputs::Jump => {
-                            movement_vec.y += move_speed.value;
+                            movement_vec.y += move_speed;
                         }
                         EngineInputs::Crouch => {
-                            movement_vec.y -= move_speed.value;
+                            movement_vec.y -= move_speed;
                         }
                         _ => {
                             // Ignore anything other than inputs
@@ -59,40 +148,28 @@ pub fn character_action_system(world: &mut World) {
             }
         }
 
-        // Update velocity
-        let mut velocity = velocity.clone().unwrap();
-
         // Calculate rotation based on cursor
         let rotation = apply_cursor_to_rotation(summed_cursor_deltas, Rotation3d::default());
         velocity.rotational_velocity = rotation;
 
         // Rotate the movement velocity
         velocity.value = {
-            let transform = world.transforms[e].as_ref();
-
-            let rotation = {
-                if transform.is_some() {
-                    let transform = transform.unwrap();
-
-                    rotation + transform.rotation
-                } else {
-                    rotation
-                }
+            let rotation = match transform {
+                Some(transform) => rotation + transform.rotation,
+                None => rotation,
             };
 
-            let movement_vec = rotation.rotate_vec3d_on_axis(movement_vec, Axi::Y);
-
-            movement_vec
+            rotation.rotate_vec3d_on_axis(movement_vec, Axi::Y)
         };
-        world.velocities[e] = Some(velocity);
 
         //TODO: this section should instead add the rotation to the velocity so it can be picked up by the collision detection
         {
             // Update transform rotation?
 
             // Update camera
-            let camera = world.third_person_cameras[e].as_ref();
-            world.third_person_cameras[e] = update_camera(camera, summed_cursor_deltas);
+            if let Some(camera) = camera {
+                camera.rotation = apply_cursor_to_rotation(summed_cursor_deltas, camera.rotation);
+            }
         }
     }
 }
@@ -116,8 +193,8 @@ fn apply_cursor_to_rotation(summed_cursor_deltas: Vec3d, rotation: Rotation3d) -
     rotation.pitch_radians = {
         let mut pitch_radians: FixedNumber = rotation.pitch_radians;
 
-        let min_pitch: FixedNumber = FixedNumber::PI() / (-2).into();
-        let max_pitch: FixedNumber = FixedNumber::PI() / 2.into();
+        let min_pitch: FixedNumber = -FixedNumber::HALF_PI();
+        let max_pitch: FixedNumber = FixedNumber::HALF_PI();
 
         pitch_radians += sensitivity * summed_cursor_deltas.y;
 
@@ -128,62 +205,47 @@ fn apply_cursor_to_rotation(summed_cursor_deltas: Vec3d, rotation: Rotation3d) -
     rotation
 }
 
-fn update_camera(
-    camera: std::option::Option<&components::ThirdPersonCameraComponent>,
-    summed_cursor_deltas: Vec3d,
-) -> Option<components::ThirdPersonCameraComponent> {
-    if camera.is_none() {
-        return None;
-    }
-
-    let mut camera = camera.unwrap().clone();
-
-    camera.rotation = apply_cursor_to_rotation(summed_cursor_deltas, camera.rotation);
-    return Some(camera);
-}
-
 /// This system cleans up any input, leaving a blank slate for the next run.
 pub fn input_cleanup_system(world: &mut World) {
-    for e in world.entities() {
-        let engine_inputs = world.engine_inputs[e].clone();
-
-        if engine_inputs.is_none() {
-            continue;
-        }
+    let entities = world.entities();
 
-        let mut engine_inputs = engine_inputs.unwrap();
+    for (_, engine_inputs) in (&mut world.engine_inputs).join(&entities) {
+        engine_inputs.inputs.clear();
+    }
+}
 
-        engine_inputs.inputs = vec![];
+/// Face entities towards the direction they are moving in. Entities that aren't moving keep their previous facing.
+pub fn facing_update_system(world: &mut World) {
+    let entities = world.entities();
 
-        world.engine_inputs[e] = Some(engine_inputs);
+    for (_, (velocity, facing)) in (&world.velocities, &mut world.facing_direction).join(&entities)
+    {
+        if let Some(direction) = Direction::from_vec3d(velocity.value) {
+            facing.direction = direction;
+        }
     }
 }
 
 /// Apply velocities to the positions
 pub fn position_update_system(world: &mut World) {
-    for e in world.entities() {
-        let velocity = world.velocities[e].as_ref();
-        let transform = world.transforms[e].as_ref();
-
-        if velocity.is_none() || transform.is_none() {
-            continue;
-        }
-
-        let velocity = velocity.as_ref().unwrap();
-        let mut transform = transform.unwrap().clone();
+    let entities = world.entities();
 
+    for (_, (velocity, transform)) in (&world.velocities, &mut world.transforms).join(&entities) {
         transform.position += velocity.value;
         transform.rotation += velocity.rotational_velocity;
 
-        let min_pitch: FixedNumber = FixedNumber::PI() / (-2).into();
-        let max_pitch: FixedNumber = FixedNumber::PI() / 2.into();
+        let min_pitch: FixedNumber = -FixedNumber::HALF_PI();
+        let max_pitch: FixedNumber = FixedNumber::HALF_PI();
 
         // Cap it so that the max/min angles are -90* and 90*
         transform.rotation.pitch_radians = FixedNumber::max(
             FixedNumber::min(transform.rotation.pitch_radians, max_pitch),
             min_pitch,
         );
-
-        world.transforms[e] = Some(transform);
     }
 }
+
+/// Calculate world space transforms so children follow their parents
+pub fn world_transform_update_system(world: &mut World) {
+    world.update_world_transforms();
+}
@@ -0,0 +1,28 @@
+use super::{
+    components::{unit_components::UnitComponent, HitPointComponent},
+    Entity, World,
+};
+
+/// A character with full hit points
+pub fn character(world: &mut World, hitpoints: u32) -> Entity {
+    let e = world.add_entity();
+    world.hitpoints[e] = Some(HitPointComponent::new(hitpoints));
+
+    e
+}
+
+/// A unit with the default stat line and the hit points for its wounds
+pub fn unit(world: &mut World) -> Entity {
+    let e = world.add_entity();
+    let unit = UnitComponent::default();
+
+    world.hitpoints[e] = Some(unit.hitpoints());
+    world.units[e] = Some(unit);
+
+    e
+}
+
+/// The character's current hit points
+pub fn hitpoints(world: &World, e: Entity) -> u32 {
+    world.hitpoints[e].as_ref().unwrap().value
+}
@@ -0,0 +1,410 @@
+use super::{Entity, Join, World};
+use crate::lib_core::serialization::{SaveReader, SaveWriter, Serializable};
+
+/// The phases of a round, in the order they're played
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub enum Phase {
+    Move,
+    Overwatch,
+    Action,
+}
+
+impl Phase {
+    pub const all: [Phase; 3] = [Phase::Move, Phase::Overwatch, Phase::Action];
+
+    pub fn index(&self) -> usize {
+        let index = match self {
+            Phase::Move => 0,
+            Phase::Overwatch => 1,
+            Phase::Action => 2,
+        };
+
+        return index;
+    }
+
+    /// The phase after this one, or None if this is the last of the round
+    pub fn next(&self) -> Option<Phase> {
+        Self::all.get(self.index() + 1).copied()
+    }
+}
+
+/// Where a turn based world is in the current round.
+/// Every unit activates once per phase in initiative order, spending action points that refill each round.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct TurnState {
+    round: u32,
+    phase: Phase,
+    /// Units in the order they activate
+    order: Vec<Entity>,
+    /// The index in the order of the active unit
+    current: usize,
+    /// The remaining action points of each unit in the order
+    action_points: Vec<u8>,
+    max_action_points: u8,
+}
+
+impl TurnState {
+    fn new(max_action_points: u8) -> Self {
+        return Self {
+            round: 0,
+            phase: Phase::Move,
+            order: vec![],
+            current: 0,
+            action_points: vec![],
+            max_action_points: max_action_points,
+        };
+    }
+
+    /// The current round, starting at 1
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// The units taking part this round, in activation order
+    pub fn order(&self) -> &Vec<Entity> {
+        &self.order
+    }
+
+    /// The unit whose activation it is, or None if no units are taking part
+    pub fn active_unit(&self) -> Option<Entity> {
+        self.order.get(self.current).copied()
+    }
+
+    /// The unit's remaining action points, or None if it isn't taking part this round
+    pub fn action_points(&self, entity: Entity) -> Option<u8> {
+        let index = self.order.iter().position(|e| *e == entity)?;
+
+        Some(self.action_points[index])
+    }
+
+    pub fn max_action_points(&self) -> u8 {
+        self.max_action_points
+    }
+}
+
+impl Serializable for Phase {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&(self.index() as u8));
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(Phase::Move),
+            1 => Ok(Phase::Overwatch),
+            2 => Ok(Phase::Action),
+            index => Err(format!("Invalid phase {}", index)),
+        }
+    }
+}
+
+impl Serializable for TurnState {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.round);
+        writer.write(&self.phase);
+        writer.write(&self.order);
+        writer.write(&self.current);
+        writer.write(&self.action_points);
+        writer.write(&self.max_action_points);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let state = Self {
+            round: reader.read()?,
+            phase: reader.read()?,
+            order: reader.read()?,
+            current: reader.read()?,
+            action_points: reader.read()?,
+            max_action_points: reader.read()?,
+        };
+
+        if state.order.len() != state.action_points.len() {
+            return Err(String::from(
+                "Turn order and action points have different lengths",
+            ));
+        }
+
+        return Ok(state);
+    }
+}
+
+impl World {
+    /// Switch to turn based play, starting the first round with the given action points per unit.
+    /// Systems still only run when `step` or `dispatch` is called, so both modes share the same components.
+    pub fn start_turns(&mut self, max_action_points: u8) {
+        let mut turns = TurnState::new(max_action_points);
+        self.start_round(&mut turns);
+
+        self.turns = Some(turns);
+    }
+
+    /// Return to real time play
+    pub fn stop_turns(&mut self) {
+        self.turns = None;
+    }
+
+    /// The state of the current round, or None if the world isn't turn based
+    pub fn turns(&self) -> Option<&TurnState> {
+        self.turns.as_ref()
+    }
+
+    /// Spend the active unit's action points, returning how many are left
+    pub fn spend_action_points(&mut self, entity: Entity, cost: u8) -> Result<u8, String> {
+        let turns = match self.turns.as_mut() {
+            Some(turns) => turns,
+            None => return Err(String::from("The world isn't turn based")),
+        };
+
+        if turns.active_unit() != Some(entity) {
+            return Err(format!("It isn't {:?}'s activation", entity));
+        }
+
+        let action_points = &mut turns.action_points[turns.current];
+        if *action_points < cost {
+            return Err(format!(
+                "{:?} needs {} action points but has {}",
+                entity, cost, action_points
+            ));
+        }
+
+        *action_points -= cost;
+
+        Ok(*action_points)
+    }
+
+    /// End the active unit's activation, moving on to the next unit that can still act.
+    /// After the last unit the next phase begins, and after the last phase a new round.
+    pub fn end_activation(&mut self) -> Result<(), String> {
+        let mut turns = match self.turns.take() {
+            Some(turns) => turns,
+            None => return Err(String::from("The world isn't turn based")),
+        };
+
+        loop {
+            turns.current += 1;
+
+            if turns.current >= turns.order.len() {
+                turns.current = 0;
+
+                match turns.phase.next() {
+                    Some(phase) => turns.phase = phase,
+                    None => self.start_round(&mut turns),
+                }
+            }
+
+            match turns.active_unit() {
+                Some(e) if !self.can_act(e) => continue,
+                _ => break,
+            }
+        }
+
+        self.turns = Some(turns);
+
+        Ok(())
+    }
+
+    /// Order the living units by initiative, with ties going to the lowest entity index, and refill their action points.
+    /// Slots are recycled, so a unit created later can win a tie against an older one.
+    fn start_round(&self, turns: &mut TurnState) {
+        let entities = self.entities();
+        let mut order: Vec<(u8, Entity)> = (&self.units)
+            .join(&entities)
+            .filter(|(e, _)| self.can_act(*e))
+            .map(|(e, unit)| (unit.initiative, e))
+            .collect();
+
+        order.sort_by(|(a_initiative, a), (b_initiative, b)| {
+            b_initiative
+                .cmp(a_initiative)
+                .then(a.index().cmp(&b.index()))
+        });
+
+        turns.round += 1;
+        turns.phase = Phase::Move;
+        turns.current = 0;
+        turns.order = order.into_iter().map(|(_, e)| e).collect();
+        turns.action_points = vec![turns.max_action_points; turns.order.len()];
+    }
+
+    /// Whether the unit is still around to take its activation
+    fn can_act(&self, entity: Entity) -> bool {
+        self.is_alive(entity) && self.units[entity].is_some() && self.dead[entity].is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::DeadComponent;
+
+    fn unit(world: &mut World, initiative: u8) -> Entity {
+        let e = crate::ecs::test_fixtures::unit(world);
+        world.units[e].as_mut().unwrap().initiative = initiative;
+
+        e
+    }
+
+    /// Record every activation until the round ends
+    fn play_round(world: &mut World) -> Vec<(Phase, Entity)> {
+        let round = world.turns().unwrap().round();
+        let mut activations = vec![];
+
+        while world.turns().unwrap().round() == round {
+            let turns = world.turns().unwrap();
+            activations.push((turns.phase(), turns.active_unit().unwrap()));
+
+            world.end_activation().unwrap();
+        }
+
+        activations
+    }
+
+    #[test]
+    fn World_turns_initiative_order() {
+        let mut world = World::builder().build().unwrap();
+        let slow = unit(&mut world, 1);
+        let fast = unit(&mut world, 5);
+        let tied_first = unit(&mut world, 3);
+        let tied_second = unit(&mut world, 3);
+        world.add_entity();
+
+        world.start_turns(2);
+
+        let turns = world.turns().unwrap();
+        assert_eq!(1, turns.round());
+        assert_eq!(Phase::Move, turns.phase());
+        assert_eq!(&vec![fast, tied_first, tied_second, slow], turns.order());
+        assert_eq!(Some(fast), turns.active_unit());
+
+        let order = vec![fast, tied_first, tied_second, slow];
+        let mut expected = vec![];
+        for phase in Phase::all.iter() {
+            for e in &order {
+                expected.push((*phase, *e));
+            }
+        }
+
+        assert_eq!(expected, play_round(&mut world));
+        assert_eq!(2, world.turns().unwrap().round());
+        assert_eq!(Phase::Move, world.turns().unwrap().phase());
+    }
+
+    #[test]
+    fn World_turns_ties_go_to_lowest_index() {
+        let mut world = World::builder().build().unwrap();
+        let removed = unit(&mut world, 3);
+        let older = unit(&mut world, 3);
+
+        world.delete_entity(removed);
+        world.step();
+
+        // Reuses the removed unit's slot
+        let newer = unit(&mut world, 3);
+        assert_eq!(removed.index(), newer.index());
+
+        world.start_turns(2);
+
+        assert_eq!(&vec![newer, older], world.turns().unwrap().order());
+    }
+
+    #[test]
+    fn World_turns_action_points() {
+        let mut world = World::builder().build().unwrap();
+        let first = unit(&mut world, 2);
+        let second = unit(&mut world, 1);
+
+        world.start_turns(3);
+
+        assert!(world.spend_action_points(second, 1).is_err());
+        assert_eq!(Ok(1), world.spend_action_points(first, 2));
+        assert!(world.spend_action_points(first, 2).is_err());
+        assert_eq!(Some(1), world.turns().unwrap().action_points(first));
+
+        world.end_activation().unwrap();
+        assert_eq!(Ok(0), world.spend_action_points(second, 3));
+
+        // Points carry between phases and refill each round
+        world.end_activation().unwrap();
+        assert_eq!(Phase::Overwatch, world.turns().unwrap().phase());
+        assert_eq!(Ok(0), world.spend_action_points(first, 1));
+
+        play_round(&mut world);
+        assert_eq!(Some(3), world.turns().unwrap().action_points(first));
+        assert_eq!(Some(3), world.turns().unwrap().action_points(second));
+    }
+
+    #[test]
+    fn World_turns_skip_dead_units() {
+        let mut world = World::builder().build().unwrap();
+        let first = unit(&mut world, 3);
+        let second = unit(&mut world, 2);
+        let third = unit(&mut world, 1);
+
+        world.start_turns(1);
+        world.dead[second] = Some(DeadComponent {
+            killer: Some(first),
+        });
+
+        world.end_activation().unwrap();
+        assert_eq!(Some(third), world.turns().unwrap().active_unit());
+
+        world.end_activation().unwrap();
+        assert_eq!(Some(first), world.turns().unwrap().active_unit());
+        assert_eq!(Phase::Overwatch, world.turns().unwrap().phase());
+
+        play_round(&mut world);
+        assert_eq!(&vec![first, third], world.turns().unwrap().order());
+    }
+
+    #[test]
+    fn World_turns_without_units() {
+        let mut world = World::builder().build().unwrap();
+
+        assert!(world.end_activation().is_err());
+
+        world.start_turns(1);
+        assert_eq!(None, world.turns().unwrap().active_unit());
+
+        world.end_activation().unwrap();
+        assert_eq!(Phase::Overwatch, world.turns().unwrap().phase());
+    }
+
+    #[test]
+    fn World_turns_share_real_time_components() {
+        let mut world = World::new();
+        let e = unit(&mut world, 1);
+
+        world.start_turns(1);
+        world.step();
+
+        assert_eq!(1, world.tick());
+        assert_eq!(Some(e), world.turns().unwrap().active_unit());
+
+        world.stop_turns();
+        assert!(world.turns().is_none());
+        assert!(world.units[e].is_some());
+    }
+
+    #[test]
+    fn World_turns_are_saved_and_rolled_back() {
+        let mut world = World::builder().build().unwrap();
+        unit(&mut world, 2);
+        let second = unit(&mut world, 1);
+
+        world.start_turns(2);
+        let snapshot = world.snapshot();
+        world.end_activation().unwrap();
+        world.spend_action_points(second, 1).unwrap();
+
+        let loaded = World::load(&world.save()).unwrap();
+        assert_eq!(world.turns(), loaded.turns());
+        assert_eq!(world.checksum(), loaded.checksum());
+
+        world.restore(&snapshot);
+        assert_eq!(snapshot.checksum(), world.checksum());
+        assert_eq!(Some(2), world.turns().unwrap().action_points(second));
+    }
+}
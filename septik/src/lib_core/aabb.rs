@@ -2,7 +2,7 @@ use super::*;
 
 use super::math::*;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 pub struct Aabb {
     pub min: Vec3d,
     pub max: Vec3d,
@@ -12,4 +12,408 @@ impl Aabb {
     pub fn new(min: Vec3d, max: Vec3d) -> Self {
         Self { min: min, max: max }
     }
+
+    /// Create an aabb from a center point and the half size on each axis
+    pub fn from_center_extents(center: Vec3d, extents: Vec3d) -> Self {
+        Self::new(center - extents, center + extents)
+    }
+
+    /// Returns true if the two overlap. Boxes that only touch do not intersect.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x < other.max.x
+            && self.max.x > other.min.x
+            && self.min.y < other.max.y
+            && self.max.y > other.min.y
+            && self.min.z < other.max.z
+            && self.max.z > other.min.z
+    }
+
+    /// Returns true if the point is inside or on the edge of the aabb
+    pub fn contains_point(&self, point: Vec3d) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    /// The smallest aabb that contains both
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(
+            Vec3d::new(
+                FixedNumber::min(self.min.x, other.min.x),
+                FixedNumber::min(self.min.y, other.min.y),
+                FixedNumber::min(self.min.z, other.min.z),
+            ),
+            Vec3d::new(
+                FixedNumber::max(self.max.x, other.max.x),
+                FixedNumber::max(self.max.y, other.max.y),
+                FixedNumber::max(self.max.z, other.max.z),
+            ),
+        )
+    }
+
+    /// Grow each side by the given amount
+    pub fn expand(&self, amount: FixedNumber) -> Self {
+        let amount = Vec3d::new(amount, amount, amount);
+
+        Self::new(self.min - amount, self.max + amount)
+    }
+
+    pub fn center(&self) -> Vec3d {
+        self.min + self.extents()
+    }
+
+    /// The half size on each axis
+    pub fn extents(&self) -> Vec3d {
+        let two: FixedNumber = 2.into();
+
+        let size = self.size();
+
+        Vec3d::new(size.x / two, size.y / two, size.z / two)
+    }
+
+    /// The full size on each axis
+    pub fn size(&self) -> Vec3d {
+        self.max - self.min
+    }
+
+    /// Move the aabb by the given offset
+    pub fn translate(&self, offset: Vec3d) -> Self {
+        Self::new(self.min + offset, self.max + offset)
+    }
+
+    /// Cast a ray against the aabb, returning the distance along the direction (in multiples of the direction) to the first hit. A ray starting inside returns 0.
+    pub fn ray_intersection(&self, origin: Vec3d, direction: Vec3d) -> Option<FixedNumber> {
+        // Slab test. Times are kept as i64 bits, as dividing by a small direction can easily overflow.
+        let mut t_enter = i64::MIN;
+        let mut t_exit = i64::MAX;
+
+        let axi = [
+            (origin.x, direction.x, self.min.x, self.max.x),
+            (origin.y, direction.y, self.min.y, self.max.y),
+            (origin.z, direction.z, self.min.z, self.max.z),
+        ];
+
+        for (origin, direction, min, max) in axi.iter() {
+            if *direction == 0.into() {
+                // Parallel to the slab, so it either always or never overlaps
+                if origin < min || origin > max {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let t_min = slab_time(*min - *origin, *direction);
+            let t_max = slab_time(*max - *origin, *direction);
+
+            let (t_near, t_far) = if t_min <= t_max {
+                (t_min, t_max)
+            } else {
+                (t_max, t_min)
+            };
+
+            t_enter = t_enter.max(t_near);
+            t_exit = t_exit.min(t_far);
+
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+
+        // The aabb is behind the ray
+        if t_exit < 0 {
+            return None;
+        }
+
+        let t_enter = t_enter.max(0);
+        if t_enter > i32::MAX as i64 {
+            return None;
+        }
+
+        Some(FixedNumber::from_bits(t_enter as i32))
+    }
+
+    /// Sweep this aabb along its velocity against another moving aabb. Returns the time of impact in [0, 1], where 0 is the start of the movement and 1 is the end.
+    /// Touching or overlapping aabbs return 0, unless they're moving apart.
+    pub fn sweep(
+        &self,
+        velocity: Vec3d,
+        other: &Self,
+        other_velocity: Vec3d,
+    ) -> Option<FixedNumber> {
+        // Treat the other as stationary, then expand it by this aabb's size so the problem becomes a ray cast from this aabb's min
+        let relative_velocity = velocity - other_velocity;
+        let expanded = Self::new(other.min - self.size(), other.max);
+
+        let time = expanded.ray_intersection(self.min, relative_velocity)?;
+
+        if time > 1.into() {
+            return None;
+        }
+
+        if time == 0.into() && self.is_separating(relative_velocity, other) {
+            return None;
+        }
+
+        Some(time)
+    }
+
+    /// Whether an aabb touching or overlapping the other is moving away from it
+    fn is_separating(&self, relative_velocity: Vec3d, other: &Self) -> bool {
+        if self.intersects(other) {
+            return relative_velocity.dot(other.center() - self.center()) < 0.into();
+        }
+
+        let axi = [
+            (
+                relative_velocity.x,
+                self.min.x,
+                self.max.x,
+                other.min.x,
+                other.max.x,
+            ),
+            (
+                relative_velocity.y,
+                self.min.y,
+                self.max.y,
+                other.min.y,
+                other.max.y,
+            ),
+            (
+                relative_velocity.z,
+                self.min.z,
+                self.max.z,
+                other.min.z,
+                other.max.z,
+            ),
+        ];
+
+        // Only touching faces can collide, so sliding along or leaving any of them separates
+        for (velocity, min, max, other_min, other_max) in axi.iter() {
+            if max == other_min && *velocity <= 0.into() {
+                return true;
+            }
+
+            if min == other_max && *velocity >= 0.into() {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Time for a ray to travel the distance, as i64 bits
+fn slab_time(distance: FixedNumber, velocity: FixedNumber) -> i64 {
+    ((distance.to_bits() as i64) << 12) / velocity.to_bits() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec(x: i32, y: i32, z: i32) -> Vec3d {
+        (x, y, z).into()
+    }
+
+    fn aabb(min: (i32, i32, i32), max: (i32, i32, i32)) -> Aabb {
+        Aabb::new(min.into(), max.into())
+    }
+
+    fn fixed(bits: i32) -> FixedNumber {
+        FixedNumber::from_bits(bits)
+    }
+
+    #[test]
+    fn Aabb_intersects() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+
+        assert!(a.intersects(&aabb((1, 1, 1), (3, 3, 3))));
+        assert!(a.intersects(&aabb((-1, -1, -1), (3, 3, 3))));
+        assert!(!a.intersects(&aabb((3, 0, 0), (4, 2, 2))));
+        assert!(!a.intersects(&aabb((0, -3, 0), (2, -1, 2))));
+    }
+
+    #[test]
+    fn Aabb_touching_does_not_intersect() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+
+        assert!(!a.intersects(&aabb((2, 0, 0), (4, 2, 2))));
+    }
+
+    #[test]
+    fn Aabb_contains_point() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+
+        assert!(a.contains_point(vec(1, 1, 1)));
+        assert!(a.contains_point(vec(2, 0, 2)));
+        assert!(!a.contains_point(vec(3, 1, 1)));
+        assert!(!a.contains_point(vec(1, -1, 1)));
+    }
+
+    #[test]
+    fn Aabb_union() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+        let b = aabb((-1, 1, 1), (1, 5, 1));
+
+        assert_eq!(aabb((-1, 0, 0), (2, 5, 2)), a.union(&b));
+        assert_eq!(a.union(&b), b.union(&a));
+    }
+
+    #[test]
+    fn Aabb_expand() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+
+        assert_eq!(aabb((-1, -1, -1), (3, 3, 3)), a.expand(1.into()));
+    }
+
+    #[test]
+    fn Aabb_center_and_extents() {
+        let a = aabb((-2, 0, 4), (2, 6, 5));
+
+        assert_eq!(
+            Vec3d::new(0.into(), 3.into(), fixed(4096 * 4 + 2048)),
+            a.center()
+        );
+        assert_eq!(Vec3d::new(2.into(), 3.into(), fixed(2048)), a.extents());
+        assert_eq!(vec(4, 6, 1), a.size());
+        assert_eq!(a, Aabb::from_center_extents(a.center(), a.extents()));
+    }
+
+    #[test]
+    fn Aabb_translate() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+
+        assert_eq!(aabb((5, -1, 0), (7, 1, 2)), a.translate(vec(5, -1, 0)));
+    }
+
+    #[test]
+    fn Aabb_ray_intersection() {
+        let a = aabb((2, -1, -1), (4, 1, 1));
+
+        assert_eq!(
+            Some(2.into()),
+            a.ray_intersection(vec(0, 0, 0), vec(1, 0, 0))
+        );
+        assert_eq!(
+            Some(1.into()),
+            a.ray_intersection(vec(0, 0, 0), vec(2, 0, 0))
+        );
+        assert_eq!(
+            Some(fixed(2048)),
+            a.ray_intersection(vec(3, 3, 0), vec(0, -4, 0))
+        );
+    }
+
+    #[test]
+    fn Aabb_ray_intersection_misses() {
+        let a = aabb((2, -1, -1), (4, 1, 1));
+
+        // Pointing away
+        assert_eq!(None, a.ray_intersection(vec(0, 0, 0), vec(-1, 0, 0)));
+        // Parallel, outside of the slab
+        assert_eq!(None, a.ray_intersection(vec(0, 5, 0), vec(1, 0, 0)));
+        // Passes by diagonally
+        assert_eq!(None, a.ray_intersection(vec(0, 0, 0), vec(1, 1, 0)));
+        // No direction
+        assert_eq!(None, a.ray_intersection(vec(0, 0, 0), vec(0, 0, 0)));
+    }
+
+    #[test]
+    fn Aabb_ray_intersection_from_inside() {
+        let a = aabb((2, -1, -1), (4, 1, 1));
+
+        assert_eq!(
+            Some(0.into()),
+            a.ray_intersection(vec(3, 0, 0), vec(1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn Aabb_ray_intersection_large_times() {
+        let a = aabb((200, -1, -1), (400, 1, 1));
+
+        // Too far away to represent
+        let direction = Vec3d::new(fixed(1), 0.into(), 0.into());
+        assert_eq!(None, a.ray_intersection(vec(0, 0, 0), direction));
+
+        // Small enough to represent
+        let direction = Vec3d::new(fixed(4096 * 100), 0.into(), 0.into());
+        assert_eq!(
+            Some(fixed(8192)),
+            a.ray_intersection(vec(0, 0, 0), direction)
+        );
+    }
+
+    #[test]
+    fn Aabb_sweep() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+        let b = aabb((4, 0, 0), (6, 2, 2));
+
+        // Moving 4 units covers the 2 unit gap halfway through
+        assert_eq!(
+            Some(fixed(2048)),
+            a.sweep(vec(4, 0, 0), &b, Vec3d::default())
+        );
+
+        // Both moving towards each other
+        assert_eq!(Some(fixed(2048)), a.sweep(vec(2, 0, 0), &b, vec(-2, 0, 0)));
+    }
+
+    #[test]
+    fn Aabb_sweep_misses() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+        let b = aabb((4, 0, 0), (6, 2, 2));
+
+        // Not far enough
+        assert_eq!(None, a.sweep(vec(1, 0, 0), &b, Vec3d::default()));
+        // Wrong direction
+        assert_eq!(None, a.sweep(vec(-4, 0, 0), &b, Vec3d::default()));
+        // Moving in parallel
+        assert_eq!(None, a.sweep(vec(0, 4, 0), &b, vec(0, 4, 0)));
+    }
+
+    #[test]
+    fn Aabb_sweep_overlapping() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+        let b = aabb((1, 1, 1), (3, 3, 3));
+
+        assert_eq!(Some(0.into()), a.sweep(vec(1, 0, 0), &b, Vec3d::default()));
+        assert_eq!(
+            Some(0.into()),
+            a.sweep(Vec3d::default(), &b, Vec3d::default())
+        );
+    }
+
+    #[test]
+    fn Aabb_sweep_separating() {
+        let a = aabb((0, 0, 0), (2, 2, 2));
+
+        // Overlapping but moving apart
+        let overlapping = aabb((1, 1, 1), (3, 3, 3));
+        assert_eq!(None, a.sweep(vec(-1, 0, 0), &overlapping, Vec3d::default()));
+        assert_eq!(None, a.sweep(Vec3d::default(), &overlapping, vec(0, 0, 1)));
+
+        // Touching and moving apart or sliding along
+        let touching = aabb((2, 0, 0), (4, 2, 2));
+        assert_eq!(None, a.sweep(vec(-1, 0, 0), &touching, Vec3d::default()));
+        assert_eq!(None, a.sweep(Vec3d::default(), &touching, vec(1, 0, 0)));
+        assert_eq!(None, a.sweep(vec(0, 1, 0), &touching, Vec3d::default()));
+
+        let below = aabb((0, -2, 0), (2, 0, 2));
+        assert_eq!(None, a.sweep(vec(0, 1, 0), &below, Vec3d::default()));
+
+        // Touching and moving together
+        assert_eq!(
+            Some(0.into()),
+            a.sweep(vec(1, 0, 0), &touching, Vec3d::default())
+        );
+        assert_eq!(
+            Some(0.into()),
+            a.sweep(vec(0, -1, 0), &below, Vec3d::default())
+        );
+    }
 }
@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Debug)]
+use crate::lib_core::serialization::{SaveReader, SaveWriter, Serializable};
+
+#[derive(Copy, Clone, Debug, Hash)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -12,6 +14,36 @@ pub struct Palatte {
     colors: [Color; Self::MAX_COLORS],
 }
 
+impl std::hash::Hash for Palatte {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.colors[..].hash(state);
+    }
+}
+
+impl std::fmt::Debug for Palatte {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.colors.iter()).finish()
+    }
+}
+
+impl Serializable for Palatte {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        for color in self.colors.iter() {
+            writer.write(color);
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let mut palatte = Self::new();
+
+        for color in palatte.colors.iter_mut() {
+            *color = reader.read()?;
+        }
+
+        Ok(palatte)
+    }
+}
+
 impl Palatte {
     pub const MAX_COLORS: usize = 256;
 
@@ -1,7 +1,10 @@
 use super::*;
 
+use math::{FixedNumber, Vec3d};
+
 /// Cardinal/intercardinal directions. Limit characters/npcs to these directions.
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// North is forward (-z) and East is right (+x), matching the movement inputs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Direction {
     North,
     NorthEast,
@@ -12,3 +15,306 @@ pub enum Direction {
     West,
     NorthWest,
 }
+
+impl Direction {
+    pub const size: usize = 8;
+
+    /// The clockwise index, starting from North
+    pub fn index(&self) -> usize {
+        let index = match self {
+            Direction::North => 0,
+            Direction::NorthEast => 1,
+            Direction::East => 2,
+            Direction::SouthEast => 3,
+            Direction::South => 4,
+            Direction::SouthWest => 5,
+            Direction::West => 6,
+            Direction::NorthWest => 7,
+        };
+
+        return index;
+    }
+
+    /// Retrieve the direction from a clockwise index, wrapping around
+    pub fn from_index(index: usize) -> Self {
+        match index % Self::size {
+            0 => Direction::North,
+            1 => Direction::NorthEast,
+            2 => Direction::East,
+            3 => Direction::SouthEast,
+            4 => Direction::South,
+            5 => Direction::SouthWest,
+            6 => Direction::West,
+            _ => Direction::NorthWest,
+        }
+    }
+
+    /// Snap a vector to the closest direction, ignoring the y axis. Returns None for vectors with no horizontal movement.
+    pub fn from_vec3d(vec3d: Vec3d) -> Option<Self> {
+        if vec3d.x == 0.into() && vec3d.z == 0.into() {
+            return None;
+        }
+
+        // Clockwise angle from North
+        let angle = FixedNumber::atan2(vec3d.x, -vec3d.z);
+
+        Some(Self::from_clockwise_radians(angle))
+    }
+
+    /// Snap a yaw to the closest direction. Uses the same convention as `Rotation3d`, where a positive yaw turns counter clockwise.
+    pub fn from_yaw(yaw_radians: FixedNumber) -> Self {
+        Self::from_clockwise_radians(-yaw_radians)
+    }
+
+    fn from_clockwise_radians(radians: FixedNumber) -> Self {
+        let step = Self::step_radians().to_bits() as i64;
+
+        // Round to the nearest step
+        let steps = (radians.to_bits() as i64 * 2 + step).div_euclid(step * 2);
+
+        Self::from_index(steps.rem_euclid(Self::size as i64) as usize)
+    }
+
+    /// The radians between two neighbouring directions
+    fn step_radians() -> FixedNumber {
+        FixedNumber::PI() / 4.into()
+    }
+
+    /// The unit vector pointing in this direction
+    pub fn to_vec3d(&self) -> Vec3d {
+        let zero: FixedNumber = 0.into();
+        let one: FixedNumber = 1.into();
+        // 1 / sqrt(2)
+        let diagonal = FixedNumber::from_bits(2896);
+
+        match self {
+            Direction::North => Vec3d::new(zero, zero, -one),
+            Direction::NorthEast => Vec3d::new(diagonal, zero, -diagonal),
+            Direction::East => Vec3d::new(one, zero, zero),
+            Direction::SouthEast => Vec3d::new(diagonal, zero, diagonal),
+            Direction::South => Vec3d::new(zero, zero, one),
+            Direction::SouthWest => Vec3d::new(-diagonal, zero, diagonal),
+            Direction::West => Vec3d::new(-one, zero, zero),
+            Direction::NorthWest => Vec3d::new(-diagonal, zero, -diagonal),
+        }
+    }
+
+    /// The yaw that faces this direction, in (-PI, PI]
+    pub fn yaw(&self) -> FixedNumber {
+        if *self == Direction::South {
+            return FixedNumber::PI();
+        }
+
+        let index = self.index() as i32;
+        let steps = if index > 4 {
+            Self::size as i32 - index
+        } else {
+            -index
+        };
+
+        Self::step_radians() * steps.into()
+    }
+
+    pub fn rotate_clockwise(&self) -> Self {
+        Self::from_index(self.index() + 1)
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Self {
+        Self::from_index(self.index() + Self::size - 1)
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::from_index(self.index() + Self::size / 2)
+    }
+
+    /// The smallest number of steps to rotate from one direction to the other, from 0 to 4
+    pub fn steps_between(&self, other: Self) -> u8 {
+        let difference = (self.index() + Self::size - other.index()) % Self::size;
+
+        if difference > Self::size / 2 {
+            return (Self::size - difference) as u8;
+        }
+
+        difference as u8
+    }
+
+    /// The smallest angle between the two directions, from 0 to PI
+    pub fn angle_difference(&self, other: Self) -> FixedNumber {
+        if self.steps_between(other) == 4 {
+            return FixedNumber::PI();
+        }
+
+        Self::step_radians() * (self.steps_between(other) as i32).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Direction; Direction::size] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    #[test]
+    fn Direction_from_vec3d() {
+        assert_eq!(
+            Some(Direction::North),
+            Direction::from_vec3d((0, 0, -5).into())
+        );
+        assert_eq!(
+            Some(Direction::East),
+            Direction::from_vec3d((3, 9, 0).into())
+        );
+        assert_eq!(
+            Some(Direction::South),
+            Direction::from_vec3d((0, 0, 1).into())
+        );
+        assert_eq!(
+            Some(Direction::West),
+            Direction::from_vec3d((-1, 0, 0).into())
+        );
+        assert_eq!(
+            Some(Direction::NorthEast),
+            Direction::from_vec3d((2, 0, -2).into())
+        );
+        assert_eq!(
+            Some(Direction::SouthWest),
+            Direction::from_vec3d((-2, 0, 2).into())
+        );
+    }
+
+    #[test]
+    fn Direction_from_vec3d_snaps_to_closest() {
+        assert_eq!(
+            Some(Direction::North),
+            Direction::from_vec3d((1, 0, -5).into())
+        );
+        assert_eq!(
+            Some(Direction::NorthEast),
+            Direction::from_vec3d((4, 0, -5).into())
+        );
+        assert_eq!(
+            Some(Direction::South),
+            Direction::from_vec3d((-1, 0, 5).into())
+        );
+    }
+
+    #[test]
+    fn Direction_from_vec3d_zero() {
+        assert_eq!(None, Direction::from_vec3d((0, 10, 0).into()));
+        assert_eq!(None, Direction::from_vec3d(Vec3d::default()));
+    }
+
+    #[test]
+    fn Direction_to_vec3d_round_trip() {
+        for direction in ALL.iter() {
+            assert_eq!(
+                Some(*direction),
+                Direction::from_vec3d(direction.to_vec3d())
+            );
+        }
+    }
+
+    #[test]
+    fn Direction_to_vec3d_is_unit_length() {
+        for direction in ALL.iter() {
+            let error = (direction.to_vec3d().length() - 1.into()).abs();
+
+            assert!(error <= FixedNumber::from_bits(1));
+        }
+    }
+
+    #[test]
+    fn Direction_yaw_round_trip() {
+        for direction in ALL.iter() {
+            assert_eq!(*direction, Direction::from_yaw(direction.yaw()));
+        }
+
+        assert_eq!(Direction::South, Direction::from_yaw(-FixedNumber::PI()));
+        assert_eq!(
+            Direction::North,
+            Direction::from_yaw(FixedNumber::TWO_PI() * 3.into())
+        );
+    }
+
+    #[test]
+    fn Direction_yaw_matches_rotation() {
+        let forward: Vec3d = (0, 0, -1).into();
+
+        for direction in ALL.iter() {
+            let rotation = math::Rotation3d {
+                pitch_radians: 0.into(),
+                yaw_radians: direction.yaw(),
+                roll_radians: 0.into(),
+            };
+
+            let rotated = rotation.rotate_vec3d_on_axis(forward, math::Axi::Y);
+
+            assert_eq!(Some(*direction), Direction::from_vec3d(rotated));
+        }
+    }
+
+    #[test]
+    fn Direction_rotate() {
+        assert_eq!(Direction::NorthEast, Direction::North.rotate_clockwise());
+        assert_eq!(Direction::North, Direction::NorthWest.rotate_clockwise());
+        assert_eq!(
+            Direction::NorthWest,
+            Direction::North.rotate_counter_clockwise()
+        );
+        assert_eq!(
+            Direction::South,
+            Direction::SouthWest.rotate_counter_clockwise()
+        );
+
+        for direction in ALL.iter() {
+            assert_eq!(
+                *direction,
+                direction.rotate_clockwise().rotate_counter_clockwise()
+            );
+        }
+    }
+
+    #[test]
+    fn Direction_opposite() {
+        assert_eq!(Direction::South, Direction::North.opposite());
+        assert_eq!(Direction::SouthWest, Direction::NorthEast.opposite());
+        assert_eq!(Direction::East, Direction::West.opposite());
+
+        for direction in ALL.iter() {
+            assert_eq!(*direction, direction.opposite().opposite());
+        }
+    }
+
+    #[test]
+    fn Direction_steps_between() {
+        assert_eq!(0, Direction::North.steps_between(Direction::North));
+        assert_eq!(1, Direction::North.steps_between(Direction::NorthWest));
+        assert_eq!(1, Direction::NorthWest.steps_between(Direction::North));
+        assert_eq!(3, Direction::East.steps_between(Direction::NorthWest));
+        assert_eq!(4, Direction::East.steps_between(Direction::West));
+    }
+
+    #[test]
+    fn Direction_angle_difference() {
+        let zero: FixedNumber = 0.into();
+
+        assert_eq!(zero, Direction::South.angle_difference(Direction::South));
+        assert_eq!(
+            FixedNumber::HALF_PI(),
+            Direction::North.angle_difference(Direction::West)
+        );
+        assert_eq!(
+            FixedNumber::PI(),
+            Direction::SouthEast.angle_difference(Direction::NorthWest)
+        );
+    }
+}
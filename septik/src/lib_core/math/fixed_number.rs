@@ -3,14 +3,28 @@ type fix = I20F12;
 
 use std::str::FromStr;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FixedNumber {
     value: fix,
 }
 
 impl FixedNumber {
     pub fn PI() -> Self {
-        Self { value: fix::PI }
+        Self {
+            value: fix::from_bits(round_from_q30(PI_Q30) as i32),
+        }
+    }
+
+    pub fn HALF_PI() -> Self {
+        Self {
+            value: fix::from_bits(round_from_q30(HALF_PI_Q30) as i32),
+        }
+    }
+
+    pub fn TWO_PI() -> Self {
+        Self {
+            value: fix::from_bits(round_from_q30(TWO_PI_Q30) as i32),
+        }
     }
 
     pub fn min(a: Self, b: Self) -> Self {
@@ -21,24 +35,86 @@ impl FixedNumber {
         b
     }
 
+    /// Absolute value
+    pub fn abs(&self) -> Self {
+        Self {
+            value: self.value.abs(),
+        }
+    }
+
     /// Sine
     pub fn sin(&self) -> Self {
-        //TODO: Convert to fixed!
-        let v: f32 = (*self).into();
-
-        let sin = v.sin();
+        let (sin, _) = self.sin_cos();
 
-        Self::from_f32(sin)
+        sin
     }
 
     /// Cosine
     pub fn cos(&self) -> Self {
-        //TODO: Convert to fixed!
-        let v: f32 = (*self).into();
+        let (_, cos) = self.sin_cos();
+
+        cos
+    }
+
+    /// Sine and cosine, calculated in a single pass.
+    pub fn sin_cos(&self) -> (Self, Self) {
+        let (sin, cos) = cordic_sin_cos(self.value.to_bits());
+
+        (
+            Self::from_bits(round_from_q30(sin) as i32),
+            Self::from_bits(round_from_q30(cos) as i32),
+        )
+    }
+
+    /// Tangent. Saturates when the cosine is zero.
+    pub fn tan(&self) -> Self {
+        let (sin, cos) = cordic_sin_cos(self.value.to_bits());
+
+        if cos == 0 {
+            if sin < 0 {
+                return Self::from_bits(i32::MIN);
+            }
+
+            return Self::from_bits(i32::MAX);
+        }
 
-        let cos = v.cos();
+        let tan = (sin << FRACTIONAL_BITS) / cos;
 
-        Self::from_f32(cos)
+        Self::from_bits(saturate_i32(tan))
+    }
+
+    /// The angle in radians between the positive x axis and the point (x, y). Returns a value in [-PI, PI].
+    pub fn atan2(y: Self, x: Self) -> Self {
+        let angle = cordic_atan2(y.value.to_bits(), x.value.to_bits());
+
+        Self::from_bits(round_from_q30(angle) as i32)
+    }
+
+    /// Square root. Returns zero for negative values.
+    pub fn sqrt(&self) -> Self {
+        let bits = self.value.to_bits();
+        if bits <= 0 {
+            return Self::from_bits(0);
+        }
+
+        // sqrt(bits / 2^12) * 2^12 == sqrt(bits * 2^12)
+        let root = isqrt_rounded((bits as u64) << FRACTIONAL_BITS);
+
+        Self::from_bits(root as i32)
+    }
+
+    /// Inverse square root (1 / sqrt(x)). Returns zero for values less than or equal to zero.
+    pub fn inv_sqrt(&self) -> Self {
+        let bits = self.value.to_bits();
+        if bits <= 0 {
+            return Self::from_bits(0);
+        }
+
+        // 2^12 / sqrt(bits / 2^12) == 2^18 / sqrt(bits) == 2^34 / sqrt(bits * 2^32)
+        let root = isqrt_rounded((bits as u64) << 32);
+        let inverse = ((1u64 << 34) + root / 2) / root;
+
+        Self::from_bits(saturate_i32(inverse as i64))
     }
 
     pub fn max(a: Self, b: Self) -> Self {
@@ -49,12 +125,37 @@ impl FixedNumber {
         a
     }
 
+    /// Restrict the value to [min, max]
+    pub fn clamp(value: Self, min: Self, max: Self) -> Self {
+        Self::max(Self::min(value, max), min)
+    }
+
     pub fn from_str(s: String) -> Self {
         Self {
             value: fix::from_str(s.as_str()).unwrap(),
         }
     }
 
+    /// Parse a decimal number, returning an error if it is malformed or out of range
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match fix::from_str(s) {
+            Ok(value) => Ok(Self { value: value }),
+            Err(e) => Err(format!("Invalid number '{}': {}", s, e)),
+        }
+    }
+
+    /// Create a number from the raw underlying bits. Used for deterministic lookups and serialization.
+    pub fn from_bits(bits: i32) -> Self {
+        Self {
+            value: fix::from_bits(bits),
+        }
+    }
+
+    /// Retrieve the raw underlying bits.
+    pub fn to_bits(&self) -> i32 {
+        self.value.to_bits()
+    }
+
     fn from_i32(number: i32) -> Self {
         Self {
             value: fix::from_num(number),
@@ -155,6 +256,166 @@ impl std::convert::From<f32> for FixedNumber {
     }
 }
 
+/// The number of fractional bits in the underlying fixed type.
+const FRACTIONAL_BITS: u32 = 12;
+
+/// Trig calculations are done with 30 fractional bits then rounded back down.
+const Q30_SHIFT: u32 = 30 - FRACTIONAL_BITS;
+
+const HALF_PI_Q30: i64 = 1686629713;
+const PI_Q30: i64 = 3373259426;
+const TWO_PI_Q30: i64 = 6746518852;
+
+/// Product of the CORDIC gain factors for all iterations.
+const CORDIC_GAIN_Q30: i64 = 652032874;
+
+const CORDIC_ITERATIONS: usize = 30;
+
+/// atan(2^-i) for each CORDIC iteration.
+const CORDIC_ATAN_TABLE_Q30: [i64; CORDIC_ITERATIONS] = [
+    843314857, 497837829, 263043837, 133525159, 67021687, 33543516, 16775851, 8388437, 4194283,
+    2097149, 1048576, 524288, 262144, 131072, 65536, 32768, 16384, 8192, 4096, 2048, 1024, 512,
+    256, 128, 64, 32, 16, 8, 4, 2,
+];
+
+fn round_from_q30(value: i64) -> i64 {
+    (value + (1 << (Q30_SHIFT - 1))) >> Q30_SHIFT
+}
+
+fn saturate_i32(value: i64) -> i32 {
+    if value > i32::MAX as i64 {
+        return i32::MAX;
+    } else if value < i32::MIN as i64 {
+        return i32::MIN;
+    }
+
+    value as i32
+}
+
+/// Returns the sine and cosine of the given angle bits, with 30 fractional bits.
+fn cordic_sin_cos(angle_bits: i32) -> (i64, i64) {
+    // Reduce to (-PI, PI]
+    let mut angle = ((angle_bits as i64) << Q30_SHIFT).rem_euclid(TWO_PI_Q30);
+    if angle > PI_Q30 {
+        angle -= TWO_PI_Q30;
+    }
+
+    // Reduce to [-PI/2, PI/2], which is the range CORDIC converges in
+    let mut negate_cos = false;
+    if angle > HALF_PI_Q30 {
+        angle = PI_Q30 - angle;
+        negate_cos = true;
+    } else if angle < -HALF_PI_Q30 {
+        angle = -PI_Q30 - angle;
+        negate_cos = true;
+    }
+
+    let mut x = CORDIC_GAIN_Q30;
+    let mut y = 0;
+    let mut z = angle;
+
+    for i in 0..CORDIC_ITERATIONS {
+        let x_shifted = x >> i;
+        let y_shifted = y >> i;
+
+        if z >= 0 {
+            x -= y_shifted;
+            y += x_shifted;
+            z -= CORDIC_ATAN_TABLE_Q30[i];
+        } else {
+            x += y_shifted;
+            y -= x_shifted;
+            z += CORDIC_ATAN_TABLE_Q30[i];
+        }
+    }
+
+    if negate_cos {
+        x = -x;
+    }
+
+    (y, x)
+}
+
+/// Returns atan2 of the given bits, with 30 fractional bits.
+fn cordic_atan2(y_bits: i32, x_bits: i32) -> i64 {
+    if y_bits == 0 && x_bits == 0 {
+        return 0;
+    }
+
+    // Scale up so small inputs keep their precision through the iterations
+    let mut x = (x_bits as i64) << 16;
+    let mut y = (y_bits as i64) << 16;
+    let mut z = 0;
+
+    // Rotate into the right half plane, which is the range CORDIC converges in
+    if x < 0 {
+        if y >= 0 {
+            let tmp = x;
+            x = y;
+            y = -tmp;
+            z = HALF_PI_Q30;
+        } else {
+            let tmp = x;
+            x = -y;
+            y = tmp;
+            z = -HALF_PI_Q30;
+        }
+    }
+
+    for i in 0..CORDIC_ITERATIONS {
+        let x_shifted = x >> i;
+        let y_shifted = y >> i;
+
+        if y > 0 {
+            x += y_shifted;
+            y -= x_shifted;
+            z += CORDIC_ATAN_TABLE_Q30[i];
+        } else {
+            x -= y_shifted;
+            y += x_shifted;
+            z -= CORDIC_ATAN_TABLE_Q30[i];
+        }
+    }
+
+    z
+}
+
+/// Integer square root, rounded down.
+pub(super) fn isqrt(value: u64) -> u64 {
+    let mut remainder = value;
+    let mut root = 0;
+    let mut bit = 1u64 << 62;
+
+    while bit > remainder {
+        bit >>= 2;
+    }
+
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+
+        bit >>= 2;
+    }
+
+    root
+}
+
+/// Integer square root, rounded to the nearest integer.
+pub(super) fn isqrt_rounded(value: u64) -> u64 {
+    let root = isqrt(value);
+
+    // (root + 0.5)^2 == root^2 + root + 0.25
+    if value - root * root > root {
+        return root + 1;
+    }
+
+    root
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +436,17 @@ mod tests {
         assert_eq!(expected, (v1 / v2).value);
     }
 
+    #[test]
+    fn FixedNumber_parse() {
+        assert_eq!(
+            Ok(FixedNumber::from_bits(-6144)),
+            FixedNumber::parse("-1.5")
+        );
+        assert_eq!(Ok(FixedNumber::from_i32(8)), FixedNumber::parse("8"));
+        assert!(FixedNumber::parse("one").is_err());
+        assert!(FixedNumber::parse("10000000").is_err());
+    }
+
     #[test]
     fn FixedNumber_divide_assign() {
         let expected = FixedNumber::from_i32(0);
@@ -297,4 +569,159 @@ mod tests {
 
         assert_eq!(value, fixed_number.value);
     }
+
+    const ONE_BIT: f64 = 1.0 / 4096.0;
+
+    fn to_f64(number: FixedNumber) -> f64 {
+        number.value.to_bits() as f64 / 4096.0
+    }
+
+    /// Walk over [min, max] in raw bit steps, returning the maximum error between the function and the reference.
+    fn max_error(
+        min: i32,
+        max: i32,
+        step: usize,
+        f: fn(FixedNumber) -> FixedNumber,
+        reference: fn(f64) -> f64,
+    ) -> f64 {
+        let mut max_error: f64 = 0.0;
+
+        for bits in (min..max).step_by(step) {
+            let input = FixedNumber::from_bits(bits);
+            let expected = reference(to_f64(input));
+            let actual = to_f64(f(input));
+
+            max_error = max_error.max((expected - actual).abs());
+        }
+
+        max_error
+    }
+
+    #[test]
+    fn FixedNumber_sin_max_error() {
+        let error = max_error(-4096 * 20, 4096 * 20, 7, |n| n.sin(), f64::sin);
+
+        assert!(error <= ONE_BIT, "error: {}", error);
+    }
+
+    #[test]
+    fn FixedNumber_cos_max_error() {
+        let error = max_error(-4096 * 20, 4096 * 20, 7, |n| n.cos(), f64::cos);
+
+        assert!(error <= ONE_BIT, "error: {}", error);
+    }
+
+    #[test]
+    fn FixedNumber_sin_cos_matches_sin_and_cos() {
+        for bits in (-4096 * 8..4096 * 8).step_by(13) {
+            let n = FixedNumber::from_bits(bits);
+
+            assert_eq!((n.sin(), n.cos()), n.sin_cos());
+        }
+    }
+
+    #[test]
+    fn FixedNumber_sin_cos_pinned_values() {
+        assert_eq!(0, FixedNumber::from_i32(0).sin().to_bits());
+        assert_eq!(4096, FixedNumber::from_i32(0).cos().to_bits());
+        assert_eq!(4096, FixedNumber::HALF_PI().sin().to_bits());
+        assert_eq!(0, FixedNumber::HALF_PI().cos().to_bits());
+        assert_eq!(0, FixedNumber::PI().sin().to_bits());
+        assert_eq!(-4096, FixedNumber::PI().cos().to_bits());
+        assert_eq!(3447, FixedNumber::from_i32(1).sin().to_bits());
+        assert_eq!(2213, FixedNumber::from_i32(1).cos().to_bits());
+        assert_eq!(-3724, FixedNumber::from_i32(-2).sin().to_bits());
+        assert_eq!(-1705, FixedNumber::from_i32(-2).cos().to_bits());
+    }
+
+    #[test]
+    fn FixedNumber_tan_max_error() {
+        // Limit to +-1.3 radians, as the error grows with the slope of tan
+        let error = max_error(-4096 * 13 / 10, 4096 * 13 / 10, 3, |n| n.tan(), f64::tan);
+
+        assert!(error <= 2.0 * ONE_BIT, "error: {}", error);
+    }
+
+    #[test]
+    fn FixedNumber_atan2_max_error() {
+        let mut max_error: f64 = 0.0;
+
+        for y in (-4096 * 4..4096 * 4).step_by(97) {
+            for x in (-4096 * 4..4096 * 4).step_by(89) {
+                let y = FixedNumber::from_bits(y);
+                let x = FixedNumber::from_bits(x);
+
+                let expected = to_f64(y).atan2(to_f64(x));
+                let actual = to_f64(FixedNumber::atan2(y, x));
+
+                max_error = max_error.max((expected - actual).abs());
+            }
+        }
+
+        assert!(max_error <= ONE_BIT, "error: {}", max_error);
+    }
+
+    #[test]
+    fn FixedNumber_atan2_pinned_values() {
+        let zero = FixedNumber::from_i32(0);
+        let one = FixedNumber::from_i32(1);
+
+        assert_eq!(zero, FixedNumber::atan2(zero, zero));
+        assert_eq!(zero, FixedNumber::atan2(zero, one));
+        assert_eq!(FixedNumber::HALF_PI(), FixedNumber::atan2(one, zero));
+        assert_eq!(-FixedNumber::HALF_PI(), FixedNumber::atan2(-one, zero));
+        assert_eq!(FixedNumber::PI(), FixedNumber::atan2(zero, -one));
+        assert_eq!(3217, FixedNumber::atan2(one, one).to_bits());
+        assert_eq!(-9651, FixedNumber::atan2(-one, -one).to_bits());
+    }
+
+    #[test]
+    fn FixedNumber_sqrt_max_error() {
+        let error = max_error(0, 4096 * 1000, 31, |n| n.sqrt(), f64::sqrt);
+        assert!(error <= ONE_BIT / 2.0, "error: {}", error);
+
+        let error = max_error(4096 * 1000, i32::MAX - 4096, 65537, |n| n.sqrt(), f64::sqrt);
+        assert!(error <= ONE_BIT / 2.0, "error: {}", error);
+    }
+
+    #[test]
+    fn FixedNumber_sqrt_pinned_values() {
+        assert_eq!(FixedNumber::from_i32(0), FixedNumber::from_i32(0).sqrt());
+        assert_eq!(FixedNumber::from_i32(0), FixedNumber::from_i32(-4).sqrt());
+        assert_eq!(FixedNumber::from_i32(1), FixedNumber::from_i32(1).sqrt());
+        assert_eq!(FixedNumber::from_i32(3), FixedNumber::from_i32(9).sqrt());
+        assert_eq!(
+            FixedNumber::from_i32(500),
+            FixedNumber::from_i32(250000).sqrt()
+        );
+        assert_eq!(5793, FixedNumber::from_i32(2).sqrt().to_bits());
+    }
+
+    #[test]
+    fn FixedNumber_inv_sqrt_max_error() {
+        let error = max_error(4096, 4096 * 1000, 31, |n| n.inv_sqrt(), |n| 1.0 / n.sqrt());
+
+        assert!(error <= ONE_BIT / 2.0, "error: {}", error);
+    }
+
+    #[test]
+    fn FixedNumber_inv_sqrt_pinned_values() {
+        assert_eq!(
+            FixedNumber::from_i32(0),
+            FixedNumber::from_i32(0).inv_sqrt()
+        );
+        assert_eq!(
+            FixedNumber::from_i32(0),
+            FixedNumber::from_i32(-4).inv_sqrt()
+        );
+        assert_eq!(
+            FixedNumber::from_i32(1),
+            FixedNumber::from_i32(1).inv_sqrt()
+        );
+        assert_eq!(2048, FixedNumber::from_i32(4).inv_sqrt().to_bits());
+        assert_eq!(
+            FixedNumber::from_i32(64),
+            FixedNumber::from_bits(1).inv_sqrt()
+        );
+    }
 }
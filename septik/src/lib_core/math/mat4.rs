@@ -0,0 +1,346 @@
+use super::*;
+
+/// Fixed point 4x4 matrix, stored as rows. Points are treated as column vectors, so `a * b` applies `b` first.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    pub m: [[FixedNumber; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        let zero: FixedNumber = 0.into();
+        let one: FixedNumber = 1.into();
+
+        Self {
+            m: [
+                [one, zero, zero, zero],
+                [zero, one, zero, zero],
+                [zero, zero, one, zero],
+                [zero, zero, zero, one],
+            ],
+        }
+    }
+
+    pub fn from_translation(translation: Vec3d) -> Self {
+        let mut matrix = Self::identity();
+
+        matrix.m[0][3] = translation.x;
+        matrix.m[1][3] = translation.y;
+        matrix.m[2][3] = translation.z;
+
+        matrix
+    }
+
+    pub fn from_scale(scale: Vec3d) -> Self {
+        let mut matrix = Self::identity();
+
+        matrix.m[0][0] = scale.x;
+        matrix.m[1][1] = scale.y;
+        matrix.m[2][2] = scale.z;
+
+        matrix
+    }
+
+    pub fn from_quaternion(q: Quaternion) -> Self {
+        let one: FixedNumber = 1.into();
+        let two: FixedNumber = 2.into();
+
+        let mut matrix = Self::identity();
+
+        matrix.m[0][0] = one - two * (q.y * q.y + q.z * q.z);
+        matrix.m[0][1] = two * (q.x * q.y - q.w * q.z);
+        matrix.m[0][2] = two * (q.x * q.z + q.w * q.y);
+
+        matrix.m[1][0] = two * (q.x * q.y + q.w * q.z);
+        matrix.m[1][1] = one - two * (q.x * q.x + q.z * q.z);
+        matrix.m[1][2] = two * (q.y * q.z - q.w * q.x);
+
+        matrix.m[2][0] = two * (q.x * q.z - q.w * q.y);
+        matrix.m[2][1] = two * (q.y * q.z + q.w * q.x);
+        matrix.m[2][2] = one - two * (q.x * q.x + q.y * q.y);
+
+        matrix
+    }
+
+    /// Create a matrix that rotates, then translates
+    pub fn from_rotation_translation(rotation: Quaternion, translation: Vec3d) -> Self {
+        let mut matrix = Self::from_quaternion(rotation);
+
+        matrix.m[0][3] = translation.x;
+        matrix.m[1][3] = translation.y;
+        matrix.m[2][3] = translation.z;
+
+        matrix
+    }
+
+    /// Create a view matrix for a camera at `eye` looking at `target`. The camera looks down -z.
+    pub fn look_at(eye: Vec3d, target: Vec3d, up: Vec3d) -> Self {
+        let forward = (target - eye).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+
+        let zero: FixedNumber = 0.into();
+        let one: FixedNumber = 1.into();
+
+        Self {
+            m: [
+                [right.x, right.y, right.z, -right.dot(eye)],
+                [up.x, up.y, up.z, -up.dot(eye)],
+                [-forward.x, -forward.y, -forward.z, forward.dot(eye)],
+                [zero, zero, zero, one],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut matrix = *self;
+
+        for row in 0..4 {
+            for column in 0..4 {
+                matrix.m[row][column] = self.m[column][row];
+            }
+        }
+
+        matrix
+    }
+
+    /// Transform a point, applying translation
+    pub fn transform_point(&self, point: Vec3d) -> Vec3d {
+        let m = &self.m;
+
+        Vec3d::new(
+            m[0][0] * point.x + m[0][1] * point.y + m[0][2] * point.z + m[0][3],
+            m[1][0] * point.x + m[1][1] * point.y + m[1][2] * point.z + m[1][3],
+            m[2][0] * point.x + m[2][1] * point.y + m[2][2] * point.z + m[2][3],
+        )
+    }
+
+    /// Transform a direction, ignoring translation
+    pub fn transform_vector(&self, vector: Vec3d) -> Vec3d {
+        let m = &self.m;
+
+        Vec3d::new(
+            m[0][0] * vector.x + m[0][1] * vector.y + m[0][2] * vector.z,
+            m[1][0] * vector.x + m[1][1] * vector.y + m[1][2] * vector.z,
+            m[2][0] * vector.x + m[2][1] * vector.y + m[2][2] * vector.z,
+        )
+    }
+
+    /// The inverse of the matrix, or None if it can't be inverted
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.m;
+
+        // Laplace expansion using the 2x2 determinants of the top and bottom halves
+        let s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+        let s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+        let s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+        let s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+        let s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+        let s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+
+        let c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+        let c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+        let c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+        let c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+        let c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+        let c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+
+        let determinant = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if determinant == 0.into() {
+            return None;
+        }
+
+        let one: FixedNumber = 1.into();
+        let inv = one / determinant;
+
+        let mut matrix = *self;
+        let r = &mut matrix.m;
+
+        r[0][0] = (m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3) * inv;
+        r[0][1] = (-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3) * inv;
+        r[0][2] = (m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3) * inv;
+        r[0][3] = (-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3) * inv;
+
+        r[1][0] = (-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1) * inv;
+        r[1][1] = (m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1) * inv;
+        r[1][2] = (-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1) * inv;
+        r[1][3] = (m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1) * inv;
+
+        r[2][0] = (m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0) * inv;
+        r[2][1] = (-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0) * inv;
+        r[2][2] = (m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0) * inv;
+        r[2][3] = (-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0) * inv;
+
+        r[3][0] = (-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0) * inv;
+        r[3][1] = (m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0) * inv;
+        r[3][2] = (-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0) * inv;
+        r[3][3] = (m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0) * inv;
+
+        Some(matrix)
+    }
+}
+
+impl std::ops::Mul for Mat4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> <Self as std::ops::Mul<Self>>::Output {
+        let mut matrix = self;
+
+        for row in 0..4 {
+            for column in 0..4 {
+                let mut sum: FixedNumber = 0.into();
+
+                for i in 0..4 {
+                    sum += self.m[row][i] * rhs.m[i][column];
+                }
+
+                matrix.m[row][column] = sum;
+            }
+        }
+
+        matrix
+    }
+}
+
+impl std::ops::MulAssign for Mat4 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec(x: i32, y: i32, z: i32) -> Vec3d {
+        (x, y, z).into()
+    }
+
+    fn assert_vec3d_close(expected: Vec3d, actual: Vec3d, tolerance_bits: i32) {
+        let differences = [
+            expected.x.to_bits() - actual.x.to_bits(),
+            expected.y.to_bits() - actual.y.to_bits(),
+            expected.z.to_bits() - actual.z.to_bits(),
+        ];
+
+        for difference in differences.iter() {
+            assert!(
+                difference.abs() <= tolerance_bits,
+                "expected: {:?}, actual: {:?}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn Mat4_identity() {
+        let point: Vec3d = (1, -2, 3).into();
+
+        assert_eq!(point, Mat4::identity().transform_point(point));
+        assert_eq!(Mat4::identity(), Mat4::identity() * Mat4::identity());
+    }
+
+    #[test]
+    fn Mat4_transform_point() {
+        let matrix = Mat4::from_translation((10, 20, 30).into());
+
+        assert_eq!(vec(11, 18, 33), matrix.transform_point((1, -2, 3).into()));
+        assert_eq!(vec(1, -2, 3), matrix.transform_vector((1, -2, 3).into()));
+
+        let matrix = Mat4::from_scale((2, 3, 4).into());
+
+        assert_eq!(vec(2, -6, 12), matrix.transform_point((1, -2, 3).into()));
+    }
+
+    #[test]
+    fn Mat4_from_rotation_translation() {
+        let rotation = Quaternion::from_axis_angle((0, 1, 0).into(), FixedNumber::HALF_PI());
+        let matrix = Mat4::from_rotation_translation(rotation, (0, 5, 0).into());
+
+        assert_vec3d_close(
+            (0, 5, -1).into(),
+            matrix.transform_point((1, 0, 0).into()),
+            4,
+        );
+    }
+
+    #[test]
+    fn Mat4_matches_quaternion_rotation() {
+        let rotation = Quaternion::from_euler(Rotation3d {
+            pitch_radians: FixedNumber::from(0.4_f32),
+            yaw_radians: FixedNumber::from(-1.1_f32),
+            roll_radians: FixedNumber::from(2.3_f32),
+        });
+        let point: Vec3d = (4, -2, 7).into();
+
+        assert_vec3d_close(
+            rotation.rotate_vec3d(point),
+            Mat4::from_quaternion(rotation).transform_point(point),
+            32,
+        );
+    }
+
+    #[test]
+    fn Mat4_compose() {
+        let translate = Mat4::from_translation((1, 2, 3).into());
+        let scale = Mat4::from_scale((2, 2, 2).into());
+
+        // Scale first, then translate
+        let matrix = translate * scale;
+
+        assert_eq!(vec(3, 4, 5), matrix.transform_point((1, 1, 1).into()));
+    }
+
+    #[test]
+    fn Mat4_inverse() {
+        let rotation = Quaternion::from_euler(Rotation3d {
+            pitch_radians: FixedNumber::from(0.3_f32),
+            yaw_radians: FixedNumber::from(1.2_f32),
+            roll_radians: FixedNumber::from(-0.5_f32),
+        });
+        let matrix = Mat4::from_rotation_translation(rotation, (10, -20, 5).into());
+        let inverse = matrix.inverse().unwrap();
+
+        let point: Vec3d = (3, 4, -5).into();
+        let round_trip = inverse.transform_point(matrix.transform_point(point));
+
+        assert_vec3d_close(point, round_trip, 32);
+    }
+
+    #[test]
+    fn Mat4_inverse_singular() {
+        let matrix = Mat4::from_scale((1, 0, 1).into());
+
+        assert_eq!(None, matrix.inverse());
+    }
+
+    #[test]
+    fn Mat4_transpose() {
+        let matrix = Mat4::from_translation((1, 2, 3).into());
+        let transposed = matrix.transpose();
+
+        assert_eq!(FixedNumber::from(1.0_f32), transposed.m[3][0]);
+        assert_eq!(FixedNumber::from(2.0_f32), transposed.m[3][1]);
+        assert_eq!(FixedNumber::from(3.0_f32), transposed.m[3][2]);
+        assert_eq!(matrix, transposed.transpose());
+    }
+
+    #[test]
+    fn Mat4_look_at() {
+        let eye: Vec3d = (0, 0, 10).into();
+        let view = Mat4::look_at(eye, Vec3d::default(), (0, 1, 0).into());
+
+        // The target ends up directly in front of the camera
+        assert_vec3d_close(
+            (0, 0, -10).into(),
+            view.transform_point(Vec3d::default()),
+            4,
+        );
+        assert_vec3d_close(Vec3d::default(), view.transform_point(eye), 4);
+
+        let eye: Vec3d = (5, 0, 0).into();
+        let view = Mat4::look_at(eye, Vec3d::default(), (0, 1, 0).into());
+
+        assert_vec3d_close((0, 0, -5).into(), view.transform_point(Vec3d::default()), 4);
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+/// An amount with its own maximum, such as hit points and stamina. Too wide for a `Range`, but can be shown as one with `Meter::to_range`.
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub struct Meter {
+    pub value: u32,
+    pub max_value: u32,
+}
+
+impl Meter {
+    /// A full meter
+    pub fn new(max_value: u32) -> Self {
+        return Self {
+            value: max_value,
+            max_value: max_value,
+        };
+    }
+
+    /// Fill the meter completely
+    pub fn fill(&mut self) {
+        self.value = self.max_value;
+    }
+
+    /// Drain the meter completely
+    pub fn drain(&mut self) {
+        self.value = 0;
+    }
+
+    /// Add to the meter, stopping when full. Returns the amount added.
+    pub fn saturating_add(&mut self, amount: u32) -> u32 {
+        let added = amount.min(self.max_value.saturating_sub(self.value));
+        self.value += added;
+
+        added
+    }
+
+    /// Remove from the meter, stopping when empty. Returns the amount removed.
+    pub fn saturating_sub(&mut self, amount: u32) -> u32 {
+        let removed = amount.min(self.value);
+        self.value -= removed;
+
+        removed
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.value >= self.max_value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value == 0
+    }
+
+    /// How full the meter is, from 0 to 100
+    pub fn percentage(&self) -> u8 {
+        self.to_range().percentage()
+    }
+
+    /// The meter normalized to a `Range`, such as for display
+    pub fn to_range(&self) -> Range {
+        Range::map(self.value, 0, self.max_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn Meter_fill_and_drain() {
+        let mut meter = Meter::new(100_000);
+        assert!(meter.is_full());
+
+        meter.drain();
+        assert!(meter.is_empty());
+        assert_eq!(100_000, meter.max_value);
+
+        meter.fill();
+        assert_eq!(Meter::new(100_000), meter);
+    }
+
+    #[test]
+    fn Meter_saturating_add() {
+        let mut meter = Meter::new(u32::MAX);
+        meter.value = u32::MAX - 5;
+
+        assert_eq!(3, meter.saturating_add(3));
+        assert_eq!(u32::MAX - 2, meter.value);
+
+        assert_eq!(2, meter.saturating_add(100));
+        assert!(meter.is_full());
+    }
+
+    #[test]
+    fn Meter_saturating_sub() {
+        let mut meter = Meter::new(10);
+
+        assert_eq!(3, meter.saturating_sub(3));
+        assert_eq!(7, meter.value);
+
+        assert_eq!(7, meter.saturating_sub(100));
+        assert!(meter.is_empty());
+    }
+
+    #[test]
+    fn Meter_to_range() {
+        let mut meter = Meter::new(1000);
+        assert_eq!(Range::full(), meter.to_range());
+        assert_eq!(100, meter.percentage());
+
+        meter.value = 500;
+        assert_eq!(Range::new(128), meter.to_range());
+        assert_eq!(50, meter.percentage());
+
+        meter.drain();
+        assert_eq!(Range::empty(), meter.to_range());
+
+        // A meter without a maximum can't be filled any further
+        assert_eq!(Range::full(), Meter::new(0).to_range());
+    }
+}
@@ -5,9 +5,19 @@ mod vec3d;
 pub use vec3d::Vec3d;
 
 mod range;
-pub use range::Range;
+pub use range::{Mappable, Range};
+
+mod meter;
+pub use meter::Meter;
 
 pub mod rng;
+pub use rng::{Dice, Rng};
+
+mod mat4;
+pub use mat4::Mat4;
+
+mod quaternion;
+pub use quaternion::Quaternion;
 
 pub mod rotation;
 pub use rotation::{Axi, Rotation3d};
@@ -0,0 +1,415 @@
+use super::*;
+
+/// Fixed point quaternion, used for composing rotations.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: FixedNumber,
+    pub x: FixedNumber,
+    pub y: FixedNumber,
+    pub z: FixedNumber,
+}
+
+impl Quaternion {
+    pub fn new(w: FixedNumber, x: FixedNumber, y: FixedNumber, z: FixedNumber) -> Self {
+        Self {
+            w: w,
+            x: x,
+            y: y,
+            z: z,
+        }
+    }
+
+    /// A quaternion that applies no rotation
+    pub fn identity() -> Self {
+        Self::new(1.into(), 0.into(), 0.into(), 0.into())
+    }
+
+    /// Create a rotation of the given radians around the axis
+    pub fn from_axis_angle(axis: Vec3d, radians: FixedNumber) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (radians / 2.into()).sin_cos();
+
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    /// Create a rotation from euler angles. Applies roll (z), then pitch (x), then yaw (y).
+    pub fn from_euler(rotation: Rotation3d) -> Self {
+        let yaw = Self::from_axis_angle((0, 1, 0).into(), rotation.yaw_radians);
+        let pitch = Self::from_axis_angle((1, 0, 0).into(), rotation.pitch_radians);
+        let roll = Self::from_axis_angle((0, 0, 1).into(), rotation.roll_radians);
+
+        // Note: order is important!
+        yaw * pitch * roll
+    }
+
+    /// Convert to euler angles, using the same convention as `from_euler()`. Pitch is limited to [-PI/2, PI/2].
+    pub fn to_euler(&self) -> Rotation3d {
+        let m = Mat4::from_quaternion(*self).m;
+
+        let one: FixedNumber = 1.into();
+        let sin_pitch = FixedNumber::clamp(-m[1][2], -one, one);
+        let cos_pitch = (one - sin_pitch * sin_pitch).sqrt();
+        let pitch = FixedNumber::atan2(sin_pitch, cos_pitch);
+
+        // When looking straight up or down, yaw and roll rotate around the same axis so put it all in to yaw
+        let gimbal_locked = cos_pitch == 0.into();
+
+        let (yaw, roll) = if gimbal_locked {
+            (FixedNumber::atan2(-m[2][0], m[0][0]), 0.into())
+        } else {
+            (
+                FixedNumber::atan2(m[0][2], m[2][2]),
+                FixedNumber::atan2(m[1][0], m[1][1]),
+            )
+        };
+
+        Rotation3d {
+            pitch_radians: pitch,
+            yaw_radians: yaw,
+            roll_radians: roll,
+        }
+    }
+
+    /// Create a rotation where the forward axis (-z) points in the given direction.
+    pub fn look_at(forward: Vec3d, up: Vec3d) -> Self {
+        let back = -forward.normalize();
+        let right = up.cross(back).normalize();
+        let up = back.cross(right);
+
+        // The columns of a rotation matrix are the rotated axi
+        let mut m = Mat4::identity();
+        m.m[0][0] = right.x;
+        m.m[1][0] = right.y;
+        m.m[2][0] = right.z;
+        m.m[0][1] = up.x;
+        m.m[1][1] = up.y;
+        m.m[2][1] = up.z;
+        m.m[0][2] = back.x;
+        m.m[1][2] = back.y;
+        m.m[2][2] = back.z;
+
+        Self::from_mat4(m)
+    }
+
+    /// Extract the rotation from the upper 3x3 of the matrix
+    pub fn from_mat4(m: Mat4) -> Self {
+        let m = m.m;
+        let one: FixedNumber = 1.into();
+        let two: FixedNumber = 2.into();
+        let quarter = FixedNumber::from_bits(1024);
+
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        let q = if trace > 0.into() {
+            let s = (trace + one).sqrt() * two;
+            Self::new(
+                quarter * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (one + m[0][0] - m[1][1] - m[2][2]).sqrt() * two;
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                quarter * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (one + m[1][1] - m[0][0] - m[2][2]).sqrt() * two;
+            Self::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                quarter * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = (one + m[2][2] - m[0][0] - m[1][1]).sqrt() * two;
+            Self::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                quarter * s,
+            )
+        };
+
+        q.normalize()
+    }
+
+    pub fn dot(&self, other: Self) -> FixedNumber {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn length(&self) -> FixedNumber {
+        self.dot(*self).sqrt()
+    }
+
+    /// Returns a quaternion with a length of 1. A zero quaternion returns the identity.
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.into() {
+            return Self::identity();
+        }
+
+        Self::new(
+            self.w / length,
+            self.x / length,
+            self.y / length,
+            self.z / length,
+        )
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// The rotation that undoes this one. A zero quaternion returns the identity.
+    pub fn inverse(&self) -> Self {
+        let length_squared = self.dot(*self);
+        if length_squared == 0.into() {
+            return Self::identity();
+        }
+
+        let conjugate = self.conjugate();
+
+        Self::new(
+            conjugate.w / length_squared,
+            conjugate.x / length_squared,
+            conjugate.y / length_squared,
+            conjugate.z / length_squared,
+        )
+    }
+
+    /// Rotate the given vector
+    pub fn rotate_vec3d(&self, vec3d: Vec3d) -> Vec3d {
+        // v + 2w(q x v) + 2q x (q x v), where q is the vector part
+        let q = Vec3d::new(self.x, self.y, self.z);
+        let t = q.cross(vec3d).multiply(2.into());
+
+        vec3d + t.multiply(self.w) + q.cross(t)
+    }
+
+    /// Spherical linear interpolation, where a `t` of 0 returns self and a `t` of 1 returns other. Always takes the shortest path.
+    pub fn slerp(&self, other: Self, t: FixedNumber) -> Self {
+        let one: FixedNumber = 1.into();
+
+        let mut other = other;
+        let mut dot = self.dot(other);
+
+        if dot < 0.into() {
+            other = Self::new(-other.w, -other.x, -other.y, -other.z);
+            dot = -dot;
+        }
+
+        let dot = FixedNumber::min(dot, one);
+
+        let sin_theta = (one - dot * dot).sqrt();
+
+        // When the rotations are close, sin(theta) is too small to divide by so fall back to a normalized lerp
+        let (weight_self, weight_other) = if sin_theta <= FixedNumber::from_bits(64) {
+            (one - t, t)
+        } else {
+            let theta = FixedNumber::atan2(sin_theta, dot);
+
+            (
+                ((one - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+
+        Self::new(
+            self.w * weight_self + other.w * weight_other,
+            self.x * weight_self + other.x * weight_other,
+            self.y * weight_self + other.y * weight_other,
+            self.z * weight_self + other.z * weight_other,
+        )
+        .normalize()
+    }
+}
+
+impl std::ops::Mul for Quaternion {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> <Self as std::ops::Mul<Self>>::Output {
+        Self::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+impl std::ops::MulAssign for Quaternion {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: FixedNumber, actual: FixedNumber, tolerance_bits: i32) {
+        let difference = (expected.to_bits() - actual.to_bits()).abs();
+
+        assert!(
+            difference <= tolerance_bits,
+            "expected: {:?}, actual: {:?}",
+            expected,
+            actual
+        );
+    }
+
+    fn assert_vec3d_close(expected: Vec3d, actual: Vec3d, tolerance_bits: i32) {
+        assert_close(expected.x, actual.x, tolerance_bits);
+        assert_close(expected.y, actual.y, tolerance_bits);
+        assert_close(expected.z, actual.z, tolerance_bits);
+    }
+
+    fn assert_rotation_close(expected: Rotation3d, actual: Rotation3d, tolerance_bits: i32) {
+        assert_close(expected.pitch_radians, actual.pitch_radians, tolerance_bits);
+        assert_close(expected.yaw_radians, actual.yaw_radians, tolerance_bits);
+        assert_close(expected.roll_radians, actual.roll_radians, tolerance_bits);
+    }
+
+    fn radians(milliradians: i32) -> FixedNumber {
+        let milliradians: FixedNumber = milliradians.into();
+
+        milliradians / 1000.into()
+    }
+
+    #[test]
+    fn Quaternion_identity_does_not_rotate() {
+        let v: Vec3d = (3, -4, 5).into();
+
+        assert_eq!(v, Quaternion::identity().rotate_vec3d(v));
+    }
+
+    #[test]
+    fn Quaternion_rotate_vec3d_around_axi() {
+        let quarter_turn = FixedNumber::HALF_PI();
+
+        let q = Quaternion::from_axis_angle((0, 1, 0).into(), quarter_turn);
+        assert_vec3d_close((0, 0, -1).into(), q.rotate_vec3d((1, 0, 0).into()), 4);
+
+        let q = Quaternion::from_axis_angle((1, 0, 0).into(), quarter_turn);
+        assert_vec3d_close((0, 0, 1).into(), q.rotate_vec3d((0, 1, 0).into()), 4);
+
+        let q = Quaternion::from_axis_angle((0, 0, 1).into(), quarter_turn);
+        assert_vec3d_close((0, 1, 0).into(), q.rotate_vec3d((1, 0, 0).into()), 4);
+    }
+
+    #[test]
+    fn Quaternion_compose() {
+        let quarter_turn = FixedNumber::HALF_PI();
+        let q = Quaternion::from_axis_angle((0, 1, 0).into(), quarter_turn);
+
+        let half_turn = q * q;
+
+        assert_vec3d_close(
+            (-1, 0, 0).into(),
+            half_turn.rotate_vec3d((1, 0, 0).into()),
+            8,
+        );
+    }
+
+    #[test]
+    fn Quaternion_inverse() {
+        let q = Quaternion::from_euler(Rotation3d {
+            pitch_radians: radians(300),
+            yaw_radians: radians(-1200),
+            roll_radians: radians(700),
+        });
+
+        let v: Vec3d = (3, -4, 5).into();
+        let round_trip = q.inverse().rotate_vec3d(q.rotate_vec3d(v));
+
+        assert_vec3d_close(v, round_trip, 16);
+    }
+
+    #[test]
+    fn Quaternion_euler_round_trip() {
+        let rotations = [
+            (0, 0, 0),
+            (300, -1200, 700),
+            (-1000, 2500, -3000),
+            (1500, 0, 0),
+        ];
+
+        for (pitch, yaw, roll) in rotations.iter() {
+            let rotation = Rotation3d {
+                pitch_radians: radians(*pitch),
+                yaw_radians: radians(*yaw),
+                roll_radians: radians(*roll),
+            };
+
+            let round_trip = Quaternion::from_euler(rotation).to_euler();
+
+            assert_rotation_close(rotation, round_trip, 24);
+        }
+    }
+
+    #[test]
+    fn Quaternion_euler_matches_single_axis_rotations() {
+        let rotation = Rotation3d {
+            pitch_radians: radians(400),
+            yaw_radians: radians(900),
+            roll_radians: radians(-600),
+        };
+        let v: Vec3d = (2, 3, -1).into();
+
+        // Roll, then pitch, then yaw
+        let expected = rotation.rotate_vec3d_on_axis(v, Axi::Z);
+        let expected = rotation.rotate_vec3d_on_axis(expected, Axi::X);
+        let expected = rotation.rotate_vec3d_on_axis(expected, Axi::Y);
+
+        let actual = Quaternion::from_euler(rotation).rotate_vec3d(v);
+
+        assert_vec3d_close(expected, actual, 16);
+    }
+
+    #[test]
+    fn Quaternion_slerp() {
+        let a = Quaternion::identity();
+        let b = Quaternion::from_axis_angle((0, 1, 0).into(), FixedNumber::HALF_PI());
+
+        let start = a.slerp(b, 0.into());
+        assert_close(a.w, start.w, 4);
+        assert_close(a.y, start.y, 4);
+
+        let end = a.slerp(b, 1.into());
+        assert_close(b.w, end.w, 4);
+        assert_close(b.y, end.y, 4);
+
+        let halfway = a.slerp(b, FixedNumber::from_bits(2048));
+        let expected =
+            Quaternion::from_axis_angle((0, 1, 0).into(), FixedNumber::HALF_PI() / 2.into());
+
+        assert_close(expected.w, halfway.w, 4);
+        assert_close(expected.x, halfway.x, 4);
+        assert_close(expected.y, halfway.y, 4);
+        assert_close(expected.z, halfway.z, 4);
+    }
+
+    #[test]
+    fn Quaternion_slerp_takes_shortest_path() {
+        let a = Quaternion::identity();
+        let b = Quaternion::from_axis_angle((0, 1, 0).into(), FixedNumber::HALF_PI());
+        let negated_b = Quaternion::new(-b.w, -b.x, -b.y, -b.z);
+
+        assert_eq!(
+            a.slerp(b, FixedNumber::from_bits(1024)),
+            a.slerp(negated_b, FixedNumber::from_bits(1024))
+        );
+    }
+
+    #[test]
+    fn Quaternion_look_at() {
+        let forward: Vec3d = (1, 0, 0).into();
+        let q = Quaternion::look_at(forward, (0, 1, 0).into());
+
+        assert_vec3d_close(forward, q.rotate_vec3d((0, 0, -1).into()), 8);
+        assert_vec3d_close((0, 1, 0).into(), q.rotate_vec3d((0, 1, 0).into()), 8);
+    }
+}
@@ -1,16 +1,246 @@
-#[derive(Copy, Clone, Debug, PartialEq)]
+use super::*;
+
+/// A normalized meter, where `Range::min` is empty and `Range::max` is full. Used for ailment status meters.
+/// Hit points and stamina need more than 256 steps, so they use the wider `Meter`, which can be shown as a `Range`.
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 pub struct Range {
     pub value: u8,
 }
+
+/// Values that can be mapped into a `Range`.
+pub trait Mappable: Copy + PartialOrd {
+    /// Convert to an i64 which preserves ordering and relative distances
+    fn to_i64(self) -> i64;
+}
+
+impl Mappable for u8 {
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Mappable for u16 {
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Mappable for u32 {
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Mappable for i32 {
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Mappable for i64 {
+    fn to_i64(self) -> i64 {
+        self
+    }
+}
+
+impl Mappable for FixedNumber {
+    fn to_i64(self) -> i64 {
+        self.to_bits() as i64
+    }
+}
+
 impl Range {
     pub const max: u8 = 255;
     pub const min: u8 = 0;
 
-    pub fn map<T>(value: T, min: T, max: T) -> Self {
-        unimplemented!();
+    pub fn new(value: u8) -> Self {
+        Self { value: value }
+    }
+
+    /// An empty meter
+    pub fn empty() -> Self {
+        Self::new(Self::min)
+    }
+
+    /// A full meter
+    pub fn full() -> Self {
+        Self::new(Self::max)
+    }
+
+    /// Map the value from [min, max] on to the range, clamping anything outside of it.
+    pub fn map<T>(value: T, min: T, max: T) -> Self
+    where
+        T: Mappable,
+    {
+        if max <= min {
+            if value >= max {
+                return Self::full();
+            }
+
+            return Self::empty();
+        }
+
+        if value <= min {
+            return Self::empty();
+        } else if value >= max {
+            return Self::full();
+        }
+
+        // i128 so the full i64 range can't overflow
+        let offset = value.to_i64() as i128 - min.to_i64() as i128;
+        let span = max.to_i64() as i128 - min.to_i64() as i128;
 
-        let v = Self::min;
+        // Round to the nearest value
+        let mapped = (offset * Self::max as i128 * 2 + span) / (span * 2);
+
+        Self::new(mapped as u8)
+    }
+
+    /// Fill the meter completely
+    pub fn fill(&mut self) {
+        self.value = Self::max;
+    }
+
+    /// Drain the meter completely
+    pub fn drain(&mut self) {
+        self.value = Self::min;
+    }
+
+    /// Add to the meter, stopping when full
+    pub fn saturating_add(&mut self, amount: u8) {
+        self.value = self.value.saturating_add(amount);
+    }
+
+    /// Remove from the meter, stopping when empty
+    pub fn saturating_sub(&mut self, amount: u8) {
+        self.value = self.value.saturating_sub(amount);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.value == Self::max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value == Self::min
+    }
+
+    /// How full the meter is, from 0 to 100
+    pub fn percentage(&self) -> u8 {
+        self.scale(100) as u8
+    }
+
+    /// How full the meter is, from 0 to 1
+    pub fn to_fixed(&self) -> FixedNumber {
+        let value: FixedNumber = (self.value as i32).into();
+        let max: FixedNumber = (Self::max as i32).into();
+
+        value / max
+    }
+
+    /// Scale the meter to [0, max], rounding to the nearest value. Useful for converting back to hit points.
+    pub fn scale(&self, max: u32) -> u32 {
+        let scaled =
+            (self.value as u64 * max as u64 * 2 + Self::max as u64) / (Self::max as u64 * 2);
+
+        scaled as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn Range_map_integers() {
+        assert_eq!(Range::empty(), Range::map(0, 0, 100));
+        assert_eq!(Range::full(), Range::map(100, 0, 100));
+        assert_eq!(Range::new(128), Range::map(50, 0, 100));
+        assert_eq!(Range::new(64), Range::map(-50, -100, 100));
+        assert_eq!(Range::new(51), Range::map(20u32, 0u32, 100u32));
+        assert_eq!(Range::new(7), Range::map(7u8, 0u8, 255u8));
+    }
+
+    #[test]
+    fn Range_map_fixed() {
+        let min: FixedNumber = (-2).into();
+        let max: FixedNumber = 2.into();
+
+        assert_eq!(Range::empty(), Range::map(min, min, max));
+        assert_eq!(Range::full(), Range::map(max, min, max));
+        assert_eq!(Range::new(128), Range::map(0.into(), min, max));
+        assert_eq!(Range::new(191), Range::map(1.into(), min, max));
+    }
+
+    #[test]
+    fn Range_map_clamps() {
+        assert_eq!(Range::empty(), Range::map(-10, 0, 100));
+        assert_eq!(Range::full(), Range::map(1000, 0, 100));
+        assert_eq!(Range::full(), Range::map(i64::MAX, i64::MIN, 0));
+    }
+
+    #[test]
+    fn Range_map_empty_span() {
+        assert_eq!(Range::full(), Range::map(5, 5, 5));
+        assert_eq!(Range::empty(), Range::map(4, 5, 5));
+        assert_eq!(Range::full(), Range::map(5, 10, 0));
+    }
+
+    #[test]
+    fn Range_fill_and_drain() {
+        let mut range = Range::new(100);
+
+        range.fill();
+        assert!(range.is_full());
+
+        range.drain();
+        assert!(range.is_empty());
+    }
+
+    #[test]
+    fn Range_saturating_add() {
+        let mut range = Range::new(250);
+
+        range.saturating_add(3);
+        assert_eq!(Range::new(253), range);
+        assert_eq!(false, range.is_full());
+
+        range.saturating_add(100);
+        assert_eq!(Range::full(), range);
+    }
+
+    #[test]
+    fn Range_saturating_sub() {
+        let mut range = Range::new(5);
+
+        range.saturating_sub(3);
+        assert_eq!(Range::new(2), range);
+
+        range.saturating_sub(100);
+        assert_eq!(Range::empty(), range);
+    }
+
+    #[test]
+    fn Range_percentage() {
+        assert_eq!(0, Range::empty().percentage());
+        assert_eq!(100, Range::full().percentage());
+        assert_eq!(50, Range::new(128).percentage());
+        assert_eq!(25, Range::new(64).percentage());
+    }
+
+    #[test]
+    fn Range_to_fixed() {
+        let zero: FixedNumber = 0.into();
+        let one: FixedNumber = 1.into();
+
+        assert_eq!(zero, Range::empty().to_fixed());
+        assert_eq!(one, Range::full().to_fixed());
+    }
 
-        return Self { value: v };
+    #[test]
+    fn Range_scale() {
+        assert_eq!(0, Range::empty().scale(1000));
+        assert_eq!(1000, Range::full().scale(1000));
+        assert_eq!(502, Range::new(128).scale(1000));
+        assert_eq!(128, Range::map(50u32, 0, 100).value);
     }
 }
@@ -0,0 +1,396 @@
+use super::*;
+
+/// Deterministic random number generator (PCG32, XSH RR variant). Given the same seed, it will always produce the same sequence, no matter the platform.
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+    const DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+    /// Create a new generator from the given seed
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, Self::DEFAULT_STREAM)
+    }
+
+    /// Create a new generator from the given seed and stream. Different streams with the same seed produce different sequences.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+
+        rng
+    }
+
+    /// Restore a generator from a previously saved state
+    pub fn from_state(state: (u64, u64)) -> Self {
+        Self {
+            state: state.0,
+            increment: state.1,
+        }
+    }
+
+    /// Retrieve the state of the generator, so that it may be saved and restored later
+    pub fn state(&self) -> (u64, u64) {
+        (self.state, self.increment)
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+
+    /// Generate the next random u32
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.step();
+
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// Generate a number in [0, bound) without modulo bias. A bound of 0 returns 0.
+    pub fn next_bounded(&mut self, bound: u32) -> u32 {
+        if bound == 0 {
+            return 0;
+        }
+
+        // Reject anything below the threshold so that every result is equally likely
+        let threshold = bound.wrapping_neg() % bound;
+
+        loop {
+            let value = self.next_u32();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Generate a number in [min, max]
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+
+        let span = (max as i64 - min as i64 + 1) as u64;
+        if span > u32::MAX as u64 {
+            return self.next_u32() as i32;
+        }
+
+        (min as i64 + self.next_bounded(span as u32) as i64) as i32
+    }
+
+    /// Generate a number in [0, 1)
+    pub fn next_fixed(&mut self) -> FixedNumber {
+        // The top 12 bits fill the fractional part
+        FixedNumber::from_bits((self.next_u32() >> 20) as i32)
+    }
+
+    /// Roll the given dice, returning the total
+    pub fn roll(&mut self, dice: Dice) -> i32 {
+        let mut total = dice.modifier;
+
+        for _ in 0..dice.count {
+            total += self.range(1, dice.sides as i32);
+        }
+
+        total
+    }
+
+    /// Roll a single six sided die
+    pub fn d6(&mut self) -> u8 {
+        self.roll(Dice::d6()) as u8
+    }
+
+    /// Pick one of the choices, where the chance of each is its weight divided by the sum of all weights. Returns None if there is nothing to pick.
+    pub fn weighted_choice<'a, T>(&mut self, choices: &'a [(T, u32)]) -> Option<&'a T> {
+        let total: u64 = choices.iter().map(|(_, weight)| *weight as u64).sum();
+        if total == 0 || total > u32::MAX as u64 {
+            return None;
+        }
+
+        let mut roll = self.next_bounded(total as u32);
+
+        for (choice, weight) in choices {
+            if roll < *weight {
+                return Some(choice);
+            }
+
+            roll -= *weight;
+        }
+
+        None
+    }
+}
+
+/// A set of dice in tabletop notation, such as 'd6' or '2d6+1'.
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+pub struct Dice {
+    pub count: u8,
+    pub sides: u8,
+    pub modifier: i32,
+}
+
+impl Dice {
+    pub fn new(count: u8, sides: u8) -> Self {
+        Self {
+            count: count,
+            sides: sides,
+            modifier: 0,
+        }
+    }
+
+    /// A single six sided die
+    pub fn d6() -> Self {
+        Self::new(1, 6)
+    }
+
+    /// Parse dice notation, such as 'd6', '2d6' or '3d8-2'
+    pub fn parse(notation: &str) -> Result<Self, String> {
+        let notation = notation.trim().to_lowercase();
+
+        let d_index = match notation.find('d') {
+            Some(i) => i,
+            None => {
+                return Err(format!("Dice '{}' is missing a 'd'.", notation));
+            }
+        };
+
+        let count = &notation[..d_index];
+        let count = if count.is_empty() {
+            1
+        } else {
+            match count.parse::<u8>() {
+                Ok(count) => count,
+                Err(_) => {
+                    return Err(format!("Dice '{}' has an invalid count.", notation));
+                }
+            }
+        };
+
+        let rest = &notation[d_index + 1..];
+        let (sides, modifier) = match rest.find(|c| c == '+' || c == '-') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+
+        let sides = match sides.parse::<u8>() {
+            Ok(sides) if sides > 0 => sides,
+            _ => {
+                return Err(format!("Dice '{}' has invalid sides.", notation));
+            }
+        };
+
+        let modifier = if modifier.is_empty() {
+            0
+        } else {
+            match modifier.trim_start_matches('+').parse::<i32>() {
+                Ok(modifier) => modifier,
+                Err(_) => {
+                    return Err(format!("Dice '{}' has an invalid modifier.", notation));
+                }
+            }
+        };
+
+        Ok(Self {
+            count: count,
+            sides: sides,
+            modifier: modifier,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn Rng_matches_pcg32_reference() {
+        // Reference output from the PCG32 demo, seeded with 42 on stream 54
+        let mut rng = Rng::with_stream(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+
+        for value in expected.iter() {
+            assert_eq!(*value, rng.next_u32());
+        }
+    }
+
+    #[test]
+    fn Rng_next_u32_sequence() {
+        let mut rng = Rng::new(1234);
+        let expected: [u32; 5] = [1134688154, 3434832470, 1049669977, 3144453042, 1676924286];
+
+        for value in expected.iter() {
+            assert_eq!(*value, rng.next_u32());
+        }
+    }
+
+    #[test]
+    fn Rng_same_seed_same_sequence() {
+        let mut a = Rng::new(99);
+        let mut b = Rng::new(99);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn Rng_restore_from_state() {
+        let mut rng = Rng::new(7);
+        rng.next_u32();
+
+        let mut restored = Rng::from_state(rng.state());
+
+        for _ in 0..100 {
+            assert_eq!(rng.next_u32(), restored.next_u32());
+        }
+    }
+
+    #[test]
+    fn Rng_range_sequence() {
+        let mut rng = Rng::new(1234);
+        let expected: [i32; 8] = [1, -2, -1, 0, -1, 4, 1, -1];
+
+        for value in expected.iter() {
+            assert_eq!(*value, rng.range(-5, 5));
+        }
+    }
+
+    #[test]
+    fn Rng_range_bounds() {
+        let mut rng = Rng::new(5);
+
+        for _ in 0..1000 {
+            let value = rng.range(-3, 3);
+            assert!(value >= -3 && value <= 3);
+        }
+
+        assert_eq!(4, rng.range(4, 4));
+        assert_eq!(4, rng.range(4, 2));
+    }
+
+    #[test]
+    fn Rng_next_fixed_sequence() {
+        let mut rng = Rng::new(1234);
+        let expected: [i32; 5] = [1082, 3275, 1001, 2998, 1599];
+
+        for value in expected.iter() {
+            let n = rng.next_fixed();
+
+            assert_eq!(*value, n.to_bits());
+        }
+    }
+
+    #[test]
+    fn Rng_next_fixed_bounds() {
+        let mut rng = Rng::new(5);
+        let zero: FixedNumber = 0.into();
+        let one: FixedNumber = 1.into();
+
+        for _ in 0..1000 {
+            let n = rng.next_fixed();
+            assert!(n >= zero && n < one);
+        }
+    }
+
+    #[test]
+    fn Rng_d6_sequence() {
+        let mut rng = Rng::new(1234);
+        let expected: [u8; 10] = [3, 3, 2, 1, 1, 4, 4, 3, 4, 2];
+
+        for value in expected.iter() {
+            assert_eq!(*value, rng.d6());
+        }
+    }
+
+    #[test]
+    fn Rng_roll_sequence() {
+        let mut rng = Rng::new(1234);
+        let dice = Dice::parse("2d6+1").unwrap();
+        let expected: [i32; 5] = [7, 4, 6, 8, 7];
+
+        for value in expected.iter() {
+            assert_eq!(*value, rng.roll(dice));
+        }
+    }
+
+    #[test]
+    fn Rng_roll_bounds() {
+        let mut rng = Rng::new(5);
+        let dice = Dice::parse("3d4-2").unwrap();
+
+        for _ in 0..1000 {
+            let value = rng.roll(dice);
+            assert!(value >= 1 && value <= 10);
+        }
+    }
+
+    #[test]
+    fn Rng_weighted_choice_sequence() {
+        let mut rng = Rng::new(1234);
+        let choices = [("common", 8), ("rare", 2), ("never", 0)];
+        let expected = [
+            "common", "common", "common", "common", "common", "rare", "common", "common",
+        ];
+
+        for value in expected.iter() {
+            assert_eq!(Some(value), rng.weighted_choice(&choices));
+        }
+    }
+
+    #[test]
+    fn Rng_weighted_choice_empty() {
+        let mut rng = Rng::new(5);
+        let empty: [(u8, u32); 0] = [];
+        let zero_weights = [(1, 0), (2, 0)];
+
+        assert_eq!(None, rng.weighted_choice(&empty));
+        assert_eq!(None, rng.weighted_choice(&zero_weights));
+    }
+
+    #[test]
+    fn Dice_parse() {
+        assert_eq!(Ok(Dice::d6()), Dice::parse("d6"));
+        assert_eq!(Ok(Dice::new(2, 6)), Dice::parse("2d6"));
+        assert_eq!(
+            Ok(Dice {
+                count: 3,
+                sides: 8,
+                modifier: -2
+            }),
+            Dice::parse("3D8-2")
+        );
+        assert_eq!(
+            Ok(Dice {
+                count: 1,
+                sides: 20,
+                modifier: 5
+            }),
+            Dice::parse(" 1d20+5 ")
+        );
+    }
+
+    #[test]
+    fn Dice_parse_errors() {
+        assert!(Dice::parse("6").is_err());
+        assert!(Dice::parse("xd6").is_err());
+        assert!(Dice::parse("2d").is_err());
+        assert!(Dice::parse("2d0").is_err());
+        assert!(Dice::parse("2d6+x").is_err());
+    }
+}
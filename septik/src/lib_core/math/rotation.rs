@@ -6,7 +6,7 @@ pub enum Axi {
     Z,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 pub struct Rotation3d {
     /// The 'x' rotation
     pub pitch_radians: FixedNumber,
@@ -25,12 +25,24 @@ impl Rotation3d {
         }
     }
 
-    pub fn apply_to_vec3d(self, vec3d: Vec3d) -> Vec3d {
-        let x_rot = Self::rotate_around_single_axis(self.pitch_radians, vec3d, Axi::X);
-        let y_rot = Self::rotate_around_single_axis(self.yaw_radians, vec3d, Axi::Y);
-        let z_rot = Self::rotate_around_single_axis(self.roll_radians, vec3d, Axi::Z);
+    /// Create a rotation from a quaternion
+    pub fn from_quaternion(quaternion: Quaternion) -> Self {
+        quaternion.to_euler()
+    }
+
+    /// Convert to a quaternion. Applies roll, then pitch, then yaw.
+    pub fn to_quaternion(self) -> Quaternion {
+        Quaternion::from_euler(self)
+    }
 
-        x_rot + y_rot + z_rot
+    /// Convert to a rotation matrix
+    pub fn to_mat4(self) -> Mat4 {
+        Mat4::from_quaternion(self.to_quaternion())
+    }
+
+    /// Rotate the vector by the composed rotation. Applies roll, then pitch, then yaw.
+    pub fn apply_to_vec3d(self, vec3d: Vec3d) -> Vec3d {
+        self.to_quaternion().rotate_vec3d(vec3d)
     }
 
     pub fn rotate_vec3d_on_axis(self, vec3d: Vec3d, axis: Axi) -> Vec3d {
@@ -46,22 +58,12 @@ impl Rotation3d {
         let y = vec3d.y;
         let z = vec3d.z;
 
+        let (sin, cos) = radians.sin_cos();
+
         let rotated_vec = match axis {
-            Axi::X => Vec3d::new(
-                x,
-                y * radians.cos() - z * radians.sin(),
-                y * radians.sin() + z * radians.cos(),
-            ),
-            Axi::Y => Vec3d::new(
-                x * radians.cos() + z * radians.sin(),
-                y,
-                -x * radians.sin() + z * radians.cos(),
-            ),
-            Axi::Z => Vec3d::new(
-                x * radians.cos() - y * radians.sin(),
-                x * radians.sin() + y * radians.cos(),
-                z,
-            ),
+            Axi::X => Vec3d::new(x, y * cos - z * sin, y * sin + z * cos),
+            Axi::Y => Vec3d::new(x * cos + z * sin, y, -x * sin + z * cos),
+            Axi::Z => Vec3d::new(x * cos - y * sin, x * sin + y * cos, z),
         };
 
         rotated_vec
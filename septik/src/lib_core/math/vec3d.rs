@@ -1,6 +1,6 @@
 use super::*;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 pub struct Vec3d {
     pub x: FixedNumber,
     pub y: FixedNumber,
@@ -23,6 +23,97 @@ impl Vec3d {
             z: self.z * number,
         };
     }
+
+    /// Dot product
+    pub fn dot(&self, other: Self) -> FixedNumber {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Cross product
+    pub fn cross(&self, other: Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// The squared length of the vector. Cheaper than `length()` when only comparisons are needed.
+    pub fn length_squared(&self) -> FixedNumber {
+        self.dot(*self)
+    }
+
+    /// The length of the vector
+    pub fn length(&self) -> FixedNumber {
+        // Sum the squares as u64 so large vectors don't overflow before the root is taken.
+        // Each square is at most 2^62, so three of them still fit.
+        let square = |n: FixedNumber| {
+            let bits = n.to_bits().unsigned_abs() as u64;
+            bits * bits
+        };
+
+        let sum_of_squares = square(self.x)
+            .saturating_add(square(self.y))
+            .saturating_add(square(self.z));
+        let length = fixed_number::isqrt_rounded(sum_of_squares);
+
+        if length > i32::MAX as u64 {
+            return FixedNumber::from_bits(i32::MAX);
+        }
+
+        FixedNumber::from_bits(length as i32)
+    }
+
+    /// Returns a vector with a length of 1 in the same direction. A zero vector returns a zero vector.
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.into() {
+            return Self::default();
+        }
+
+        Self::new(self.x / length, self.y / length, self.z / length)
+    }
+
+    /// The distance between two points
+    pub fn distance(&self, other: Self) -> FixedNumber {
+        (other - *self).length()
+    }
+
+    /// The squared distance between two points
+    pub fn distance_squared(&self, other: Self) -> FixedNumber {
+        (other - *self).length_squared()
+    }
+
+    /// Linearly interpolate between two vectors, where a `t` of 0 returns self and a `t` of 1 returns other.
+    pub fn lerp(&self, other: Self, t: FixedNumber) -> Self {
+        *self + (other - *self).multiply(t)
+    }
+
+    /// Restrict each component to the range of the matching components in min and max
+    pub fn clamp(&self, min: Self, max: Self) -> Self {
+        Self::new(
+            FixedNumber::clamp(self.x, min.x, max.x),
+            FixedNumber::clamp(self.y, min.y, max.y),
+            FixedNumber::clamp(self.z, min.z, max.z),
+        )
+    }
+
+    /// Project the vector onto another. Projecting onto a zero vector returns a zero vector.
+    pub fn project(&self, onto: Self) -> Self {
+        let onto_length_squared = onto.length_squared();
+        if onto_length_squared == 0.into() {
+            return Self::default();
+        }
+
+        onto.multiply(self.dot(onto) / onto_length_squared)
+    }
+
+    /// Reflect the vector off of a surface with the given normal. The normal is expected to be normalized.
+    pub fn reflect(&self, normal: Self) -> Self {
+        let two: FixedNumber = 2.into();
+
+        *self - normal.multiply(two * self.dot(normal))
+    }
 }
 
 impl std::ops::Neg for Vec3d {
@@ -118,3 +209,130 @@ impl Into<Vec3d> for (i32, i32, i32) {
         return Vec3d::new(self.0.into(), self.1.into(), self.2.into());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec(x: i32, y: i32, z: i32) -> Vec3d {
+        (x, y, z).into()
+    }
+
+    #[test]
+    fn Vec3d_dot() {
+        let expected: FixedNumber = 32.into();
+
+        assert_eq!(expected, vec(1, 2, 3).dot(vec(4, 5, 6)));
+
+        let expected: FixedNumber = 0.into();
+
+        assert_eq!(expected, vec(1, 0, 0).dot(vec(0, 1, 0)));
+    }
+
+    #[test]
+    fn Vec3d_cross() {
+        assert_eq!(vec(0, 0, 1), vec(1, 0, 0).cross(vec(0, 1, 0)));
+        assert_eq!(vec(0, 0, -1), vec(0, 1, 0).cross(vec(1, 0, 0)));
+        assert_eq!(vec(-3, 6, -3), vec(1, 2, 3).cross(vec(4, 5, 6)));
+    }
+
+    #[test]
+    fn Vec3d_length_squared() {
+        let expected: FixedNumber = 50.into();
+
+        assert_eq!(expected, vec(3, 4, 5).length_squared());
+    }
+
+    #[test]
+    fn Vec3d_length() {
+        let expected: FixedNumber = 5.into();
+        assert_eq!(expected, vec(3, 4, 0).length());
+
+        let expected: FixedNumber = 13.into();
+        assert_eq!(expected, vec(0, -5, 12).length());
+
+        let expected: FixedNumber = 0.into();
+        assert_eq!(expected, Vec3d::default().length());
+    }
+
+    #[test]
+    fn Vec3d_length_does_not_overflow_for_large_vectors() {
+        let expected: FixedNumber = 5000.into();
+
+        assert_eq!(expected, vec(3000, 4000, 0).length());
+
+        let half = FixedNumber::from_bits(1 << 30);
+        assert_eq!(
+            FixedNumber::from_bits(1859775393),
+            Vec3d::new(half, -half, half).length()
+        );
+
+        let max = FixedNumber::from_bits(i32::MAX);
+        let min = FixedNumber::from_bits(i32::MIN);
+        assert_eq!(max, Vec3d::new(max, max, max).length());
+        assert_eq!(max, Vec3d::new(min, min, min).length());
+    }
+
+    #[test]
+    fn Vec3d_normalize() {
+        assert_eq!(vec(0, 0, 1), vec(0, 0, 25).normalize());
+        assert_eq!(vec(-1, 0, 0), vec(-7, 0, 0).normalize());
+
+        let normalized = vec(3, 4, 0).normalize();
+        let expected = Vec3d::new(
+            FixedNumber::from_bits(2457),
+            FixedNumber::from_bits(3276),
+            0.into(),
+        );
+
+        assert_eq!(expected, normalized);
+    }
+
+    #[test]
+    fn Vec3d_normalize_zero() {
+        assert_eq!(Vec3d::default(), Vec3d::default().normalize());
+    }
+
+    #[test]
+    fn Vec3d_distance() {
+        let expected: FixedNumber = 5.into();
+
+        assert_eq!(expected, vec(1, 1, 1).distance(vec(4, 5, 1)));
+        assert_eq!(expected, vec(4, 5, 1).distance(vec(1, 1, 1)));
+
+        let expected: FixedNumber = 25.into();
+
+        assert_eq!(expected, vec(1, 1, 1).distance_squared(vec(4, 5, 1)));
+    }
+
+    #[test]
+    fn Vec3d_lerp() {
+        let a = vec(0, 10, -10);
+        let b = vec(10, 20, 10);
+
+        assert_eq!(a, a.lerp(b, 0.into()));
+        assert_eq!(b, a.lerp(b, 1.into()));
+        assert_eq!(vec(5, 15, 0), a.lerp(b, FixedNumber::from_bits(2048)));
+    }
+
+    #[test]
+    fn Vec3d_clamp() {
+        let min = vec(-1, -1, -1);
+        let max = vec(1, 1, 1);
+
+        assert_eq!(vec(1, -1, 0), vec(5, -5, 0).clamp(min, max));
+    }
+
+    #[test]
+    fn Vec3d_project() {
+        assert_eq!(vec(3, 0, 0), vec(3, 4, 5).project(vec(2, 0, 0)));
+        assert_eq!(vec(0, 4, 0), vec(3, 4, 5).project(vec(0, -1, 0)));
+        assert_eq!(Vec3d::default(), vec(3, 4, 5).project(Vec3d::default()));
+    }
+
+    #[test]
+    fn Vec3d_reflect() {
+        assert_eq!(vec(1, 1, 0), vec(1, -1, 0).reflect(vec(0, 1, 0)));
+        assert_eq!(vec(-2, 3, 4), vec(2, 3, 4).reflect(vec(-1, 0, 0)));
+    }
+}
@@ -1,6 +1,7 @@
 mod aabb;
 pub mod colors;
 pub mod math;
+pub mod serialization;
 pub mod voxels;
 
 pub use aabb::Aabb;
@@ -10,7 +11,7 @@ pub use direction::Direction;
 
 pub type PlayerId = u8;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 pub enum InputType {
     Pressed(PlayerId, EngineInputs),
     Held(PlayerId, EngineInputs),
@@ -19,7 +20,7 @@ pub enum InputType {
     CursorNormalized(PlayerId, math::Vec3d),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
 pub enum EngineInputs {
     MoveForward,
     MoveBack,
@@ -0,0 +1,506 @@
+use super::{
+    colors::Color,
+    math::{FixedNumber, Meter, Range, Rng, Rotation3d, Vec3d},
+    voxels::{voxel::Voxel, voxel_chunk::VoxelChunk},
+    Aabb, Direction, EngineInputs, InputType,
+};
+
+/// Types that can be written to and read from the binary save format.
+/// Integers are little endian, so saves are portable between platforms.
+pub trait Serializable: Sized {
+    fn serialize(&self, writer: &mut SaveWriter);
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String>;
+}
+
+/// Buffer that values are serialized into
+pub struct SaveWriter {
+    bytes: Vec<u8>,
+}
+
+impl SaveWriter {
+    pub fn new() -> Self {
+        return Self { bytes: vec![] };
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn write<T: Serializable>(&mut self, value: &T) {
+        value.serialize(self);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Cursor over serialized bytes. Tracks the version of the save being read, so types can handle older layouts.
+pub struct SaveReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    version: u16,
+}
+
+impl<'a> SaveReader<'a> {
+    pub fn new(bytes: &'a [u8], version: u16) -> Self {
+        return Self {
+            bytes: bytes,
+            position: 0,
+            version: version,
+        };
+    }
+
+    /// The version of the save being read
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: u16) {
+        self.version = version;
+    }
+
+    /// The number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.remaining() {
+            return Err(format!(
+                "Unexpected end of save data at byte {}, wanted {} more",
+                self.position, len
+            ));
+        }
+
+        let bytes = &self.bytes[self.position..self.position + len];
+        self.position += len;
+
+        Ok(bytes)
+    }
+
+    pub fn read<T: Serializable>(&mut self) -> Result<T, String> {
+        T::deserialize(self)
+    }
+}
+
+macro_rules! serializable_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Serializable for $ty {
+                fn serialize(&self, writer: &mut SaveWriter) {
+                    writer.write_bytes(&self.to_le_bytes());
+                }
+
+                fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+                    let mut bytes = [0; std::mem::size_of::<$ty>()];
+                    bytes.copy_from_slice(reader.read_bytes(std::mem::size_of::<$ty>())?);
+
+                    Ok(<$ty>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+serializable_int!(u8, u16, u32, u64, i32, i64);
+
+impl Serializable for usize {
+    /// Always written as a u64 so saves don't depend on the platform
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&(*self as u64));
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let value: u64 = reader.read()?;
+
+        if value > std::usize::MAX as u64 {
+            return Err(format!("{} is too large for this platform", value));
+        }
+
+        Ok(value as usize)
+    }
+}
+
+impl Serializable for bool {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&(*self as u8));
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(format!("Invalid bool {}", value)),
+        }
+    }
+}
+
+impl<T: Serializable> Serializable for Option<T> {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        match self {
+            Some(value) => {
+                writer.write(&true);
+                writer.write(value);
+            }
+            None => writer.write(&false),
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        if reader.read::<bool>()? {
+            return Ok(Some(reader.read()?));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<T: Serializable> Serializable for Vec<T> {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.len());
+
+        for value in self {
+            writer.write(value);
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let len: usize = reader.read()?;
+
+        // Don't trust the length for the allocation, in case the data is corrupt
+        let mut values = Vec::with_capacity(len.min(reader.remaining()));
+        for _ in 0..len {
+            values.push(reader.read()?);
+        }
+
+        Ok(values)
+    }
+}
+
+impl Serializable for FixedNumber {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.to_bits());
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        Ok(Self::from_bits(reader.read()?))
+    }
+}
+
+impl Serializable for Vec3d {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.x);
+        writer.write(&self.y);
+        writer.write(&self.z);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        Ok(Self::new(reader.read()?, reader.read()?, reader.read()?))
+    }
+}
+
+impl Serializable for Rotation3d {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.pitch_radians);
+        writer.write(&self.yaw_radians);
+        writer.write(&self.roll_radians);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            pitch_radians: reader.read()?,
+            yaw_radians: reader.read()?,
+            roll_radians: reader.read()?,
+        });
+    }
+}
+
+impl Serializable for Range {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.value);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            value: reader.read()?,
+        });
+    }
+}
+
+impl Serializable for Meter {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.value);
+        writer.write(&self.max_value);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            value: reader.read()?,
+            max_value: reader.read()?,
+        });
+    }
+}
+
+impl Serializable for Rng {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        let (state, increment) = self.state();
+
+        writer.write(&state);
+        writer.write(&increment);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        Ok(Self::from_state((reader.read()?, reader.read()?)))
+    }
+}
+
+impl Serializable for Aabb {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.min);
+        writer.write(&self.max);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            min: reader.read()?,
+            max: reader.read()?,
+        });
+    }
+}
+
+impl Serializable for Direction {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&(self.index() as u8));
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let index: u8 = reader.read()?;
+
+        if index as usize >= Self::size {
+            return Err(format!("Invalid direction {}", index));
+        }
+
+        Ok(Self::from_index(index as usize))
+    }
+}
+
+impl Serializable for EngineInputs {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        let tag: u8 = match self {
+            EngineInputs::MoveForward => 0,
+            EngineInputs::MoveBack => 1,
+            EngineInputs::MoveLeft => 2,
+            EngineInputs::MoveRight => 3,
+            EngineInputs::HorizontalAttack => 4,
+            EngineInputs::VerticalAttack => 5,
+            EngineInputs::Jump => 6,
+            EngineInputs::Crouch => 7,
+            EngineInputs::Dodge => 8,
+        };
+
+        writer.write(&tag);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(EngineInputs::MoveForward),
+            1 => Ok(EngineInputs::MoveBack),
+            2 => Ok(EngineInputs::MoveLeft),
+            3 => Ok(EngineInputs::MoveRight),
+            4 => Ok(EngineInputs::HorizontalAttack),
+            5 => Ok(EngineInputs::VerticalAttack),
+            6 => Ok(EngineInputs::Jump),
+            7 => Ok(EngineInputs::Crouch),
+            8 => Ok(EngineInputs::Dodge),
+            tag => Err(format!("Invalid engine input {}", tag)),
+        }
+    }
+}
+
+impl Serializable for InputType {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        match self {
+            InputType::Pressed(player, input) => {
+                writer.write(&0u8);
+                writer.write(player);
+                writer.write(input);
+            }
+            InputType::Held(player, input) => {
+                writer.write(&1u8);
+                writer.write(player);
+                writer.write(input);
+            }
+            InputType::Released(player, input) => {
+                writer.write(&2u8);
+                writer.write(player);
+                writer.write(input);
+            }
+            InputType::CursorNormalized(player, cursor) => {
+                writer.write(&3u8);
+                writer.write(player);
+                writer.write(cursor);
+            }
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(InputType::Pressed(reader.read()?, reader.read()?)),
+            1 => Ok(InputType::Held(reader.read()?, reader.read()?)),
+            2 => Ok(InputType::Released(reader.read()?, reader.read()?)),
+            3 => Ok(InputType::CursorNormalized(reader.read()?, reader.read()?)),
+            tag => Err(format!("Invalid input type {}", tag)),
+        }
+    }
+}
+
+impl Serializable for Color {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.r);
+        writer.write(&self.g);
+        writer.write(&self.b);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            r: reader.read()?,
+            g: reader.read()?,
+            b: reader.read()?,
+        });
+    }
+}
+
+impl Serializable for Voxel {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.palatte_index);
+        writer.write(&self.material_type);
+        writer.write(&self.active);
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        return Ok(Self {
+            palatte_index: reader.read()?,
+            material_type: reader.read()?,
+            active: reader.read()?,
+        });
+    }
+}
+
+impl Serializable for VoxelChunk {
+    fn serialize(&self, writer: &mut SaveWriter) {
+        writer.write(&self.palatte);
+
+        for plane in self.voxels.iter() {
+            for row in plane.iter() {
+                for voxel in row.iter() {
+                    writer.write(voxel);
+                }
+            }
+        }
+    }
+
+    fn deserialize(reader: &mut SaveReader) -> Result<Self, String> {
+        let mut chunk = Self::new();
+        chunk.palatte = reader.read()?;
+
+        for plane in chunk.voxels.iter_mut() {
+            for row in plane.iter_mut() {
+                for voxel in row.iter_mut() {
+                    *voxel = reader.read()?;
+                }
+            }
+        }
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serializable>(value: &T) -> T {
+        let mut writer = SaveWriter::new();
+        writer.write(value);
+
+        let bytes = writer.into_bytes();
+        let mut reader = SaveReader::new(&bytes, 1);
+        let result = reader.read().unwrap();
+
+        assert_eq!(0, reader.remaining());
+
+        result
+    }
+
+    #[test]
+    fn Serializable_ints_are_little_endian() {
+        let mut writer = SaveWriter::new();
+        writer.write(&0x0102u16);
+        writer.write(&-2i32);
+        writer.write(&3usize);
+
+        assert_eq!(
+            vec![2, 1, 254, 255, 255, 255, 3, 0, 0, 0, 0, 0, 0, 0],
+            writer.into_bytes()
+        );
+    }
+
+    #[test]
+    fn Serializable_round_trip() {
+        assert_eq!(u64::MAX, round_trip(&u64::MAX));
+        assert_eq!(i64::MIN, round_trip(&i64::MIN));
+        assert_eq!(true, round_trip(&true));
+        assert_eq!(Some(5u8), round_trip(&Some(5u8)));
+        assert_eq!(None, round_trip(&None::<u8>));
+        assert_eq!(vec![1u32, 2, 3], round_trip(&vec![1u32, 2, 3]));
+
+        let vec3d = Vec3d::new(FixedNumber::from_bits(-1), 2.into(), 3.into());
+        assert_eq!(vec3d, round_trip(&vec3d));
+
+        let mut rng = Rng::new(12);
+        rng.next_u32();
+        assert_eq!(rng, round_trip(&rng));
+
+        let mut meter = Meter::new(u32::MAX);
+        meter.value = 7;
+        assert_eq!(meter, round_trip(&meter));
+
+        assert_eq!(Direction::SouthWest, round_trip(&Direction::SouthWest));
+
+        let input = InputType::CursorNormalized(2, vec3d);
+        assert_eq!(input, round_trip(&input));
+
+        let input = InputType::Released(1, EngineInputs::Dodge);
+        assert_eq!(input, round_trip(&input));
+    }
+
+    #[test]
+    fn Serializable_voxel_chunk_round_trip() {
+        let mut chunk = VoxelChunk::new();
+        chunk.voxels[1][2][3].active = false;
+        chunk.voxels[7][0][4].palatte_index = 9;
+
+        let result = round_trip(&chunk);
+
+        assert_eq!(false, result.voxels[1][2][3].active);
+        assert_eq!(9, result.voxels[7][0][4].palatte_index);
+        assert_eq!(true, result.voxels[0][0][0].active);
+    }
+
+    #[test]
+    fn SaveReader_errors_on_bad_data() {
+        let mut reader = SaveReader::new(&[1, 2], 1);
+        assert!(reader.read::<u32>().is_err());
+
+        let mut reader = SaveReader::new(&[2], 1);
+        assert!(reader.read::<bool>().is_err());
+
+        let mut reader = SaveReader::new(&[9], 1);
+        assert!(reader.read::<Direction>().is_err());
+
+        // Claims far more elements than there is data for
+        let mut reader = SaveReader::new(&[255, 255, 255, 255, 0, 0, 0, 0, 1], 1);
+        assert!(reader.read::<Vec<u8>>().is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use super::{materials::*, *};
 use crate::lib_core::colors::*;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Hash)]
 pub struct Voxel {
     pub palatte_index: PalatteIndexType,
     pub material_type: VoxelMaterialType,
@@ -1,7 +1,7 @@
 use super::voxel::Voxel;
 use crate::lib_core::colors::*;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Hash)]
 pub struct VoxelChunk {
     pub palatte: Palatte,
     pub voxels: [[[Voxel; Self::BASE_ARRAY_SIZE]; Self::BASE_ARRAY_SIZE]; Self::BASE_ARRAY_SIZE],
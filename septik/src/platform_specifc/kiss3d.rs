@@ -145,16 +145,10 @@ impl WindowGfx for Kiss3dBackend {
 }
 
 fn rotation_into_quaternion(rotation: Rotation3d) -> cgmath::Quaternion<f32> {
-    let yaw = cgmath::Rad::<f32>(rotation.yaw_radians.into());
-    let pitch = cgmath::Rad::<f32>(rotation.pitch_radians.into());
-    let roll = cgmath::Rad::<f32>(rotation.roll_radians.into());
+    // Use the simulation's rotation convention so the renderer always matches it
+    let q = rotation.to_quaternion();
 
-    let yaw = cgmath::Quaternion::<f32>::from_angle_y(yaw);
-    let pitch = cgmath::Quaternion::<f32>::from_angle_x(pitch);
-    let roll = cgmath::Quaternion::<f32>::from_angle_z(roll);
-
-    // Note: order is important!
-    return (yaw * pitch * roll);
+    return cgmath::Quaternion::<f32>::new(q.w.into(), q.x.into(), q.y.into(), q.z.into());
 }
 
 fn update_camera(backend: &mut Kiss3dBackend, world: &World, e: Entity) {
@@ -204,7 +198,7 @@ fn draw_meshes(backend: &mut Kiss3dBackend, world: &World, e: Entity) {
             }
 
             let mut three_mesh_data = three_mesh_data.unwrap();
-            let transform = world.transforms[e].as_ref();
+            let transform = world.world_transforms[e].as_ref();
             if transform.is_some() {
                 let transform = transform.unwrap();
                 let pos = transform.position;
@@ -225,7 +219,7 @@ fn draw_meshes(backend: &mut Kiss3dBackend, world: &World, e: Entity) {
                 }
             };
 
-            let transform = world.transforms[e].as_ref();
+            let transform = world.world_transforms[e].as_ref();
             let group = backend.window.factory.group();
 
             for g in group_map.values_mut() {